@@ -27,7 +27,9 @@ use amplify::hex::{FromHex, ToHex};
 use amplify::{ByteArray, Bytes32StrRev, Wrapper};
 use commit_verify::{DigestExt, Sha256};
 
-use crate::{BlockDataParseError, ConsensusDecode, ConsensusEncode, LIB_NAME_BITCOIN};
+use crate::{
+    BlockDataParseError, ConsensusDecode, ConsensusEncode, Tx, VarIntArray, LIB_NAME_BITCOIN,
+};
 
 #[derive(Wrapper, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, From)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -56,6 +58,7 @@ pub struct BlockMerkleRoot(
 #[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct BlockHeader {
     /// Block version, now repurposed for soft fork signalling.
     pub version: i32,
@@ -96,6 +99,22 @@ impl BlockHeader {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode, StrictDumb)]
+#[strict_type(lib = LIB_NAME_BITCOIN)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct Block {
+    /// Header of the block.
+    pub header: BlockHeader,
+    /// Transactions of the block, starting with the coinbase transaction.
+    pub transactions: VarIntArray<Tx>,
+}
+
+impl Block {
+    #[inline]
+    pub fn block_hash(&self) -> BlockHash { self.header.block_hash() }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
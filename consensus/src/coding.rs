@@ -25,10 +25,11 @@ use amplify::confinement::{Confined, MediumBlob, SmallBlob, TinyBlob, U32};
 use amplify::{confinement, ByteArray, Bytes32, IoError, Wrapper};
 
 use crate::{
-    Annex, BlockHash, BlockHeader, BlockMerkleRoot, ControlBlock, InternalPk, InvalidLeafVer,
-    LeafVer, LockTime, Outpoint, Parity, RedeemScript, Sats, ScriptBytes, ScriptPubkey, SeqNo,
-    SigScript, Sighash, TapBranchHash, TapLeafHash, TapMerklePath, TapScript, Tx, TxIn, TxOut,
-    TxVer, Txid, Vout, Witness, WitnessScript, LIB_NAME_BITCOIN, TAPROOT_ANNEX_PREFIX,
+    Annex, Block, BlockHash, BlockHeader, BlockMerkleRoot, ControlBlock, InternalPk,
+    InvalidLeafVer, LeafVer, LockTime, Outpoint, Parity, RedeemScript, Sats, ScriptBytes,
+    ScriptPubkey, SeqNo, SigScript, Sighash, TapBranchHash, TapLeafHash, TapMerklePath, TapScript,
+    Tx, TxIn, TxOut, TxVer, Txid, Vout, Witness, WitnessScript, LIB_NAME_BITCOIN,
+    TAPROOT_ANNEX_PREFIX,
 };
 
 /// Bitcoin consensus allows arrays which length is encoded as VarInt to grow up
@@ -257,6 +258,25 @@ impl ConsensusDecode for BlockHeader {
     }
 }
 
+impl ConsensusEncode for Block {
+    fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
+        let mut counter = self.header.consensus_encode(writer)?;
+        counter += self.transactions.consensus_encode(writer)?;
+        Ok(counter)
+    }
+}
+
+impl ConsensusDecode for Block {
+    fn consensus_decode(reader: &mut impl Read) -> Result<Self, ConsensusDecodeError> {
+        let header = BlockHeader::consensus_decode(reader)?;
+        let transactions = VarIntArray::consensus_decode(reader)?;
+        Ok(Block {
+            header,
+            transactions,
+        })
+    }
+}
+
 impl ConsensusEncode for BlockHash {
     fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
         writer.write_all(&self.to_byte_array())?;
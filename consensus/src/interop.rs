@@ -0,0 +1,109 @@
+// Bitcoin protocol consensus library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions between the data types of this crate and their equivalents from
+//! `rust-bitcoin` library.
+
+use amplify::{ByteArray, Wrapper};
+use bitcoin::consensus::encode;
+use bitcoin::hashes::Hash;
+
+use crate::{
+    BlockHash, ConsensusDecode, ConsensusDecodeError, ConsensusEncode, Outpoint, ScriptPubkey, Tx,
+    Txid, Vout,
+};
+
+impl From<Txid> for bitcoin::Txid {
+    fn from(txid: Txid) -> Self { bitcoin::Txid::from_byte_array(txid.to_byte_array()) }
+}
+
+impl From<bitcoin::Txid> for Txid {
+    fn from(txid: bitcoin::Txid) -> Self { Txid::from(txid.to_byte_array()) }
+}
+
+impl From<BlockHash> for bitcoin::BlockHash {
+    fn from(hash: BlockHash) -> Self {
+        bitcoin::BlockHash::from_byte_array(hash.into_inner().to_byte_array())
+    }
+}
+
+impl From<bitcoin::BlockHash> for BlockHash {
+    fn from(hash: bitcoin::BlockHash) -> Self { BlockHash::from(hash.to_byte_array()) }
+}
+
+impl From<Outpoint> for bitcoin::OutPoint {
+    fn from(outpoint: Outpoint) -> Self {
+        bitcoin::OutPoint::new(outpoint.txid.into(), outpoint.vout.into_u32())
+    }
+}
+
+impl From<bitcoin::OutPoint> for Outpoint {
+    fn from(outpoint: bitcoin::OutPoint) -> Self {
+        Outpoint::new(outpoint.txid.into(), Vout::from_u32(outpoint.vout))
+    }
+}
+
+impl From<ScriptPubkey> for bitcoin::ScriptBuf {
+    fn from(script: ScriptPubkey) -> Self {
+        bitcoin::ScriptBuf::from_bytes(script.into_inner().into_vec())
+    }
+}
+
+impl From<bitcoin::ScriptBuf> for ScriptPubkey {
+    fn from(script: bitcoin::ScriptBuf) -> Self { ScriptPubkey::from_unsafe(script.into_bytes()) }
+}
+
+/// Conversion goes through the consensus serialization, which may fail for
+/// transactions without inputs, since `rust-bitcoin` misinterprets them as
+/// segwit transactions.
+impl TryFrom<&Tx> for bitcoin::Transaction {
+    type Error = encode::Error;
+
+    fn try_from(tx: &Tx) -> Result<Self, Self::Error> {
+        encode::deserialize(&tx.consensus_serialize())
+    }
+}
+
+impl TryFrom<&bitcoin::Transaction> for Tx {
+    type Error = ConsensusDecodeError;
+
+    fn try_from(tx: &bitcoin::Transaction) -> Result<Self, Self::Error> {
+        Tx::consensus_deserialize(encode::serialize(tx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn outpoint() {
+        let outpoint = Outpoint::from_str(
+            "961d5d1e9a4b5e4c5cb4cc6e2f8d9ed5a4e5a4f0b4d6a1e5c4d3b2a190807060:3",
+        )
+        .unwrap();
+        let converted = bitcoin::OutPoint::from(outpoint);
+        assert_eq!(converted.to_string(), outpoint.to_string());
+        assert_eq!(Outpoint::from(converted), outpoint);
+    }
+}
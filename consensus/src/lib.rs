@@ -64,10 +64,14 @@ mod util;
 mod weights;
 #[cfg(feature = "stl")]
 pub mod stl;
+#[cfg(feature = "schemars")]
+mod schema;
+#[cfg(feature = "rust-bitcoin")]
+mod interop;
 mod coding;
 mod sigcache;
 
-pub use block::{BlockHash, BlockHeader, BlockMerkleRoot};
+pub use block::{Block, BlockHash, BlockHeader, BlockMerkleRoot};
 pub use coding::{
     ByteStr, ConsensusDataError, ConsensusDecode, ConsensusDecodeError, ConsensusEncode, LenVarInt,
     VarInt, VarIntArray, VarIntBytes,
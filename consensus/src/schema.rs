@@ -0,0 +1,59 @@
+// Bitcoin protocol consensus library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON schemas for the data types which are serialized as strings in
+//! human-readable serde formats.
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
+
+use crate::{
+    BlockHash, BlockMerkleRoot, InternalPk, Outpoint, ScriptBytes, TapLeafHash, TapNodeHash, Txid,
+};
+
+macro_rules! impl_string_schema {
+    ($($ty:ident => $descr:literal),* $(,)?) => {$(
+        impl JsonSchema for $ty {
+            fn schema_name() -> String { s!(stringify!($ty)) }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                let mut schema = SchemaObject {
+                    instance_type: Some(InstanceType::String.into()),
+                    ..default!()
+                };
+                schema.metadata().description = Some(s!($descr));
+                schema.into()
+            }
+        }
+    )*};
+}
+
+impl_string_schema! {
+    Txid => "Transaction id in hexadecimal representation",
+    BlockHash => "Block hash in hexadecimal representation",
+    BlockMerkleRoot => "Merkle root of block transactions in hexadecimal representation",
+    Outpoint => "Transaction output in `<txid>:<vout>` representation",
+    InternalPk => "Taproot internal x-only public key in hexadecimal representation",
+    TapLeafHash => "Taproot leaf hash in hexadecimal representation",
+    TapNodeHash => "Taproot script tree node hash in hexadecimal representation",
+    ScriptBytes => "Script bytes in hexadecimal representation",
+}
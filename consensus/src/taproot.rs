@@ -427,6 +427,7 @@ pub struct InvalidLeafVer(u8);
 /// The leaf version for tapleafs.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum LeafVer {
     /// BIP-342 tapscript.
     #[default]
@@ -511,6 +512,7 @@ impl UpperHex for LeafVer {
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN, dumb = { Self(0x51) })]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FutureLeafVer(u8);
 
 impl FutureLeafVer {
@@ -544,6 +546,7 @@ impl UpperHex for FutureLeafVer {
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[display("{version:04x} {script:x}")]
 pub struct LeafScript {
     pub version: LeafVer,
@@ -63,6 +63,7 @@ impl Txid {
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BITCOIN)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[display(inner)]
 // 0xFFFFFFFF used in coinbase
 pub struct Vout(u32);
@@ -0,0 +1,80 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the commitment hashing paths. Run with and without
+//! `sha2-asm` feature to compare the portable and accelerated SHA256
+//! implementations:
+//!
+//! ```console
+//! $ cargo bench -p bp-dbc --bench hashing
+//! $ cargo bench -p bp-dbc --bench hashing --features sha2-asm
+//! ```
+
+use std::str::FromStr;
+
+use bc::{InternalPk, OpCode, ScriptPubkey, TapLeafHash, TapScript};
+use commit_verify::{mpc, CommitVerify, ConvolveCommit, DigestExt, EmbedCommitVerify, Sha256};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use dbc::tapret::{TapretCommitment, TapretPathProof};
+
+const INTERNAL_PK: &str = "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3";
+
+fn sha256(c: &mut Criterion) {
+    let data = [0x5Au8; 1024];
+    let mut group = c.benchmark_group("sha256");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("tagged_1k", |b| {
+        b.iter(|| {
+            let mut engine = Sha256::from_tag("urn:lnp-bp:dbc:bench");
+            engine.input_raw(black_box(&data));
+            engine.finish()
+        })
+    });
+    group.finish();
+}
+
+fn commitments(c: &mut Criterion) {
+    let msg = mpc::Commitment::from([0xA5; 32]);
+    let internal_pk = InternalPk::from_str(INTERNAL_PK).unwrap();
+    let path_proof = TapretPathProof::root(0);
+
+    let mut group = c.benchmark_group("commitment");
+    group.bench_function("opret", |b| {
+        b.iter(|| {
+            let mut script = ScriptPubkey::from_unsafe(vec![OpCode::Return as u8]);
+            script.embed_commit(black_box(&msg)).unwrap();
+            script
+        })
+    });
+    group.bench_function("tapret_leaf", |b| {
+        b.iter(|| {
+            let script = TapScript::commit(&TapretCommitment::with(*black_box(&msg), 0));
+            TapLeafHash::with_tap_script(&script)
+        })
+    });
+    group.bench_function("tapret_key", |b| {
+        b.iter(|| internal_pk.convolve_commit(&path_proof, black_box(&msg)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sha256, commitments);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the tapret commitment script construction, comparing the
+//! fixed-size buffer used by [`TapScript::commit`] with the script builder.
+
+use bc::{TapCode, TapScript};
+use commit_verify::{mpc, CommitVerify};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dbc::tapret::TapretCommitment;
+use strict_encoding::StrictSerialize;
+
+fn builder(commitment: &TapretCommitment) -> TapScript {
+    let mut tapret = TapScript::with_capacity(64);
+    for _ in 0..29 {
+        tapret.push_opcode(TapCode::Reserved);
+    }
+    tapret.push_opcode(TapCode::Return);
+    let data = commitment.to_strict_serialized::<33>().expect("exact size match");
+    tapret.push_slice(&data);
+    tapret
+}
+
+fn tapret_script(c: &mut Criterion) {
+    let commitment = TapretCommitment::with(mpc::Commitment::from([0xA5; 32]), 7);
+    assert_eq!(builder(&commitment), TapScript::commit(&commitment));
+
+    let mut group = c.benchmark_group("tapret_script");
+    group.bench_function("buffer", |b| b.iter(|| TapScript::commit(black_box(&commitment))));
+    group.bench_function("builder", |b| b.iter(|| builder(black_box(&commitment))));
+    group.finish();
+}
+
+criterion_group!(benches, tapret_script);
+criterion_main!(benches);
@@ -0,0 +1,375 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Anchors are data structures used in deterministic bitcoin commitments for
+//! keeping information about the proof of the commitment in connection to the
+//! transaction which contains the commitment, and multi-protocol merkle tree as
+//! defined by LNPBP-4.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::{ByteArray, Bytes32};
+use baid64::{Baid64ParseError, DisplayBaid64, FromBaid64Str};
+use bc::{Tx, Txid};
+use commit_verify::mpc::{self, Message, ProtocolId};
+use commit_verify::{CommitId, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize};
+
+use crate::{DbcError, DbcProof, Proof, VerifyError, VerifyOpts, LIB_NAME_BPCORE};
+
+/// Errors verifying anchors.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DbcAnchorError<E: Error> {
+    /// witness transaction {actual} doesn't match transaction {expected}
+    /// referenced by the anchor.
+    TxidMismatch {
+        /// Transaction id referenced by the anchor.
+        expected: Txid,
+        /// Id of the transaction provided for the verification.
+        actual: Txid,
+    },
+
+    /// invalid multi-protocol commitment proof. Details: {0}
+    #[from]
+    Mpc(mpc::InvalidProof),
+
+    /// invalid deterministic bitcoin commitment. Details: {0}
+    Dbc(E),
+}
+
+/// Errors merging anchors.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DbcAnchorMergeError {
+    /// anchors can't be merged since they reference different witness
+    /// transactions {0} and {1}.
+    TxidMismatch(Txid, Txid),
+
+    /// anchors can't be merged since they have different DBC proofs.
+    DbcMismatch,
+
+    /// anchors can't be merged since they have different multi-protocol
+    /// commitments. Details: {0}
+    #[from]
+    MpcMismatch(mpc::MergeError),
+}
+
+/// Identifier of an anchor, computed as a tagged hash of its canonical strict
+/// encoding.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AnchorId(
+    #[from]
+    #[from([u8; 32])]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    Bytes32,
+);
+
+impl CommitmentId for AnchorId {
+    const TAG: &'static str = "urn:lnp-bp:dbc:anchor#2024-12-04";
+}
+
+impl From<Sha256> for AnchorId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+impl DisplayBaid64 for AnchorId {
+    const HRI: &'static str = "anchor";
+    const CHUNKING: bool = true;
+    const PREFIX: bool = true;
+    const EMBED_CHECKSUM: bool = false;
+    const MNEMONIC: bool = false;
+    fn to_baid64_payload(&self) -> [u8; 32] { self.to_byte_array() }
+}
+
+impl FromBaid64Str for AnchorId {}
+
+impl Display for AnchorId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.fmt_baid64(f) }
+}
+
+impl FromStr for AnchorId {
+    type Err = Baid64ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_baid64_str(s) }
+}
+
+/// Anchor is a data structure used in deterministic bitcoin commitments for
+/// keeping information about the proof of the commitment in connection to the
+/// transaction which contains the commitment, and multi-protocol merkle tree as
+/// defined by LNPBP-4.
+///
+/// Anchor may contain either a merkle proof for a single protocol
+/// ([`mpc::MerkleProof`], which is the default) or a partially revealed
+/// merkle tree covering several protocols ([`mpc::MerkleBlock`]).
+///
+/// The DBC proof may be either a proof for a specific method (implementing
+/// [`Proof`]) or a [`DbcProof`], which may contain a proof for any method.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = AnchorId)]
+pub struct DbcAnchor<
+    D: StrictDumb + StrictEncode + StrictDecode,
+    L: mpc::Proof + StrictDumb = mpc::MerkleProof,
+> {
+    /// Transaction containing deterministic bitcoin commitment.
+    pub txid: Txid,
+
+    /// Structured multi-protocol LNPBP-4 data the transaction commits to.
+    pub mpc_proof: L,
+
+    /// Proof of the DBC commitment.
+    pub dbc_proof: D,
+}
+
+impl<D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb> StrictSerialize
+    for DbcAnchor<D, L>
+{
+}
+impl<D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb> StrictDeserialize
+    for DbcAnchor<D, L>
+{
+}
+
+#[cfg(feature = "serde")]
+mod _serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    impl<D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb> Serialize
+        for DbcAnchor<D, L>
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            crate::strict_serde::serialize(self, serializer)
+        }
+    }
+
+    impl<'de, D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb>
+        Deserialize<'de> for DbcAnchor<D, L>
+    {
+        fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+            crate::strict_serde::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb> schemars::JsonSchema
+    for DbcAnchor<D, L>
+{
+    fn schema_name() -> String { s!("DbcAnchor") }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        <String as schemars::JsonSchema>::json_schema(gen)
+    }
+}
+
+impl<D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb> DbcAnchor<D, L> {
+    /// Returns identifier of the anchor.
+    #[inline]
+    pub fn anchor_id(&self) -> AnchorId { self.commit_id() }
+}
+
+impl<D: Clone + Eq + StrictDumb + StrictEncode + StrictDecode> DbcAnchor<D> {
+    /// Constructs anchor for the given witness transaction id.
+    pub fn new(txid: Txid, mpc_proof: mpc::MerkleProof, dbc_proof: D) -> Self {
+        DbcAnchor {
+            txid,
+            mpc_proof,
+            dbc_proof,
+        }
+    }
+
+    /// Reconstructs multi-protocol commitment from the proof, the protocol id
+    /// and the message.
+    #[inline]
+    pub fn convolve(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+    ) -> Result<mpc::Commitment, mpc::InvalidProof> {
+        self.mpc_proof.convolve(protocol_id, message)
+    }
+
+    fn check_txid<E: Error>(&self, tx: &Tx) -> Result<(), DbcAnchorError<E>> {
+        let txid = tx.txid();
+        if txid != self.txid {
+            return Err(DbcAnchorError::TxidMismatch {
+                expected: self.txid,
+                actual: txid,
+            });
+        }
+        Ok(())
+    }
+
+    /// Converts anchor into an anchor containing partially revealed merkle
+    /// tree, which can be merged with other anchors for the same witness
+    /// transaction.
+    pub fn to_merkle_block(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+    ) -> Result<DbcAnchor<D, mpc::MerkleBlock>, mpc::InvalidProof> {
+        let mpc_proof = mpc::MerkleBlock::with(&self.mpc_proof, protocol_id, message)?;
+        Ok(DbcAnchor {
+            txid: self.txid,
+            mpc_proof,
+            dbc_proof: self.dbc_proof.clone(),
+        })
+    }
+}
+
+impl<D: Clone + Eq + StrictDumb + StrictEncode + StrictDecode> DbcAnchor<D, mpc::MerkleBlock> {
+    /// Extracts anchor for a single protocol from the partially revealed
+    /// merkle tree.
+    pub fn to_merkle_proof(
+        &self,
+        protocol_id: ProtocolId,
+    ) -> Result<DbcAnchor<D>, mpc::LeafNotKnown> {
+        let mpc_proof = self.mpc_proof.to_merkle_proof(protocol_id)?;
+        Ok(DbcAnchor {
+            txid: self.txid,
+            mpc_proof,
+            dbc_proof: self.dbc_proof.clone(),
+        })
+    }
+
+    /// Merges two anchors referring to the same witness transaction and DBC
+    /// proof, but revealing different protocols, into a single anchor
+    /// containing the union of the revealed multi-protocol commitment leaves.
+    pub fn merge(mut self, other: Self) -> Result<Self, DbcAnchorMergeError> {
+        if self.txid != other.txid {
+            return Err(DbcAnchorMergeError::TxidMismatch(self.txid, other.txid));
+        }
+        if self.dbc_proof != other.dbc_proof {
+            return Err(DbcAnchorMergeError::DbcMismatch);
+        }
+        self.mpc_proof.merge_reveal(other.mpc_proof)?;
+        Ok(self)
+    }
+}
+
+impl<D: Proof> DbcAnchor<D> {
+    /// Verifies that the transaction commits to the message under the given
+    /// protocol, returning the multi-protocol commitment contained in the
+    /// transaction.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(txid = %self.txid, protocol = %protocol_id),
+        err(level = "debug")
+    ))]
+    pub fn verify(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+    ) -> Result<mpc::Commitment, DbcAnchorError<D::Error>> {
+        self.check_txid(tx)?;
+        let commitment = self.convolve(protocol_id, message)?;
+        self.dbc_proof.verify(&commitment, tx).map_err(DbcAnchorError::Dbc)?;
+        Ok(commitment)
+    }
+
+    /// Verifies that the transaction commits to the message under the given
+    /// protocol, applying verification policy defined by the options.
+    ///
+    /// Anchor is not marked with a network, thus if the options require one
+    /// the verification fails with [`crate::PolicyViolation::NoNetwork`]; anchors
+    /// must be wrapped into [`crate::NetworkBound`] for such checks.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(txid = %self.txid, protocol = %protocol_id),
+        err(level = "debug")
+    ))]
+    pub fn verify_with(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+        opts: &VerifyOpts,
+    ) -> Result<mpc::Commitment, DbcAnchorError<VerifyError<D::Error>>> {
+        opts.check_unmarked().map_err(|v| DbcAnchorError::Dbc(VerifyError::Policy(v)))?;
+        self.check_txid(tx)?;
+        let commitment = self.convolve(protocol_id, message)?;
+        self.dbc_proof.verify_with(&commitment, tx, opts).map_err(DbcAnchorError::Dbc)?;
+        Ok(commitment)
+    }
+}
+
+impl DbcAnchor<DbcProof> {
+    /// Verifies that the transaction commits to the message under the given
+    /// protocol, returning the multi-protocol commitment contained in the
+    /// transaction.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(txid = %self.txid, protocol = %protocol_id),
+        err(level = "debug")
+    ))]
+    pub fn verify(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+    ) -> Result<mpc::Commitment, DbcAnchorError<DbcError>> {
+        self.check_txid(tx)?;
+        let commitment = self.convolve(protocol_id, message)?;
+        self.dbc_proof.verify(&commitment, tx).map_err(DbcAnchorError::Dbc)?;
+        Ok(commitment)
+    }
+
+    /// Verifies that the transaction commits to the message under the given
+    /// protocol, applying verification policy defined by the options.
+    ///
+    /// Anchor is not marked with a network, thus if the options require one
+    /// the verification fails with [`crate::PolicyViolation::NoNetwork`]; anchors
+    /// must be wrapped into [`crate::NetworkBound`] for such checks.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(txid = %self.txid, protocol = %protocol_id),
+        err(level = "debug")
+    ))]
+    pub fn verify_with(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+        opts: &VerifyOpts,
+    ) -> Result<mpc::Commitment, DbcAnchorError<VerifyError<DbcError>>> {
+        opts.check_unmarked().map_err(|v| DbcAnchorError::Dbc(VerifyError::Policy(v)))?;
+        self.check_txid(tx)?;
+        let commitment = self.convolve(protocol_id, message)?;
+        self.dbc_proof.verify_with(&commitment, tx, opts).map_err(DbcAnchorError::Dbc)?;
+        Ok(commitment)
+    }
+}
@@ -0,0 +1,101 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ASCII armor for proofs and anchors, allowing to paste them into emails,
+//! issue trackers and QR codes.
+//!
+//! The armor is the one used by strict types for type libraries and other
+//! strict-serialized data: types implement [`StrictArmor`], and are armored
+//! and parsed with [`AsciiArmor::to_ascii_armored_string`] and
+//! [`AsciiArmor::from_ascii_armored_str`]. The `Id` header of the armor is an
+//! [`AnchorId`] for anchors and a [`strict_id`] of the serialized data for the
+//! other types; it is checked when the armor is parsed.
+
+use amplify::confinement::U32;
+pub use ascii_armor::{AsciiArmor, StrictArmor, StrictArmorError};
+use commit_verify::{mpc, CommitmentId, DigestExt, Sha256, StrictHash};
+use strict_encoding::{StrictDecode, StrictDumb, StrictEncode, StrictSerialize};
+
+use crate::opret::OpretProof;
+use crate::tapret::TapretProof;
+use crate::{AnchorId, DbcAnchor, DbcProof};
+
+/// Computes identifier of strict-serialized data, used in the armor headers of
+/// the types not having their own identifiers.
+pub fn strict_id(data: &impl StrictSerialize) -> StrictHash {
+    let data = data.to_strict_serialized::<U32>().expect("data exceed 4GB");
+    let mut engine = Sha256::from_tag(StrictHash::TAG);
+    engine.input_raw(data.as_slice());
+    StrictHash::from(engine)
+}
+
+impl StrictArmor for OpretProof {
+    type Id = StrictHash;
+    const PLATE_TITLE: &'static str = "DBC OPRET PROOF";
+
+    fn armor_id(&self) -> Self::Id { strict_id(self) }
+}
+
+impl StrictArmor for TapretProof {
+    type Id = StrictHash;
+    const PLATE_TITLE: &'static str = "DBC TAPRET PROOF";
+
+    fn armor_id(&self) -> Self::Id { strict_id(self) }
+}
+
+impl StrictArmor for DbcProof {
+    type Id = StrictHash;
+    const PLATE_TITLE: &'static str = "DBC PROOF";
+
+    fn armor_id(&self) -> Self::Id { strict_id(self) }
+}
+
+impl<D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb> StrictArmor
+    for DbcAnchor<D, L>
+{
+    type Id = AnchorId;
+    const PLATE_TITLE: &'static str = "DBC ANCHOR";
+
+    fn armor_id(&self) -> Self::Id { self.anchor_id() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::tapret_proof;
+
+    #[test]
+    fn roundtrip() {
+        let proof = tapret_proof(0);
+        let armored = proof.to_ascii_armored_string();
+        assert!(armored.starts_with("-----BEGIN DBC TAPRET PROOF-----\n"));
+        assert!(armored.contains(&format!("Id: {}", strict_id(&proof))));
+        assert_eq!(TapretProof::from_ascii_armored_str(&armored).unwrap(), proof);
+        assert!(OpretProof::from_ascii_armored_str(&armored).is_err());
+
+        let other = tapret_proof(1).to_ascii_armored_string();
+        let id = |armored: &str| {
+            armored.lines().find(|line| line.starts_with("Id: ")).unwrap().to_owned()
+        };
+        let forged = armored.replace(&id(&armored), &id(&other));
+        assert!(TapretProof::from_ascii_armored_str(&forged).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Batch verification of deterministic bitcoin commitment proofs.
+
+use std::collections::HashMap;
+
+use bc::{Tx, Txid};
+use commit_verify::mpc;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{DbcError, DbcProof};
+
+/// Failure of the batch verification.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("item #{pos} of the batch is invalid. Details: {err}")]
+pub struct BatchError {
+    /// Position of the first invalid item in the batch.
+    pub pos: usize,
+
+    /// Error verifying the item.
+    pub err: DbcError,
+}
+
+/// Results of the batch verification, one per item of the batch.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct BatchReport {
+    results: Vec<Result<(), DbcError>>,
+}
+
+impl BatchReport {
+    /// Returns number of items in the batch.
+    pub fn len(&self) -> usize { self.results.len() }
+
+    /// Detects whether the batch was empty.
+    pub fn is_empty(&self) -> bool { self.results.is_empty() }
+
+    /// Returns verification result of the item at the given position.
+    pub fn get(&self, pos: usize) -> Option<&Result<(), DbcError>> { self.results.get(pos) }
+
+    /// Returns verification results for all items, in the order of the batch.
+    pub fn results(&self) -> &[Result<(), DbcError>] { &self.results }
+
+    /// Detects whether all items of the batch are valid.
+    pub fn is_valid(&self) -> bool { self.results.iter().all(Result::is_ok) }
+
+    /// Returns number of valid items.
+    pub fn valid_count(&self) -> usize { self.results.iter().filter(|res| res.is_ok()).count() }
+
+    /// Returns positions and errors of all invalid items.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &DbcError)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, res)| res.as_ref().err().map(|err| (pos, err)))
+    }
+
+    /// Converts the report into the number of verified items or the error
+    /// for the invalid item with the smallest position in the batch.
+    pub fn into_result(self) -> Result<usize, BatchError> {
+        let total = self.results.len();
+        match self
+            .results
+            .into_iter()
+            .enumerate()
+            .find_map(|(pos, res)| res.err().map(|err| (pos, err)))
+        {
+            Some((pos, err)) => Err(BatchError { pos, err }),
+            None => Ok(total),
+        }
+    }
+}
+
+/// Verifies many DBC proofs at once, reporting result for each of them.
+///
+/// Items which are repeated in the batch (i.e. having the same commitment,
+/// witness transaction id and proof) are verified only once and share the
+/// result. If the crate is compiled with `rayon` feature, the verification
+/// runs in parallel.
+pub fn verify_batch<'a>(
+    items: impl IntoIterator<Item = (&'a mpc::Commitment, &'a Tx, &'a DbcProof)>,
+) -> BatchReport {
+    let mut seen = HashMap::<(mpc::Commitment, Txid, &DbcProof), usize>::new();
+    let mut unique = Vec::new();
+    let mut refs = Vec::new();
+    for (msg, tx, proof) in items {
+        let next = unique.len();
+        let index = *seen.entry((*msg, tx.txid(), proof)).or_insert(next);
+        if index == next {
+            unique.push((msg, tx, proof));
+        }
+        refs.push(index);
+    }
+
+    #[cfg(feature = "rayon")]
+    let results =
+        unique.into_par_iter().map(|(msg, tx, proof)| proof.verify(msg, tx)).collect::<Vec<_>>();
+    #[cfg(not(feature = "rayon"))]
+    let results =
+        unique.into_iter().map(|(msg, tx, proof)| proof.verify(msg, tx)).collect::<Vec<_>>();
+
+    BatchReport {
+        results: refs.into_iter().map(|index| results[index].clone()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::ScriptPubkey;
+
+    use super::*;
+    use crate::opret::{OpretProof, OpretVerifyError};
+    use crate::test_helpers::tx;
+
+    fn opret_tx(msg: &mpc::Commitment) -> Tx { tx([ScriptPubkey::op_return(msg.as_slice())]) }
+
+    #[test]
+    fn first_failure() {
+        let msg = mpc::Commitment::from([1u8; 32]);
+        let other = mpc::Commitment::from([2u8; 32]);
+        let tx = opret_tx(&msg);
+        let proof = DbcProof::Opret(OpretProof::default());
+
+        assert_eq!(verify_batch([(&msg, &tx, &proof), (&msg, &tx, &proof)]).into_result(), Ok(2));
+        assert_eq!(
+            verify_batch([(&msg, &tx, &proof), (&other, &tx, &proof), (&other, &tx, &proof)])
+                .into_result(),
+            Err(BatchError {
+                pos: 1,
+                err: DbcError::Opret(OpretVerifyError::PayloadMismatch {
+                    expected: other,
+                    actual: msg
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn mixed() {
+        let msg = mpc::Commitment::from([1u8; 32]);
+        let other = mpc::Commitment::from([2u8; 32]);
+        let tx = opret_tx(&msg);
+        let other_tx = opret_tx(&other);
+        let empty_tx = crate::test_helpers::tx([]);
+        let proof = DbcProof::Opret(OpretProof::default());
+
+        let report = verify_batch([
+            (&msg, &tx, &proof),
+            (&other, &tx, &proof),
+            (&other, &other_tx, &proof),
+            (&msg, &empty_tx, &proof),
+            (&other, &tx, &proof),
+        ]);
+        assert_eq!(report.len(), 5);
+        assert!(!report.is_valid());
+        assert_eq!(report.valid_count(), 2);
+        assert_eq!(report.get(0), Some(&Ok(())));
+        assert_eq!(report.get(2), Some(&Ok(())));
+        let mismatch = DbcError::Opret(OpretVerifyError::PayloadMismatch {
+            expected: other,
+            actual: msg,
+        });
+        assert_eq!(report.failures().collect::<Vec<_>>(), vec![
+            (1, &mismatch),
+            (3, &DbcError::Opret(OpretVerifyError::NoOpretOutput)),
+            (4, &mismatch),
+        ]);
+
+        let report = verify_batch(Vec::<(&mpc::Commitment, &Tx, &DbcProof)>::new());
+        assert!(report.is_empty());
+        assert!(report.is_valid());
+    }
+}
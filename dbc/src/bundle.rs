@@ -0,0 +1,408 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bundles of anchors storing proof components shared by several anchors only
+//! once.
+//!
+//! Besides the witness transaction ids and the proofs, which are shared as a
+//! whole, the bundles share tapret partner nodes: outputs of the same wallet
+//! usually have the same script tree, thus the tapret proofs of different
+//! witness transactions differ only in the internal key and nonce, while the
+//! partner node (which may contain the whole leaf script) is the same.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use amplify::confinement::{Confined, MediumVec, SmallVec};
+use bc::{InternalPk, Txid};
+use commit_verify::mpc::{self, MerkleBlock};
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+
+use crate::container::Containerized;
+use crate::opret::OpretProof;
+use crate::tapret::{TapretNodePartner, TapretPathProof, TapretProof};
+use crate::{DbcAnchor, DbcProof, LIB_NAME_BPCORE};
+
+/// Errors of anchor bundles.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AnchorBundleError {
+    /// anchor bundle can't contain more entries.
+    TooManyEntries,
+
+    /// anchor #{0} references a component absent in the bundle.
+    InvalidRef(usize),
+
+    /// tapret proof of anchor #{0} has partner node containing an alternative
+    /// commitment.
+    InvalidPartner(usize),
+}
+
+/// Reference to the components of a single anchor inside [`AnchorBundle`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct AnchorRef {
+    /// Index of the witness transaction id.
+    pub txid: u16,
+    /// Index of the multi-protocol commitment proof.
+    pub mpc_proof: u32,
+    /// Index of the DBC proof.
+    pub dbc_proof: u16,
+}
+
+/// Tapret proof inside [`AnchorBundle`], referencing its partner node.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct BundledTapret {
+    /// The internal key used by the taproot output.
+    pub internal_pk: InternalPk,
+    /// Nonce of the tapret commitment.
+    pub nonce: u8,
+    /// Index of the partner node, if the output has script spending paths.
+    pub partner: Option<u16>,
+}
+
+/// DBC proof inside [`AnchorBundle`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE, tags = custom, dumb = Self::Opret(strict_dumb!()))]
+pub enum BundledProof {
+    /// Opret commitment proof.
+    #[strict_type(tag = 0x00)]
+    Opret(OpretProof),
+
+    /// Tapret commitment proof.
+    #[strict_type(tag = 0x01)]
+    Tapret(BundledTapret),
+}
+
+/// Bundle of anchors storing each of the witness transaction ids,
+/// multi-protocol commitment proofs, DBC proofs and tapret partner nodes only
+/// once.
+///
+/// Anchors for several protocols committed in the same witness transaction
+/// share both the transaction id and the DBC proof; in batch transfers this
+/// duplication dominates the size of the data. Anchors of different witness
+/// transactions share the DBC proofs if they are equal (like opret proofs),
+/// and tapret proofs share their partner nodes. The sharing is preserved by
+/// the strict encoding of the bundle.
+///
+/// Bundles are constructed with [`AnchorBundleBuilder`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct AnchorBundle<L: mpc::Proof + StrictDumb = mpc::MerkleProof> {
+    /// Witness transaction ids.
+    pub txids: SmallVec<Txid>,
+    /// Distinct multi-protocol commitment proofs.
+    pub mpc_proofs: MediumVec<L>,
+    /// Distinct DBC proofs.
+    pub dbc_proofs: SmallVec<BundledProof>,
+    /// Distinct partner nodes of the tapret proofs.
+    pub partners: SmallVec<TapretNodePartner>,
+    /// Anchors in the bundle, referencing the components by their indexes.
+    pub anchors: MediumVec<AnchorRef>,
+}
+
+impl<L: mpc::Proof + StrictDumb> StrictSerialize for AnchorBundle<L> {}
+impl<L: mpc::Proof + StrictDumb> StrictDeserialize for AnchorBundle<L> {}
+
+impl Containerized for AnchorBundle {
+    const CONTAINER_TAG: u16 = 0x0150;
+}
+
+impl Containerized for AnchorBundle<MerkleBlock> {
+    const CONTAINER_TAG: u16 = 0x0151;
+}
+
+impl<L: mpc::Proof + StrictDumb> Default for AnchorBundle<L> {
+    fn default() -> Self {
+        AnchorBundle {
+            txids: empty!(),
+            mpc_proofs: empty!(),
+            dbc_proofs: empty!(),
+            partners: empty!(),
+            anchors: empty!(),
+        }
+    }
+}
+
+impl<L: mpc::Proof + StrictDumb + Clone + Eq> AnchorBundle<L> {
+    /// Constructs empty bundle.
+    pub fn new() -> Self { default!() }
+
+    /// Returns number of anchors in the bundle.
+    pub fn len(&self) -> usize { self.anchors.len() }
+
+    /// Detects whether the bundle has no anchors.
+    pub fn is_empty(&self) -> bool { self.anchors.is_empty() }
+
+    /// Returns anchor with a given index.
+    ///
+    /// Returns `Ok(None)` if there is no anchor with the index, and errors if
+    /// the anchor references components absent in the bundle, which may
+    /// happen only with the bundles decoded from untrusted data.
+    pub fn get(&self, index: usize) -> Result<Option<DbcAnchor<DbcProof, L>>, AnchorBundleError> {
+        let Some(item) = self.anchors.get(index) else {
+            return Ok(None);
+        };
+        let (Some(txid), Some(mpc_proof), Some(dbc_proof)) = (
+            self.txids.get(item.txid as usize),
+            self.mpc_proofs.get(item.mpc_proof as usize),
+            self.dbc_proofs.get(item.dbc_proof as usize),
+        ) else {
+            return Err(AnchorBundleError::InvalidRef(index));
+        };
+        Ok(Some(DbcAnchor {
+            txid: *txid,
+            mpc_proof: mpc_proof.clone(),
+            dbc_proof: self.dbc_proof(index, *dbc_proof)?,
+        }))
+    }
+
+    fn dbc_proof(&self, index: usize, proof: BundledProof) -> Result<DbcProof, AnchorBundleError> {
+        let tapret = match proof {
+            BundledProof::Opret(proof) => return Ok(DbcProof::Opret(proof)),
+            BundledProof::Tapret(tapret) => tapret,
+        };
+        let path_proof = match tapret.partner {
+            None => TapretPathProof::root(tapret.nonce),
+            Some(partner) => {
+                let partner = self
+                    .partners
+                    .get(partner as usize)
+                    .ok_or(AnchorBundleError::InvalidRef(index))?;
+                TapretPathProof::with(partner.clone(), tapret.nonce)
+                    .map_err(|_| AnchorBundleError::InvalidPartner(index))?
+            }
+        };
+        Ok(DbcProof::Tapret(TapretProof {
+            path_proof,
+            internal_pk: tapret.internal_pk,
+        }))
+    }
+
+    /// Restores all anchors of the bundle in the order they were added.
+    pub fn to_anchors(&self) -> Result<Vec<DbcAnchor<DbcProof, L>>, AnchorBundleError> {
+        (0..self.len())
+            .map(|index| self.get(index).map(|anchor| anchor.expect("index is in range")))
+            .collect()
+    }
+}
+
+impl<L: mpc::Proof + StrictDumb + Clone + Eq + Hash> AnchorBundle<L> {
+    /// Constructs bundle from a list of anchors.
+    pub fn from_anchors(
+        anchors: impl IntoIterator<Item = DbcAnchor<DbcProof, L>>,
+    ) -> Result<Self, AnchorBundleError> {
+        let mut builder = AnchorBundleBuilder::new();
+        for anchor in anchors {
+            builder.push(anchor)?;
+        }
+        Ok(builder.finish())
+    }
+}
+
+/// Builder of [`AnchorBundle`], indexing the components already present in
+/// the bundle.
+#[derive(Clone, Debug)]
+pub struct AnchorBundleBuilder<L: mpc::Proof + StrictDumb = mpc::MerkleProof> {
+    bundle: AnchorBundle<L>,
+    txids: HashMap<Txid, usize>,
+    mpc_proofs: HashMap<L, usize>,
+    dbc_proofs: HashMap<BundledProof, usize>,
+    partners: HashMap<TapretNodePartner, usize>,
+}
+
+impl<L: mpc::Proof + StrictDumb + Clone + Eq + Hash> Default for AnchorBundleBuilder<L> {
+    fn default() -> Self { Self::from(AnchorBundle::default()) }
+}
+
+impl<L: mpc::Proof + StrictDumb + Clone + Eq + Hash> From<AnchorBundle<L>>
+    for AnchorBundleBuilder<L>
+{
+    /// Constructs builder extending an existing bundle.
+    fn from(bundle: AnchorBundle<L>) -> Self {
+        fn index<T: Clone + Eq + Hash>(items: &[T]) -> HashMap<T, usize> {
+            let mut index = HashMap::with_capacity(items.len());
+            for (pos, item) in items.iter().enumerate() {
+                index.entry(item.clone()).or_insert(pos);
+            }
+            index
+        }
+        AnchorBundleBuilder {
+            txids: index(&bundle.txids),
+            mpc_proofs: index(&bundle.mpc_proofs),
+            dbc_proofs: index(&bundle.dbc_proofs),
+            partners: index(&bundle.partners),
+            bundle,
+        }
+    }
+}
+
+impl<L: mpc::Proof + StrictDumb + Clone + Eq + Hash> AnchorBundleBuilder<L> {
+    /// Constructs builder of an empty bundle.
+    pub fn new() -> Self { default!() }
+
+    /// Adds anchor to the bundle, reusing already present components, and
+    /// returns its index.
+    pub fn push(&mut self, anchor: DbcAnchor<DbcProof, L>) -> Result<usize, AnchorBundleError> {
+        let txid = intern(&mut self.bundle.txids, &mut self.txids, anchor.txid)?;
+        let mpc_proof =
+            intern(&mut self.bundle.mpc_proofs, &mut self.mpc_proofs, anchor.mpc_proof)?;
+        let dbc_proof = match anchor.dbc_proof {
+            DbcProof::Opret(proof) => BundledProof::Opret(proof),
+            DbcProof::Tapret(proof) => {
+                let partner = match proof.path_proof.partner_node() {
+                    None => None,
+                    Some(partner) => Some(intern(
+                        &mut self.bundle.partners,
+                        &mut self.partners,
+                        partner.clone(),
+                    )? as u16),
+                };
+                BundledProof::Tapret(BundledTapret {
+                    internal_pk: proof.internal_pk,
+                    nonce: proof.path_proof.nonce(),
+                    partner,
+                })
+            }
+        };
+        let dbc_proof = intern(&mut self.bundle.dbc_proofs, &mut self.dbc_proofs, dbc_proof)?;
+        self.bundle
+            .anchors
+            .push(AnchorRef {
+                txid: txid as u16,
+                mpc_proof: mpc_proof as u32,
+                dbc_proof: dbc_proof as u16,
+            })
+            .map_err(|_| AnchorBundleError::TooManyEntries)?;
+        Ok(self.bundle.anchors.len() - 1)
+    }
+
+    /// Returns the constructed bundle.
+    pub fn finish(self) -> AnchorBundle<L> { self.bundle }
+}
+
+fn intern<T: Clone + Eq + Hash, const MAX: usize>(
+    items: &mut Confined<Vec<T>, 0, MAX>,
+    index: &mut HashMap<T, usize>,
+    item: T,
+) -> Result<usize, AnchorBundleError> {
+    if let Some(pos) = index.get(&item) {
+        return Ok(*pos);
+    }
+    items.push(item.clone()).map_err(|_| AnchorBundleError::TooManyEntries)?;
+    let pos = items.len() - 1;
+    index.insert(item, pos);
+    Ok(pos)
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use bc::{LeafScript, LeafVer};
+    use commit_verify::mpc::{Message, ProtocolId};
+
+    use super::*;
+    use crate::test_helpers::{internal_pk, tx};
+    use crate::{commit_to_tx, CommitMethod};
+
+    #[test]
+    fn sharing() {
+        let tx = tx([]);
+        let pids = (1..=4u8).map(|no| ProtocolId::from([no; 32])).collect::<Vec<_>>();
+        let messages = pids.iter().map(|pid| (*pid, Message::from([0xAAu8; 32])));
+        let (_, anchor) = commit_to_tx(messages.collect(), tx, CommitMethod::Opret).unwrap();
+        let anchors =
+            pids.iter().map(|pid| anchor.to_merkle_proof(*pid).unwrap()).collect::<Vec<_>>();
+
+        let bundle = AnchorBundle::from_anchors(anchors.clone()).unwrap();
+        assert_eq!(bundle.len(), 4);
+        assert_eq!(bundle.txids.len(), 1);
+        assert_eq!(bundle.dbc_proofs.len(), 1);
+        assert_eq!(bundle.to_anchors().unwrap(), anchors);
+
+        let data = bundle.to_strict_serialized::<U32>().unwrap();
+        let plain_len = anchors
+            .iter()
+            .map(|anchor| anchor.to_strict_serialized::<U32>().unwrap().len())
+            .sum::<usize>();
+        assert!(data.len() < plain_len);
+        assert_eq!(AnchorBundle::from_strict_serialized::<U32>(data).unwrap(), bundle);
+
+        let mut builder = AnchorBundleBuilder::from(bundle.clone());
+        assert_eq!(builder.push(anchors[0].clone()), Ok(4));
+        let extended = builder.finish();
+        assert_eq!(extended.mpc_proofs.len(), bundle.mpc_proofs.len());
+        assert_eq!(extended.get(4).unwrap(), Some(anchors[0].clone()));
+
+        let mut broken = bundle;
+        let item = AnchorRef {
+            dbc_proof: 1,
+            ..broken.anchors[0]
+        };
+        broken.anchors = MediumVec::from_checked(vec![item]);
+        assert_eq!(broken.get(0), Err(AnchorBundleError::InvalidRef(0)));
+    }
+
+    #[test]
+    fn partner_sharing() {
+        let pid = ProtocolId::from([1u8; 32]);
+        let tx = tx([]);
+        let messages = bmap! { pid => Message::from([0xAAu8; 32]) };
+        let (_, anchor) = commit_to_tx(messages, tx, CommitMethod::Opret).unwrap();
+        let mpc_proof = anchor.to_merkle_proof(pid).unwrap().mpc_proof;
+
+        let script = LeafScript::with_bytes(LeafVer::TapScript, vec![0x51; 200]).unwrap();
+        let partner = TapretNodePartner::RightLeaf(script);
+        let anchors = (1..=4u8)
+            .map(|no| DbcAnchor {
+                txid: Txid::from([no; 32]),
+                mpc_proof: mpc_proof.clone(),
+                dbc_proof: DbcProof::Tapret(TapretProof {
+                    path_proof: TapretPathProof::with(partner.clone(), no).unwrap(),
+                    internal_pk: internal_pk(),
+                }),
+            })
+            .collect::<Vec<_>>();
+
+        let bundle = AnchorBundle::from_anchors(anchors.clone()).unwrap();
+        assert_eq!(bundle.dbc_proofs.len(), 4);
+        assert_eq!(bundle.partners.len(), 1);
+        assert_eq!(bundle.to_anchors().unwrap(), anchors);
+
+        // The 200-byte partner script is stored once instead of four times
+        let data = bundle.to_strict_serialized::<U32>().unwrap();
+        let plain_len = anchors
+            .iter()
+            .map(|anchor| anchor.to_strict_serialized::<U32>().unwrap().len())
+            .sum::<usize>();
+        assert!(data.len() + 3 * 200 < plain_len);
+        assert_eq!(AnchorBundle::from_strict_serialized::<U32>(data).unwrap(), bundle);
+
+        let mut broken = bundle;
+        broken.partners = empty!();
+        assert_eq!(broken.get(0), Err(AnchorBundleError::InvalidRef(0)));
+    }
+}
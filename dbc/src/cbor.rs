@@ -0,0 +1,93 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic CBOR encoding of proofs and anchors, compatible with IPLD
+//! DAG-CBOR codec.
+//!
+//! Maps are encoded with canonical key ordering and decoding rejects any
+//! non-canonical encoding, such that each value has exactly one CBOR
+//! representation.
+
+use commit_verify::mpc::MerkleBlock;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::opret::OpretProof;
+use crate::tapret::TapretProof;
+use crate::{DbcAnchor, DbcProof, Proof};
+
+/// Errors decoding data from CBOR.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CborError {
+    /// invalid CBOR data. Details: {0}
+    Decode(String),
+
+    /// CBOR data doesn't use the canonical encoding.
+    NonCanonical,
+}
+
+/// Data which can be encoded with deterministic CBOR.
+pub trait Cbor: Serialize + DeserializeOwned {
+    /// Encodes data into deterministic CBOR.
+    fn to_cbor(&self) -> Vec<u8> {
+        serde_ipld_dagcbor::to_vec(self).expect("CBOR encoding of in-memory data")
+    }
+
+    /// Decodes data from CBOR, ensuring that the data are entirely consumed
+    /// and use the canonical encoding.
+    fn from_cbor(data: impl AsRef<[u8]>) -> Result<Self, CborError> {
+        let data = data.as_ref();
+        let value: Self = serde_ipld_dagcbor::from_slice(data)
+            .map_err(|err| CborError::Decode(err.to_string()))?;
+        if value.to_cbor() != data {
+            return Err(CborError::NonCanonical);
+        }
+        Ok(value)
+    }
+}
+
+impl Cbor for OpretProof {}
+impl Cbor for TapretProof {}
+impl Cbor for DbcProof {}
+impl<D: Proof + Serialize + DeserializeOwned> Cbor for DbcAnchor<D> {}
+impl Cbor for DbcAnchor<DbcProof> {}
+impl<D: Proof + Serialize + DeserializeOwned> Cbor for DbcAnchor<D, MerkleBlock> {}
+impl Cbor for DbcAnchor<DbcProof, MerkleBlock> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::tapret_proof;
+
+    #[test]
+    fn roundtrip() {
+        let proof = tapret_proof(0);
+        let data = proof.to_cbor();
+        // Proofs are encoded as CBOR byte strings of their strict encoding
+        assert_eq!(data[0] & 0xE0, 0x40);
+        assert_eq!(TapretProof::from_cbor(&data).unwrap(), proof);
+
+        let mut extended = data.clone();
+        extended.push(0);
+        assert!(TapretProof::from_cbor(&extended).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Embedding of multi-protocol commitments into transactions in a single call.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::{self, MediumOrdMap};
+use bc::opcodes::OP_RETURN;
+use bc::{InternalPk, Sats, ScriptPubkey, Tx, TxOut, Txid};
+use commit_verify::mpc::{self, Message, ProtocolId, MPC_MINIMAL_DEPTH};
+use commit_verify::{CommitId, ConvolveCommit, EmbedCommitVerify, TryCommitVerify};
+
+use crate::opret::OpretError;
+use crate::tapret::{TapretError, TapretPathProof, TapretProof};
+use crate::{DbcAnchor, DbcProof, Method};
+
+/// Errors embedding multi-protocol commitment into a transaction.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(inner)]
+pub enum CommitError {
+    /// Invalid multi-protocol commitment.
+    #[from]
+    Mpc(mpc::Error),
+
+    /// Failed opret commitment.
+    #[from]
+    Opret(OpretError),
+
+    /// Failed tapret commitment.
+    #[from]
+    Tapret(TapretError),
+
+    /// Transaction can't have more outputs to host the commitment.
+    #[from]
+    TooManyOutputs(confinement::Error),
+}
+
+/// Method used to embed the commitment, together with the information
+/// required to construct the commitment.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum CommitMethod {
+    /// Commit to the first OP_RETURN output of the transaction, which must be
+    /// empty. If the transaction has no OP_RETURN outputs, a new zero-value
+    /// output is added.
+    Opret,
+
+    /// Commit to the first taproot output of the transaction.
+    Tapret {
+        /// Internal key of the first taproot output.
+        internal_pk: InternalPk,
+        /// Path proof describing the position of the commitment in the script
+        /// tree of the output.
+        path_proof: TapretPathProof,
+    },
+}
+
+impl CommitMethod {
+    /// Returns a single-use seal closing method used for the commitment.
+    pub fn method(&self) -> Method {
+        match self {
+            CommitMethod::Opret => Method::OpretFirst,
+            CommitMethod::Tapret { .. } => Method::TapretFirst,
+        }
+    }
+}
+
+/// Anchors for a set of alternative witness transactions (like fee-bumping
+/// replacements or split batches) committing to the same multi-protocol
+/// commitment tree.
+///
+/// Allows selecting the anchor for whichever of the transactions got mined.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BatchAnchors {
+    /// Multi-protocol commitment tree shared by all the transactions.
+    pub mpc_proof: mpc::MerkleBlock,
+
+    /// Proofs of the DBC commitments for each of the transactions.
+    pub dbc_proofs: BTreeMap<Txid, DbcProof>,
+}
+
+impl BatchAnchors {
+    /// Returns ids of all the transactions committing to the tree.
+    pub fn txids(&self) -> impl Iterator<Item = Txid> + '_ { self.dbc_proofs.keys().copied() }
+
+    /// Returns anchor for the transaction with the given id, if the transaction
+    /// is part of the batch.
+    pub fn anchor(&self, txid: Txid) -> Option<DbcAnchor<DbcProof, mpc::MerkleBlock>> {
+        let dbc_proof = self.dbc_proofs.get(&txid)?.clone();
+        Some(DbcAnchor {
+            txid,
+            mpc_proof: self.mpc_proof.clone(),
+            dbc_proof,
+        })
+    }
+
+    /// Selects anchor matching the confirmed transaction, if the transaction
+    /// is part of the batch.
+    #[inline]
+    pub fn select(&self, tx: &Tx) -> Option<DbcAnchor<DbcProof, mpc::MerkleBlock>> {
+        self.anchor(tx.txid())
+    }
+}
+
+fn mpc_tree(messages: BTreeMap<ProtocolId, Message>) -> Result<mpc::MerkleTree, mpc::Error> {
+    let source = mpc::MultiSource {
+        method: mpc::Method::Sha256t,
+        min_depth: MPC_MINIMAL_DEPTH,
+        messages: MediumOrdMap::from_iter_checked(messages),
+        static_entropy: None,
+    };
+    mpc::MerkleTree::try_commit(&source)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(method = %method.method()), err)
+)]
+fn embed(
+    tx: &mut Tx,
+    commitment: &mpc::Commitment,
+    method: CommitMethod,
+) -> Result<DbcProof, CommitError> {
+    let proof = match method {
+        CommitMethod::Opret => {
+            if !tx.outputs().any(|txout| txout.script_pubkey.is_op_return()) {
+                let txout = TxOut::new(ScriptPubkey::from_unsafe(vec![OP_RETURN]), Sats::ZERO);
+                tx.outputs.push(txout)?;
+            }
+            DbcProof::Opret(tx.embed_commit(commitment)?)
+        }
+        CommitMethod::Tapret {
+            internal_pk,
+            path_proof,
+        } => {
+            let supplement = TapretProof {
+                path_proof,
+                internal_pk,
+            };
+            let (commitment_tx, proof) = tx.convolve_commit(&supplement, commitment)?;
+            *tx = commitment_tx;
+            DbcProof::Tapret(proof)
+        }
+    };
+    #[cfg(feature = "tracing")]
+    tracing::debug!(txid = %tx.txid(), "commitment is embedded into the transaction");
+    Ok(proof)
+}
+
+/// Builds LNPBP-4 multi-protocol commitment tree for the provided messages,
+/// embeds its commitment into the transaction using the chosen method and
+/// returns the modified transaction together with the anchor revealing all
+/// the messages.
+pub fn commit_to_tx(
+    messages: BTreeMap<ProtocolId, Message>,
+    mut tx: Tx,
+    method: CommitMethod,
+) -> Result<(Tx, DbcAnchor<DbcProof, mpc::MerkleBlock>), CommitError> {
+    let tree = mpc_tree(messages)?;
+    let dbc_proof = embed(&mut tx, &tree.commit_id(), method)?;
+
+    let anchor = DbcAnchor {
+        txid: tx.txid(),
+        mpc_proof: mpc::MerkleBlock::from(&tree),
+        dbc_proof,
+    };
+    Ok((tx, anchor))
+}
+
+/// Builds a single LNPBP-4 multi-protocol commitment tree for the provided
+/// messages and embeds its commitment into each of the transactions using
+/// the method chosen for the transaction.
+///
+/// Returns the modified transactions (in the original order) together with
+/// the anchors for all of them, which verify against the same messages.
+pub fn commit_to_txs(
+    messages: BTreeMap<ProtocolId, Message>,
+    txs: impl IntoIterator<Item = (Tx, CommitMethod)>,
+) -> Result<(Vec<Tx>, BatchAnchors), CommitError> {
+    let tree = mpc_tree(messages)?;
+    let commitment = tree.commit_id();
+
+    let mut committed = vec![];
+    let mut dbc_proofs = BTreeMap::new();
+    for (mut tx, method) in txs {
+        let dbc_proof = embed(&mut tx, &commitment, method)?;
+        dbc_proofs.insert(tx.txid(), dbc_proof);
+        committed.push(tx);
+    }
+
+    let anchors = BatchAnchors {
+        mpc_proof: mpc::MerkleBlock::from(&tree),
+        dbc_proofs,
+    };
+    Ok((committed, anchors))
+}
+
+#[cfg(test)]
+mod test {
+    use bc::LockTime;
+
+    use super::*;
+    use crate::test_helpers::tx;
+
+    #[test]
+    fn opret() {
+        let tx = tx([]);
+        let messages = bmap! {
+            ProtocolId::from([1u8; 32]) => Message::from([11u8; 32]),
+            ProtocolId::from([2u8; 32]) => Message::from([12u8; 32]),
+        };
+        let (tx, anchor) = commit_to_tx(messages.clone(), tx, CommitMethod::Opret).unwrap();
+        assert_eq!(tx.outputs.len(), 1);
+        assert_eq!(anchor.dbc_proof.method(), Method::OpretFirst);
+        for (pid, msg) in messages {
+            let anchor = anchor.to_merkle_proof(pid).unwrap();
+            assert!(anchor.verify(pid, msg, &tx).is_ok());
+        }
+    }
+
+    #[test]
+    fn batch() {
+        let tx1 = tx([]);
+        let mut tx2 = tx1.clone();
+        tx2.lock_time = LockTime::from_consensus_u32(1);
+        let messages = bmap! {
+            ProtocolId::from([1u8; 32]) => Message::from([11u8; 32]),
+            ProtocolId::from([2u8; 32]) => Message::from([12u8; 32]),
+        };
+        let (txs, anchors) = commit_to_txs(messages.clone(), [
+            (tx1, CommitMethod::Opret),
+            (tx2, CommitMethod::Opret),
+        ])
+        .unwrap();
+        assert_eq!(anchors.txids().count(), 2);
+        for tx in &txs {
+            let anchor = anchors.select(tx).unwrap();
+            for (pid, msg) in &messages {
+                let anchor = anchor.to_merkle_proof(*pid).unwrap();
+                assert!(anchor.verify(*pid, *msg, tx).is_ok());
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Binary container format for storing proofs and anchors in files.
+//!
+//! Container has the following layout:
+//! - `0..4`: magic bytes [`CONTAINER_MAGIC`];
+//! - `4`: container format version;
+//! - `5..7`: type tag of the payload, in little-endian byte order;
+//! - `7..11`: length of the payload, in little-endian byte order;
+//! - strict-encoded payload;
+//! - last 4 bytes: first bytes of SHA256 hash of all preceding data.
+//!
+//! Type tags are allocated by the crates defining the stored types: `0x01xx`
+//! is used by this crate, `0x02xx` - by single-use seals crate.
+
+use std::path::Path;
+use std::{fs, io};
+
+use amplify::confinement::{Confined, U32};
+use commit_verify::mpc::MerkleBlock;
+use commit_verify::{DigestExt, Sha256};
+use strict_encoding::{DeserializeError, StrictDeserialize, StrictSerialize};
+
+use crate::opret::OpretProof;
+use crate::tapret::TapretProof;
+use crate::{DbcAnchor, DbcProof, Proof};
+
+/// Magic bytes starting every container.
+pub const CONTAINER_MAGIC: [u8; 4] = *b"bpcc";
+
+/// Version of the container format produced by this library.
+pub const CONTAINER_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 11;
+const CHECKSUM_LEN: usize = 4;
+
+/// Errors reading data from a container.
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ContainerError {
+    /// I/O error reading the container.
+    #[from]
+    #[display(inner)]
+    Io(io::Error),
+
+    /// data are too short to be a container.
+    Truncated,
+
+    /// data doesn't start with the container magic bytes.
+    NoMagic,
+
+    /// container has unsupported format version {0}.
+    UnsupportedVersion(u8),
+
+    /// container holds data of type {actual:#06x}, while type {expected:#06x}
+    /// was expected.
+    TypeMismatch {
+        /// Type tag expected by the caller.
+        expected: u16,
+        /// Type tag found in the container.
+        actual: u16,
+    },
+
+    /// container payload length doesn't match the length specified in the
+    /// header.
+    LengthMismatch,
+
+    /// container checksum doesn't match its data.
+    ChecksumMismatch,
+
+    /// invalid container payload. Details: {0}
+    #[from]
+    Decode(DeserializeError),
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut engine = Sha256::default();
+    engine.input_raw(data);
+    let hash = engine.finish();
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Data which can be stored in a binary container.
+pub trait Containerized: StrictSerialize + StrictDeserialize {
+    /// Type tag identifying data type inside the container.
+    const CONTAINER_TAG: u16;
+
+    /// Serializes data into a container.
+    fn to_container(&self) -> Vec<u8> {
+        let payload = self.to_strict_serialized::<U32>().expect("payload exceeds 4GB").release();
+        let mut data = Vec::with_capacity(HEADER_LEN + payload.len() + CHECKSUM_LEN);
+        data.extend(CONTAINER_MAGIC);
+        data.push(CONTAINER_VERSION);
+        data.extend(Self::CONTAINER_TAG.to_le_bytes());
+        data.extend((payload.len() as u32).to_le_bytes());
+        data.extend(payload);
+        data.extend(checksum(&data));
+        data
+    }
+
+    /// Deserializes data from a container, checking its format version, type
+    /// and integrity.
+    fn from_container(data: impl AsRef<[u8]>) -> Result<Self, ContainerError> {
+        let data = data.as_ref();
+        if data.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(ContainerError::Truncated);
+        }
+        if data[..4] != CONTAINER_MAGIC {
+            return Err(ContainerError::NoMagic);
+        }
+        if data[4] != CONTAINER_VERSION {
+            return Err(ContainerError::UnsupportedVersion(data[4]));
+        }
+        let tag = u16::from_le_bytes([data[5], data[6]]);
+        if tag != Self::CONTAINER_TAG {
+            return Err(ContainerError::TypeMismatch {
+                expected: Self::CONTAINER_TAG,
+                actual: tag,
+            });
+        }
+        let len = u32::from_le_bytes([data[7], data[8], data[9], data[10]]) as usize;
+        if data.len() - HEADER_LEN - CHECKSUM_LEN != len {
+            return Err(ContainerError::LengthMismatch);
+        }
+        let (body, check) = data.split_at(HEADER_LEN + len);
+        if check != checksum(body) {
+            return Err(ContainerError::ChecksumMismatch);
+        }
+        let payload = Confined::try_from(body[HEADER_LEN..].to_vec()).expect("payload length");
+        Self::from_strict_serialized::<U32>(payload).map_err(ContainerError::from)
+    }
+
+    /// Saves data as a container file.
+    fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_container())
+    }
+
+    /// Loads data from a container file.
+    fn load(path: impl AsRef<Path>) -> Result<Self, ContainerError> {
+        let data = fs::read(path)?;
+        Self::from_container(data)
+    }
+}
+
+impl Containerized for OpretProof {
+    const CONTAINER_TAG: u16 = 0x0100;
+}
+
+impl Containerized for TapretProof {
+    const CONTAINER_TAG: u16 = 0x0101;
+}
+
+impl Containerized for DbcProof {
+    const CONTAINER_TAG: u16 = 0x010F;
+}
+
+impl<D: Proof> Containerized for DbcAnchor<D> {
+    const CONTAINER_TAG: u16 = 0x0110 | D::METHOD as u16;
+}
+
+impl Containerized for DbcAnchor<DbcProof> {
+    const CONTAINER_TAG: u16 = 0x011F;
+}
+
+impl<D: Proof> Containerized for DbcAnchor<D, MerkleBlock> {
+    const CONTAINER_TAG: u16 = 0x0120 | D::METHOD as u16;
+}
+
+impl Containerized for DbcAnchor<DbcProof, MerkleBlock> {
+    const CONTAINER_TAG: u16 = 0x012F;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::tapret_proof;
+
+    #[test]
+    fn roundtrip() {
+        let proof = tapret_proof(0);
+        let data = proof.to_container();
+        assert_eq!(data[..4], CONTAINER_MAGIC);
+        assert_eq!(TapretProof::from_container(&data).unwrap(), proof);
+
+        assert!(matches!(
+            OpretProof::from_container(&data),
+            Err(ContainerError::TypeMismatch {
+                expected: 0x0100,
+                actual: 0x0101
+            })
+        ));
+
+        let mut corrupted = data.clone();
+        corrupted[HEADER_LEN] ^= 0xFF;
+        assert!(matches!(
+            TapretProof::from_container(&corrupted),
+            Err(ContainerError::ChecksumMismatch)
+        ));
+        assert!(matches!(
+            TapretProof::from_container(&data[..data.len() - 1]),
+            Err(ContainerError::LengthMismatch)
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the outputs which can host tapret commitments from their
+//! output descriptors.
+
+use bc::{
+    InternalPk, IntoTapHash, LeafScript, LeafVer, OutputPk, TapBranchHash, TapLeafHash, TapNodeHash,
+};
+use commit_verify::{mpc, ConvolveCommit};
+use miniscript::bitcoin::hashes::Hash;
+use miniscript::bitcoin::taproot::TAPROOT_CONTROL_MAX_NODE_COUNT;
+use miniscript::descriptor::DescriptorPublicKey;
+use miniscript::Descriptor;
+
+use crate::tapret::{TapretNodePartner, TapretPathError, TapretPathProof, TapretProof};
+
+/// Errors detecting tapret commitment host from an output descriptor.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum DescriptorError {
+    /// descriptor is not a taproot descriptor and can't host tapret
+    /// commitments.
+    NonTaproot,
+
+    /// keys of the descriptor can't be derived. Details: {0}
+    Derivation(String),
+
+    /// descriptor contains invalid taproot script. Details: {0}
+    InvalidScript(String),
+
+    /// taproot script tree of the descriptor has the maximal depth, leaving no
+    /// place for the tapret commitment.
+    TreeTooDeep,
+
+    /// taproot script tree of the descriptor can't be a tapret commitment
+    /// partner. Details: {0}
+    #[from]
+    Partner(TapretPathError),
+
+    /// none of the nonce values puts the tapret commitment into a correct
+    /// consensus order with the script tree of the descriptor.
+    NoNonce,
+}
+
+/// Information about an output defined by a descriptor, required to embed
+/// tapret commitment into it.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TapretHost {
+    /// Internal key of the taproot output.
+    pub internal_pk: InternalPk,
+
+    /// Partner node for the tapret commitment at the first level of the script
+    /// tree, if the output has script spending paths.
+    pub partner: Option<TapretNodePartner>,
+}
+
+impl TapretHost {
+    /// Constructs tapret path proof for the output with the given nonce.
+    pub fn path_proof(&self, nonce: u8) -> Result<TapretPathProof, TapretPathError> {
+        match &self.partner {
+            None => Ok(TapretPathProof::root(nonce)),
+            Some(partner) => TapretPathProof::with(partner.clone(), nonce),
+        }
+    }
+
+    /// Embeds tapret commitment into the output, returning the output key and
+    /// the proof of the commitment.
+    ///
+    /// The commitment leaf must precede the partner node in the consensus
+    /// ordering of the script tree, which depends on the nonce; thus the
+    /// nonces are tried one by one until the ordering is valid.
+    pub fn commit(
+        &self,
+        commitment: &mpc::Commitment,
+    ) -> Result<(OutputPk, TapretProof), DescriptorError> {
+        for nonce in 0..=u8::MAX {
+            let path_proof = self.path_proof(nonce)?;
+            if let Ok(res) = self.internal_pk.convolve_commit(&path_proof, commitment) {
+                return Ok(res);
+            }
+        }
+        Err(DescriptorError::NoNonce)
+    }
+}
+
+/// Detects whether the output defined by the descriptor at a given derivation
+/// index can host tapret commitment, returning the information required to
+/// construct [`crate::tapret::TapretProof`].
+pub fn tapret_host(
+    descriptor: &Descriptor<DescriptorPublicKey>,
+    index: u32,
+) -> Result<TapretHost, DescriptorError> {
+    let descriptor = descriptor
+        .at_derivation_index(index)
+        .map_err(|err| DescriptorError::Derivation(err.to_string()))?;
+    let Descriptor::Tr(tr) = descriptor else {
+        return Err(DescriptorError::NonTaproot);
+    };
+    let spend_info = tr.spend_info();
+    let internal_pk = InternalPk::from_byte_array(spend_info.internal_key().serialize())
+        .expect("valid x-only key");
+
+    let mut partner = None;
+    let mut max_depth = 0usize;
+    for ((script, version), control_blocks) in spend_info.script_map() {
+        let Some(control_block) = control_blocks.iter().next() else {
+            continue;
+        };
+        let branch = control_block.merkle_branch.as_slice();
+        max_depth = max_depth.max(branch.len());
+        if partner.is_some() {
+            continue;
+        }
+        let version = LeafVer::from_consensus_u8(version.to_consensus())
+            .map_err(|err| DescriptorError::InvalidScript(err.to_string()))?;
+        let leaf = LeafScript::with_bytes(version, script.to_bytes())
+            .map_err(|err| DescriptorError::InvalidScript(err.to_string()))?;
+        partner = Some(match branch.split_last() {
+            None => TapretNodePartner::RightLeaf(leaf),
+            Some((top, rest)) => {
+                let mut node = TapLeafHash::with_leaf_script(&leaf).into_tap_hash();
+                for hash in rest {
+                    let sibling = TapNodeHash::from(hash.to_byte_array());
+                    node = TapBranchHash::with_nodes(node, sibling).into_tap_hash();
+                }
+                TapretNodePartner::right_branch(node, TapNodeHash::from(top.to_byte_array()))
+            }
+        });
+    }
+    if max_depth >= TAPROOT_CONTROL_MAX_NODE_COUNT {
+        return Err(DescriptorError::TreeTooDeep);
+    }
+
+    let host = TapretHost {
+        internal_pk,
+        partner,
+    };
+    host.path_proof(0)?;
+    Ok(host)
+}
+
+/// Checks whether outputs defined by the descriptor can host tapret
+/// commitments, allowing to reject incompatible descriptors before the
+/// transaction construction.
+#[inline]
+pub fn check_tapret_host(
+    descriptor: &Descriptor<DescriptorPublicKey>,
+) -> Result<(), DescriptorError> {
+    tapret_host(descriptor, 0).map(|_| ())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use commit_verify::ConvolveCommitProof;
+
+    use super::*;
+    use crate::tapret::TapretFirst;
+    use crate::test_helpers::{internal_pk, INTERNAL_PK as KEY};
+
+    #[test]
+    fn key_only() {
+        let descriptor = Descriptor::from_str(&format!("tr({KEY})")).unwrap();
+        let host = tapret_host(&descriptor, 0).unwrap();
+        assert_eq!(host.internal_pk, internal_pk());
+        assert_eq!(host.partner, None);
+    }
+
+    #[test]
+    fn non_taproot() {
+        let descriptor = Descriptor::from_str(&format!("wpkh(02{KEY})")).unwrap();
+        assert_eq!(check_tapret_host(&descriptor), Err(DescriptorError::NonTaproot));
+    }
+
+    #[test]
+    fn script_tree() {
+        const KEY_A: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        const KEY_B: &str = "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        for descriptor in
+            [format!("tr({KEY},pk({KEY_A}))"), format!("tr({KEY},{{pk({KEY_A}),pk({KEY_B})}})")]
+        {
+            let descriptor = Descriptor::from_str(&descriptor).unwrap();
+            let host = tapret_host(&descriptor, 0).unwrap();
+            assert!(host.partner.is_some());
+            let mut nonces = bset![];
+            for no in 0u8..16 {
+                let msg = mpc::Commitment::from([no; 32]);
+                let (output_pk, proof) = host.commit(&msg).unwrap();
+                nonces.insert(proof.path_proof.nonce());
+                ConvolveCommitProof::<mpc::Commitment, InternalPk, TapretFirst>::verify(
+                    &proof, &msg, &output_pk,
+                )
+                .unwrap();
+            }
+            // Some of the messages require non-zero nonce to order the commitment
+            assert!(nonces.len() > 1);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proofs of the same multi-protocol commitment made under both tapret and
+//! opret methods in a single transaction.
+
+use bc::Tx;
+use commit_verify::{mpc, ConvolveVerifyError};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::opret::{OpretProof, OpretVerifyError};
+use crate::tapret::TapretProof;
+use crate::{Proof, LIB_NAME_BPCORE};
+
+/// Verification semantics for [`DualProof`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, Default)]
+#[display(lowercase)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Redundancy {
+    /// Commitments under both methods must be valid.
+    #[default]
+    All,
+
+    /// Commitment under at least one of the methods must be valid.
+    Any,
+}
+
+/// Errors verifying [`DualProof`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum DualProofError {
+    /// invalid opret commitment. Details: {0}
+    Opret(OpretVerifyError),
+
+    /// invalid tapret commitment. Details: {0}
+    Tapret(ConvolveVerifyError),
+
+    /// both commitments are invalid; opret: {opret}; tapret: {tapret}
+    Both {
+        /// Opret verification error.
+        opret: OpretVerifyError,
+        /// Tapret verification error.
+        tapret: ConvolveVerifyError,
+    },
+}
+
+/// Proof of the same multi-protocol commitment made both with tapret and opret
+/// methods within a single transaction.
+///
+/// Allows the commitment to remain verifiable even if one of the methods is
+/// deemed problematic later.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct DualProof {
+    /// Opret commitment proof.
+    pub opret: OpretProof,
+    /// Tapret commitment proof.
+    pub tapret: TapretProof,
+}
+
+impl StrictSerialize for DualProof {}
+impl StrictDeserialize for DualProof {}
+
+#[cfg(feature = "serde")]
+crate::impl_strict_serde!(DualProof);
+
+impl DualProof {
+    /// Constructs dual proof from the proofs for each of the methods.
+    pub fn new(opret: OpretProof, tapret: TapretProof) -> Self { DualProof { opret, tapret } }
+
+    /// Verifies commitments against the provided transaction using the
+    /// requested semantics.
+    pub fn verify(
+        &self,
+        msg: &mpc::Commitment,
+        tx: &Tx,
+        redundancy: Redundancy,
+    ) -> Result<(), DualProofError> {
+        let opret = self.opret.verify(msg, tx);
+        let tapret = self.tapret.verify(msg, tx);
+        match (opret, tapret, redundancy) {
+            (Ok(()), Ok(()), _) => Ok(()),
+            (Ok(()), Err(_), Redundancy::Any) | (Err(_), Ok(()), Redundancy::Any) => Ok(()),
+            (Ok(()), Err(err), Redundancy::All) => Err(DualProofError::Tapret(err)),
+            (Err(err), Ok(()), Redundancy::All) => Err(DualProofError::Opret(err)),
+            (Err(opret), Err(tapret), _) => Err(DualProofError::Both { opret, tapret }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::ScriptPubkey;
+    use commit_verify::ConvolveCommit;
+
+    use super::*;
+    use crate::test_helpers::{p2tr, tapret_proof, tx};
+
+    #[test]
+    fn redundancy() {
+        let msg = mpc::Commitment::from([8u8; 32]);
+        let other = mpc::Commitment::from([9u8; 32]);
+        let tapret = tapret_proof(0);
+        let proof = DualProof::new(OpretProof::default(), tapret.clone());
+
+        let (both, _) = tx([p2tr(0x55), ScriptPubkey::op_return(msg.as_slice())])
+            .convolve_commit(&tapret, &msg)
+            .unwrap();
+        assert_eq!(proof.verify(&msg, &both, Redundancy::All), Ok(()));
+        assert_eq!(proof.verify(&msg, &both, Redundancy::Any), Ok(()));
+
+        let opret_only = tx([p2tr(0x55), ScriptPubkey::op_return(msg.as_slice())]);
+        assert!(matches!(
+            proof.verify(&msg, &opret_only, Redundancy::All),
+            Err(DualProofError::Tapret(_))
+        ));
+        assert_eq!(proof.verify(&msg, &opret_only, Redundancy::Any), Ok(()));
+
+        let (tapret_only, _) = tx([p2tr(0x55), ScriptPubkey::op_return(other.as_slice())])
+            .convolve_commit(&tapret, &msg)
+            .unwrap();
+        assert_eq!(
+            proof.verify(&msg, &tapret_only, Redundancy::All),
+            Err(DualProofError::Opret(OpretVerifyError::PayloadMismatch {
+                expected: msg,
+                actual: other
+            }))
+        );
+        assert_eq!(proof.verify(&msg, &tapret_only, Redundancy::Any), Ok(()));
+
+        let none = tx([p2tr(0x55)]);
+        assert!(matches!(
+            proof.verify(&msg, &none, Redundancy::Any),
+            Err(DualProofError::Both {
+                opret: OpretVerifyError::NoOpretOutput,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured reports explaining how deterministic bitcoin commitments are
+//! verified.
+
+use std::ops::Range;
+
+use bc::{InternalPk, OutputPk, ScriptPubkey, TapNodeHash, TapScript, Tx, Txid, Vout};
+use commit_verify::mpc::{self, Message, ProtocolId};
+use commit_verify::{CommitVerify, ConvolveCommit};
+
+use crate::tapret::{TapretCommitment, TAPRET_SCRIPT_COMMITMENT_PREFIX};
+use crate::{DbcAnchor, DbcAnchorError, DbcError, DbcProof, Method};
+
+/// Details of a verified tapret commitment.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct TapretReport {
+    /// Internal key of the taproot output.
+    pub internal_pk: InternalPk,
+    /// Output key recomputed from the internal key and the commitment.
+    pub output_key: OutputPk,
+    /// Depth of the commitment leaf in the taproot script tree.
+    pub depth: u8,
+    /// Hash of the partner node of the commitment leaf, if any.
+    pub partner_hash: Option<TapNodeHash>,
+    /// Nonce used to put the commitment into the right side of the tree.
+    pub nonce: u8,
+}
+
+/// Report on a successful verification of a DBC proof.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct VerifyReport {
+    /// Id of the witness transaction.
+    pub txid: Txid,
+    /// Multi-protocol commitment contained in the transaction.
+    pub commitment: mpc::Commitment,
+    /// Method used by the commitment.
+    pub method: Method,
+    /// Transaction output hosting the commitment.
+    pub vout: Vout,
+    /// Script pubkey of the output hosting the commitment.
+    pub script_pubkey: ScriptPubkey,
+    /// Details of the tapret commitment, if the tapret method is used.
+    pub tapret: Option<TapretReport>,
+}
+
+impl DbcProof {
+    /// Verifies DBC proof against the provided transaction, returning report
+    /// describing how the commitment is verified.
+    pub fn verify_explain(&self, msg: &mpc::Commitment, tx: &Tx) -> Result<VerifyReport, DbcError> {
+        self.verify(msg, tx)?;
+
+        let method = self.method();
+        let (vout, txout) = tx
+            .outputs()
+            .enumerate()
+            .find(|(_, txout)| match method {
+                Method::OpretFirst => txout.script_pubkey.is_op_return(),
+                Method::TapretFirst => txout.script_pubkey.is_p2tr(),
+            })
+            .expect("verified proof must have host output");
+
+        let tapret = match self {
+            DbcProof::Opret(_) => None,
+            DbcProof::Tapret(proof) => {
+                let (output_key, _) = proof
+                    .internal_pk
+                    .convolve_commit(&proof.path_proof, msg)
+                    .expect("verified proof must be convolvable");
+                let partner_hash = proof.path_proof.original_merkle_root();
+                Some(TapretReport {
+                    internal_pk: proof.internal_pk,
+                    output_key,
+                    depth: partner_hash.is_some() as u8,
+                    partner_hash,
+                    nonce: proof.path_proof.nonce(),
+                })
+            }
+        };
+
+        Ok(VerifyReport {
+            txid: tx.txid(),
+            commitment: *msg,
+            method,
+            vout: Vout::from_u32(vout as u32),
+            script_pubkey: txout.script_pubkey.clone(),
+            tapret,
+        })
+    }
+}
+
+impl DbcAnchor<DbcProof> {
+    /// Verifies that the transaction commits to the message under the given
+    /// protocol, returning report describing how the commitment is verified.
+    pub fn verify_explain(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+    ) -> Result<VerifyReport, DbcAnchorError<DbcError>> {
+        let commitment = self.verify(protocol_id, message, tx)?;
+        self.dbc_proof.verify_explain(&commitment, tx).map_err(DbcAnchorError::Dbc)
+    }
+}
+
+/// Script containing commitment bytes.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum CommitmentScript {
+    /// Commitment is contained in the scriptPubkey of the host output.
+    ScriptPubkey(ScriptPubkey),
+
+    /// Commitment is contained in the tapret leaf script, which is hidden
+    /// inside the output key of the host output.
+    TapScript(TapScript),
+}
+
+/// Description of the physical location of a commitment within a transaction.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CommitmentLayout {
+    /// Method used by the commitment.
+    pub method: Method,
+    /// Transaction output hosting the commitment.
+    pub vout: Vout,
+    /// Script containing the commitment bytes.
+    pub script: CommitmentScript,
+    /// Byte range of the commitment data within the script.
+    pub range: Range<usize>,
+    /// Depth of the tapret leaf in the taproot script tree, if the tapret
+    /// method is used.
+    pub leaf_depth: Option<u8>,
+}
+
+impl DbcProof {
+    /// Verifies DBC proof against the provided transaction and describes
+    /// where in the transaction the commitment bytes are located.
+    pub fn layout(&self, msg: &mpc::Commitment, tx: &Tx) -> Result<CommitmentLayout, DbcError> {
+        let report = self.verify_explain(msg, tx)?;
+        Ok(match report.tapret {
+            None => {
+                // verified opret commitment is always the direct push ending the script
+                let len = msg.as_slice().len();
+                let start = report.script_pubkey.len() - len;
+                CommitmentLayout {
+                    method: report.method,
+                    vout: report.vout,
+                    script: CommitmentScript::ScriptPubkey(report.script_pubkey),
+                    range: start..start + len,
+                    leaf_depth: None,
+                }
+            }
+            Some(tapret) => {
+                let script = TapScript::commit(&TapretCommitment::with(*msg, tapret.nonce));
+                let start = TAPRET_SCRIPT_COMMITMENT_PREFIX.len();
+                CommitmentLayout {
+                    method: report.method,
+                    vout: report.vout,
+                    range: start..script.len(),
+                    script: CommitmentScript::TapScript(script),
+                    leaf_depth: Some(tapret.depth),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::opret::OpretProof;
+    use crate::test_helpers::{internal_pk, p2tr, tapret_proof, tx};
+
+    #[test]
+    fn opret() {
+        let msg = mpc::Commitment::from([8u8; 32]);
+        let tx = tx([ScriptPubkey::p2sh([1u8; 20]), ScriptPubkey::op_return(msg.as_slice())]);
+        let proof = DbcProof::Opret(OpretProof::default());
+
+        let report = proof.verify_explain(&msg, &tx).unwrap();
+        assert_eq!(report.method, Method::OpretFirst);
+        assert_eq!(report.vout, Vout::from_u32(1));
+        assert_eq!(report.tapret, None);
+
+        let layout = proof.layout(&msg, &tx).unwrap();
+        assert_eq!(layout.range, 2..34);
+        assert_eq!(layout.leaf_depth, None);
+        let CommitmentScript::ScriptPubkey(script) = layout.script else {
+            panic!("opret commitment must be located in scriptPubkey")
+        };
+        assert_eq!(&script[layout.range], msg.as_slice());
+
+        let other = mpc::Commitment::from([9u8; 32]);
+        assert!(proof.verify_explain(&other, &tx).is_err());
+    }
+
+    #[test]
+    fn tapret() {
+        let msg = mpc::Commitment::from([8u8; 32]);
+        let tapret = tapret_proof(3);
+        let (tx, _) = tx([p2tr(0x55)]).convolve_commit(&tapret, &msg).unwrap();
+        let proof = DbcProof::Tapret(tapret);
+
+        let report = proof.verify_explain(&msg, &tx).unwrap();
+        assert_eq!(report.method, Method::TapretFirst);
+        assert_eq!(report.vout, Vout::from_u32(0));
+        let details = report.tapret.unwrap();
+        assert_eq!(details.internal_pk, internal_pk());
+        assert_eq!(details.depth, 0);
+        assert_eq!(details.partner_hash, None);
+        assert_eq!(details.nonce, 3);
+
+        let layout = proof.layout(&msg, &tx).unwrap();
+        assert_eq!(layout.leaf_depth, Some(0));
+        let CommitmentScript::TapScript(script) = layout.script else {
+            panic!("tapret commitment must be located in the tapret leaf script")
+        };
+        assert_eq!(layout.range.start, TAPRET_SCRIPT_COMMITMENT_PREFIX.len());
+        assert_eq!(&script[layout.range][..32], msg.as_slice());
+    }
+}
@@ -0,0 +1,170 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Weight, dust and fee calculations for transaction outputs hosting
+//! deterministic bitcoin commitments.
+
+use bc::{LenVarInt, Sats, ScriptPubkey, Tx, TxOut, VBytes, VarInt, Weight, WeightUnits};
+
+use crate::Method;
+
+/// Default dust relay fee rate used by bitcoin core, in satoshis per
+/// kilo-vbyte.
+pub const DEFAULT_DUST_RELAY_FEE: u64 = 3000;
+
+/// Length of a scriptPubkey hosting a commitment (both for OP_RETURN with
+/// 32-byte push and for P2TR output).
+pub const HOST_SCRIPT_LEN: usize = 34;
+
+/// Size of the input spending a witness output, as used by bitcoin core dust
+/// computations.
+const WITNESS_SPEND_SIZE: u64 = 32 + 4 + 1 + (107 / 4) + 4;
+
+/// Costs of hosting a commitment in a transaction.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct HostCost {
+    /// Weight which has to be added to the transaction.
+    pub weight: WeightUnits,
+    /// Minimal value of the host output, which allows the transaction to be
+    /// relayed by the nodes with the default policy.
+    pub min_value: Sats,
+}
+
+impl HostCost {
+    /// Returns virtual size added to the transaction.
+    #[inline]
+    pub fn vbytes(&self) -> VBytes { VBytes::from(self.weight) }
+
+    /// Computes fee which has to be added to the transaction for the given
+    /// fee rate, measured in satoshis per kilo-vbyte.
+    #[inline]
+    pub fn fee(&self, sat_per_kvb: u64) -> Sats { fee_for(self.weight, sat_per_kvb) }
+
+    /// Computes total amount spent on hosting the commitment, i.e. the fee for
+    /// the given fee rate (in satoshis per kilo-vbyte) plus the minimal value
+    /// of the host output. Can be used for ranking host candidates.
+    #[inline]
+    pub fn total(&self, sat_per_kvb: u64) -> Sats {
+        self.fee(sat_per_kvb).saturating_add(self.min_value)
+    }
+}
+
+/// Computes fee for the given weight with a fee rate measured in satoshis per
+/// kilo-vbyte, rounding up.
+pub fn fee_for(weight: WeightUnits, sat_per_kvb: u64) -> Sats {
+    let vbytes = VBytes::from(weight).into_u32() as u64;
+    Sats::from_sats((vbytes * sat_per_kvb).div_ceil(1000))
+}
+
+/// Constructs template of the scriptPubkey which will host the commitment.
+fn host_script_template() -> ScriptPubkey { ScriptPubkey::from_unsafe(vec![0u8; HOST_SCRIPT_LEN]) }
+
+impl Method {
+    /// Returns weight of a transaction output hosting the commitment.
+    pub fn host_output_weight(self) -> WeightUnits {
+        TxOut::new(host_script_template(), Sats::ZERO).weight_units()
+    }
+
+    /// Returns minimal value of the host output, such that it is not considered
+    /// dust by the nodes using the provided dust relay fee (in satoshis per
+    /// kilo-vbyte).
+    ///
+    /// OP_RETURN outputs are unspendable and may have a zero value.
+    pub fn dust_limit(self, dust_relay_fee: u64) -> Sats {
+        match self {
+            Method::OpretFirst => Sats::ZERO,
+            Method::TapretFirst => {
+                let size = self.host_output_weight().into_u32() as u64 / 4 + WITNESS_SPEND_SIZE;
+                Sats::from_sats((size * dust_relay_fee).div_ceil(1000))
+            }
+        }
+    }
+
+    /// Computes costs of adding a new output hosting the commitment to the
+    /// transaction.
+    ///
+    /// The weight includes the possible increase in the length of the output
+    /// count prefix.
+    pub fn new_host_cost(self, tx: &Tx, dust_relay_fee: u64) -> HostCost {
+        let count = tx.outputs.len();
+        let prefix_delta = VarInt::with(count + 1).len() - tx.outputs.len_var_int().len();
+        HostCost {
+            weight: self.host_output_weight() + WeightUnits::no_discount(prefix_delta),
+            min_value: self.dust_limit(dust_relay_fee),
+        }
+    }
+
+    /// Computes costs of embedding the commitment into an existing transaction
+    /// output.
+    ///
+    /// For opret this requires the first OP_RETURN output to have no data;
+    /// tapret tweak doesn't change the size of the first taproot output. If
+    /// the transaction doesn't have a suitable output, returns `None`.
+    ///
+    /// Tapret cost doesn't account for the increased size of the control
+    /// block, which is required only for the script path spendings.
+    pub fn tweak_cost(self, tx: &Tx, dust_relay_fee: u64) -> Option<HostCost> {
+        let weight = match self {
+            Method::OpretFirst => {
+                let txout = tx.outputs().find(|txout| txout.script_pubkey.is_op_return())?;
+                if txout.script_pubkey.len() != 1 {
+                    return None;
+                }
+                let old = txout.script_pubkey.weight_units().into_u32();
+                let new = host_script_template().weight_units().into_u32();
+                WeightUnits::no_discount(((new - old) / 4) as usize)
+            }
+            Method::TapretFirst => {
+                tx.outputs().find(|txout| txout.script_pubkey.is_p2tr())?;
+                WeightUnits::no_discount(0)
+            }
+        };
+        Some(HostCost {
+            weight,
+            min_value: self.dust_limit(dust_relay_fee),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::opcodes::OP_RETURN;
+
+    use super::*;
+    use crate::test_helpers::tx;
+
+    #[test]
+    fn host_output() {
+        assert_eq!(Method::OpretFirst.host_output_weight().into_u32(), 172);
+        assert_eq!(Method::TapretFirst.host_output_weight().into_u32(), 172);
+        assert_eq!(Method::OpretFirst.dust_limit(DEFAULT_DUST_RELAY_FEE), Sats::ZERO);
+        assert_eq!(Method::TapretFirst.dust_limit(DEFAULT_DUST_RELAY_FEE), Sats::from_sats(330u64));
+    }
+
+    #[test]
+    fn tweak() {
+        let tx = tx([ScriptPubkey::from_unsafe(vec![OP_RETURN])]);
+        let cost = Method::OpretFirst.tweak_cost(&tx, DEFAULT_DUST_RELAY_FEE).unwrap();
+        assert_eq!(cost.weight.into_u32(), 132);
+        assert_eq!(cost.fee(1000), Sats::from_sats(33u64));
+        assert_eq!(Method::TapretFirst.tweak_cost(&tx, DEFAULT_DUST_RELAY_FEE), None);
+    }
+}
@@ -0,0 +1,193 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Short human-verifiable fingerprints of commitments.
+//!
+//! Fingerprints are used to compare the commitment an online machine claims
+//! to create with the commitment displayed by an air-gapped signing device.
+//! A fingerprint is 80 bits of a tagged hash ([`FINGERPRINT_TAG`]), making
+//! it infeasible for an attacker to find another commitment with the same
+//! fingerprint, displayed as 16 characters of Crockford base32 split into four
+//! groups, like `k3x9-7fqa-m2e8-0hvd`.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::ByteArray;
+use commit_verify::{mpc, DigestExt, Sha256};
+use strict_encoding::{StrictDecode, StrictDumb, StrictEncode};
+
+use crate::tapret::TapretCommitment;
+use crate::DbcAnchor;
+
+/// Tag of the hash used to compute fingerprints.
+pub const FINGERPRINT_TAG: &str = "urn:lnp-bp:dbc:fingerprint#2024-12-05";
+
+/// Length of the fingerprint in bytes.
+pub const FINGERPRINT_LEN: usize = 10;
+
+const FINGERPRINT_CHARS: usize = FINGERPRINT_LEN * 8 / 5;
+const GROUP_LEN: usize = 4;
+
+const ALPHABET: &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+
+/// Errors parsing and checking fingerprints.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum FingerprintError {
+    /// fingerprint must contain 16 characters, while {0} characters were
+    /// provided.
+    InvalidLen(usize),
+
+    /// fingerprint contains invalid character '{0}'.
+    InvalidChar(char),
+
+    /// fingerprint {actual} doesn't match the commitment fingerprint
+    /// {expected}.
+    Mismatch {
+        /// Fingerprint of the commitment.
+        expected: Fingerprint,
+        /// Fingerprint provided for the check.
+        actual: Fingerprint,
+    },
+}
+
+/// Short human-verifiable fingerprint of a commitment.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Fingerprint([u8; FINGERPRINT_LEN]);
+
+impl Fingerprint {
+    /// Computes fingerprint of the commitment data.
+    pub fn with(data: impl AsRef<[u8]>) -> Self {
+        let mut engine = Sha256::from_tag(FINGERPRINT_TAG);
+        engine.input_raw(data.as_ref());
+        let hash = engine.finish();
+        let mut fingerprint = [0u8; FINGERPRINT_LEN];
+        fingerprint.copy_from_slice(&hash[..FINGERPRINT_LEN]);
+        Fingerprint(fingerprint)
+    }
+
+    /// Returns fingerprint bytes.
+    pub fn to_byte_array(&self) -> [u8; FINGERPRINT_LEN] { self.0 }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bits = self.0.iter().fold(0u128, |acc, byte| (acc << 8) | *byte as u128);
+        for no in 0..FINGERPRINT_CHARS {
+            if no > 0 && no % GROUP_LEN == 0 {
+                f.write_str("-")?;
+            }
+            let index = (bits >> ((FINGERPRINT_CHARS - 1 - no) * 5)) & 0x1F;
+            write!(f, "{}", ALPHABET[index as usize] as char)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = FingerprintError;
+
+    /// Parses fingerprint, ignoring the case, dashes and whitespaces. Letters
+    /// `o`, `i` and `l`, which are absent in the alphabet, are read as `0`
+    /// and `1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .map(|c| match c.to_ascii_lowercase() {
+                'o' => '0',
+                'i' | 'l' => '1',
+                c => c,
+            })
+            .collect::<Vec<_>>();
+        if chars.len() != FINGERPRINT_CHARS {
+            return Err(FingerprintError::InvalidLen(chars.len()));
+        }
+        let mut bits = 0u128;
+        for c in chars {
+            let index = ALPHABET
+                .iter()
+                .position(|a| *a as char == c)
+                .ok_or(FingerprintError::InvalidChar(c))?;
+            bits = (bits << 5) | index as u128;
+        }
+        let mut fingerprint = [0u8; FINGERPRINT_LEN];
+        fingerprint.copy_from_slice(&bits.to_be_bytes()[16 - FINGERPRINT_LEN..]);
+        Ok(Fingerprint(fingerprint))
+    }
+}
+
+/// Data which can be represented by a human-verifiable fingerprint.
+pub trait Fingerprinted {
+    /// Computes fingerprint of the data.
+    fn fingerprint(&self) -> Fingerprint;
+
+    /// Checks that the fingerprint string, as displayed by another device,
+    /// matches the data.
+    fn check_fingerprint(&self, fingerprint: &str) -> Result<(), FingerprintError> {
+        let expected = self.fingerprint();
+        let actual = Fingerprint::from_str(fingerprint)?;
+        if actual != expected {
+            return Err(FingerprintError::Mismatch { expected, actual });
+        }
+        Ok(())
+    }
+}
+
+impl<D: StrictDumb + StrictEncode + StrictDecode, L: mpc::Proof + StrictDumb> Fingerprinted
+    for DbcAnchor<D, L>
+{
+    /// Fingerprint of an anchor is computed from its [`crate::AnchorId`].
+    fn fingerprint(&self) -> Fingerprint { Fingerprint::with(self.anchor_id().to_byte_array()) }
+}
+
+impl Fingerprinted for TapretCommitment {
+    /// Fingerprint of a tapret commitment is computed from its serialized
+    /// data.
+    fn fingerprint(&self) -> Fingerprint { Fingerprint::with(self.to_array()) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let commitment = TapretCommitment::with(mpc::Commitment::from([7u8; 32]), 1);
+        let fingerprint = commitment.fingerprint();
+        let s = fingerprint.to_string();
+        assert_eq!(s.len(), 19);
+        assert_eq!(s.split('-').map(str::len).collect::<Vec<_>>(), vec![4; 4]);
+        assert_eq!(Fingerprint::from_str(&s), Ok(fingerprint));
+        assert_eq!(Fingerprint::from_str(&s.to_uppercase().replace('-', " ")), Ok(fingerprint));
+        commitment.check_fingerprint(&s).unwrap();
+
+        let other = TapretCommitment::with(mpc::Commitment::from([7u8; 32]), 2);
+        assert!(matches!(other.check_fingerprint(&s), Err(FingerprintError::Mismatch { .. })));
+        assert_eq!(Fingerprint::from_str("abc"), Err(FingerprintError::InvalidLen(3)));
+        assert_eq!(Fingerprint::from_str("abcd-efgh"), Err(FingerprintError::InvalidLen(8)));
+        assert_eq!(
+            Fingerprint::from_str("abcd-efgh-jkmn-pqru"),
+            Err(FingerprintError::InvalidChar('u'))
+        );
+    }
+}
@@ -0,0 +1,298 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Selection of the transaction outputs which may host deterministic bitcoin
+//! commitments.
+
+use bc::{Sats, Tx, Vout};
+
+use crate::fees::HostCost;
+use crate::opret::OpretProof;
+use crate::{DbcProof, Method};
+
+/// Errors computing costs of hosting a commitment.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum HostError {
+    /// transaction doesn't have output {0} suitable for hosting the
+    /// commitment.
+    NoHostOutput(HostCandidate),
+}
+
+/// Transaction output which may host a commitment.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum HostOutput {
+    /// Existing transaction output with the given number.
+    #[display("{0}")]
+    Existing(Vout),
+
+    /// New output, which has to be added to the transaction.
+    #[display("new")]
+    New,
+}
+
+/// Method applicable to a transaction together with the output which will
+/// host the commitment.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{method}:{host}")]
+pub struct HostCandidate {
+    /// Commitment method.
+    pub method: Method,
+    /// Output hosting the commitment.
+    pub host: HostOutput,
+}
+
+impl HostCandidate {
+    /// Computes costs of hosting the commitment, using the provided dust relay
+    /// fee (in satoshis per kilo-vbyte).
+    ///
+    /// Existing outputs already carry their value, so for them the minimal
+    /// value of the host output is zero and only the added weight counts.
+    ///
+    /// Errors if the candidate doesn't match the transaction, i.e. the
+    /// existing host output is absent or already contains some data.
+    pub fn cost(&self, tx: &Tx, dust_relay_fee: u64) -> Result<HostCost, HostError> {
+        match self.host {
+            HostOutput::New => Ok(self.method.new_host_cost(tx, dust_relay_fee)),
+            HostOutput::Existing(_) => self
+                .method
+                .tweak_cost(tx, dust_relay_fee)
+                .map(|cost| HostCost {
+                    min_value: Sats::ZERO,
+                    ..cost
+                })
+                .ok_or(HostError::NoHostOutput(*self)),
+        }
+    }
+}
+
+impl Method {
+    /// Detects which output of the transaction may host the commitment made
+    /// with this method.
+    ///
+    /// Opret commitment is placed into the first OP_RETURN output, which must
+    /// have no data; if the transaction has no OP_RETURN outputs a new one can
+    /// be added. Tapret commitment is placed into the first taproot output;
+    /// if there is no such output the method is not applicable.
+    pub fn host_output(self, tx: &Tx) -> Option<HostOutput> {
+        let mut outputs = tx.outputs().enumerate();
+        match self {
+            Method::OpretFirst => {
+                match outputs.find(|(_, txout)| txout.script_pubkey.is_op_return()) {
+                    None => Some(HostOutput::New),
+                    Some((no, txout)) if txout.script_pubkey.len() == 1 => {
+                        Some(HostOutput::Existing(Vout::from_u32(no as u32)))
+                    }
+                    Some(_) => None,
+                }
+            }
+            Method::TapretFirst => outputs
+                .find(|(_, txout)| txout.script_pubkey.is_p2tr())
+                .map(|(no, _)| HostOutput::Existing(Vout::from_u32(no as u32))),
+        }
+    }
+}
+
+/// Returns all methods applicable to the transaction, together with the
+/// outputs which will host the commitment for each of them.
+pub fn host_candidates(tx: &Tx) -> Vec<HostCandidate> {
+    [Method::OpretFirst, Method::TapretFirst]
+        .into_iter()
+        .filter_map(|method| method.host_output(tx).map(|host| HostCandidate { method, host }))
+        .collect()
+}
+
+/// Outputs of a transaction which look like hosting a commitment under each
+/// of the methods.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct CommitmentCandidates {
+    /// First OP_RETURN output, if it carries 32 bytes of data.
+    pub opret: Option<Vout>,
+    /// First taproot output, which may contain a tapret commitment.
+    pub tapret: Option<Vout>,
+}
+
+impl CommitmentCandidates {
+    /// Detects outputs of the transaction which may host a commitment.
+    pub fn with(tx: &Tx) -> Self {
+        let opret = tx
+            .outputs()
+            .enumerate()
+            .find(|(_, txout)| txout.script_pubkey.is_op_return())
+            .filter(|(_, txout)| {
+                OpretProof::payload(&txout.script_pubkey).is_some_and(|data| data.len() == 32)
+            })
+            .map(|(no, _)| Vout::from_u32(no as u32));
+        let tapret = Method::TapretFirst.host_output(tx).and_then(|host| match host {
+            HostOutput::Existing(vout) => Some(vout),
+            HostOutput::New => None,
+        });
+        CommitmentCandidates { opret, tapret }
+    }
+
+    /// Returns methods under which the transaction may contain a commitment.
+    pub fn methods(&self) -> Vec<Method> {
+        let mut methods = vec![];
+        if self.opret.is_some() {
+            methods.push(Method::OpretFirst);
+        }
+        if self.tapret.is_some() {
+            methods.push(Method::TapretFirst);
+        }
+        methods
+    }
+
+    /// Detects whether the transaction may contain commitments under more
+    /// than a single method.
+    #[inline]
+    pub fn is_ambiguous(&self) -> bool { self.opret.is_some() && self.tapret.is_some() }
+}
+
+/// Error indicating that a transaction may contain commitments under several
+/// methods.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(
+    "transaction contains candidates for both opret ({opret}) and tapret ({tapret}) commitments, \
+     while the proof uses {method} method"
+)]
+pub struct AmbiguousCommitment {
+    /// Method used by the proof.
+    pub method: Method,
+    /// OP_RETURN output which may contain commitment.
+    pub opret: Vout,
+    /// Taproot output which may contain commitment.
+    pub tapret: Vout,
+}
+
+impl DbcProof {
+    /// Checks that the transaction doesn't carry a commitment candidate under
+    /// a method other than the one used by the proof. Validators should use
+    /// this check to ensure that the transaction can't be interpreted as
+    /// committing to different messages under different methods.
+    ///
+    /// Taproot outputs carrying tapret commitments can't be distinguished from
+    /// other taproot outputs, so the transaction is flagged only if the proof
+    /// uses the tapret method while the first OP_RETURN output of the
+    /// transaction carries 32 bytes of data, i.e. when both hosts actually
+    /// contain a commitment.
+    pub fn check_unambiguous(&self, tx: &Tx) -> Result<(), AmbiguousCommitment> {
+        match (self, CommitmentCandidates::with(tx)) {
+            (
+                DbcProof::Tapret(_),
+                CommitmentCandidates {
+                    opret: Some(opret),
+                    tapret: Some(tapret),
+                },
+            ) => Err(AmbiguousCommitment {
+                method: self.method(),
+                opret,
+                tapret,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::opcodes::OP_RETURN;
+    use bc::ScriptPubkey;
+
+    use super::*;
+    use crate::test_helpers::{p2tr, tapret_proof, tx};
+
+    #[test]
+    fn candidates() {
+        assert_eq!(host_candidates(&tx([]))[..], [HostCandidate {
+            method: Method::OpretFirst,
+            host: HostOutput::New
+        }]);
+
+        let tx1 = tx([ScriptPubkey::op_return(&[1, 2, 3])]);
+        assert_eq!(host_candidates(&tx1), vec![]);
+
+        let tx2 = tx([p2tr(0x55), ScriptPubkey::from_unsafe(vec![OP_RETURN])]);
+        assert_eq!(host_candidates(&tx2), vec![
+            HostCandidate {
+                method: Method::OpretFirst,
+                host: HostOutput::Existing(Vout::from_u32(1))
+            },
+            HostCandidate {
+                method: Method::TapretFirst,
+                host: HostOutput::Existing(Vout::from_u32(0))
+            }
+        ]);
+        assert!(!CommitmentCandidates::with(&tx2).is_ambiguous());
+        let opret = HostCandidate {
+            method: Method::OpretFirst,
+            host: HostOutput::Existing(Vout::from_u32(1)),
+        };
+        assert!(opret.cost(&tx2, 3000).is_ok());
+        assert_eq!(opret.cost(&tx1, 3000), Err(HostError::NoHostOutput(opret)));
+
+        let tx3 = tx([p2tr(0x55), ScriptPubkey::op_return(&[0xAA; 32])]);
+        let candidates = CommitmentCandidates::with(&tx3);
+        assert_eq!(candidates.methods(), vec![Method::OpretFirst, Method::TapretFirst]);
+        assert!(candidates.is_ambiguous());
+    }
+
+    #[test]
+    fn cost() {
+        let tx = tx([p2tr(0x55), ScriptPubkey::from_unsafe(vec![OP_RETURN])]);
+        for method in [Method::OpretFirst, Method::TapretFirst] {
+            let existing = HostCandidate {
+                method,
+                host: method.host_output(&tx).unwrap(),
+            }
+            .cost(&tx, 3000)
+            .unwrap();
+            let new = HostCandidate {
+                method,
+                host: HostOutput::New,
+            }
+            .cost(&tx, 3000)
+            .unwrap();
+            assert_eq!(existing.min_value, Sats::ZERO);
+            assert!(existing.weight < new.weight);
+            assert!(existing.total(1000) < new.total(1000));
+        }
+    }
+
+    #[test]
+    fn unambiguous() {
+        let tapret = DbcProof::Tapret(tapret_proof(0));
+        let opret = DbcProof::Opret(OpretProof::default());
+
+        let tx1 = tx([p2tr(0x55), ScriptPubkey::op_return(&[0xAA; 32])]);
+        assert_eq!(opret.check_unambiguous(&tx1), Ok(()));
+        assert_eq!(
+            tapret.check_unambiguous(&tx1),
+            Err(AmbiguousCommitment {
+                method: Method::TapretFirst,
+                opret: Vout::from_u32(1),
+                tapret: Vout::from_u32(0)
+            })
+        );
+
+        let tx2 = tx([p2tr(0x55), ScriptPubkey::from_unsafe(vec![OP_RETURN])]);
+        assert_eq!(tapret.check_unambiguous(&tx2), Ok(()));
+    }
+}
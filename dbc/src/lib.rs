@@ -46,15 +46,64 @@ extern crate amplify;
 extern crate serde;
 #[macro_use]
 extern crate strict_encoding;
+#[macro_use]
 extern crate commit_verify;
 
 /// Name of the strict type library generated from the data types in this crate.
 pub const LIB_NAME_BPCORE: &str = "BPCore";
 
+pub mod armor;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod container;
+#[cfg(feature = "miniscript")]
+pub mod descriptor;
+pub mod fees;
+pub mod fingerprint;
+pub mod host;
 pub mod keytweak;
 pub mod opret;
+#[cfg(feature = "ots")]
+pub mod ots;
+pub mod psbt;
+#[cfg(feature = "schemars")]
+pub mod schema;
 pub mod sigtweak;
+pub mod stream;
+#[cfg(feature = "serde")]
+pub mod strict_serde;
 pub mod tapret;
+pub mod versioned;
 mod proof;
+mod anchor;
+mod batch;
+mod bundle;
+mod commit;
+mod dual;
+mod explain;
+mod opts;
+mod network;
+mod multichain;
+mod ownership;
+mod pruned;
+mod scan;
+#[cfg(test)]
+mod test_helpers;
 
-pub use proof::{Method, MethodParseError, Proof};
+pub use anchor::{AnchorId, DbcAnchor, DbcAnchorError, DbcAnchorMergeError};
+pub use batch::{verify_batch, BatchError, BatchReport};
+pub use bundle::{
+    AnchorBundle, AnchorBundleBuilder, AnchorBundleError, AnchorRef, BundledProof, BundledTapret,
+};
+pub use commit::{commit_to_tx, commit_to_txs, BatchAnchors, CommitError, CommitMethod};
+pub use dual::{DualProof, DualProofError, Redundancy};
+pub use explain::{CommitmentLayout, CommitmentScript, TapretReport, VerifyReport};
+pub use multichain::{ChainProof, MultiAnchor, MultiAnchorError};
+pub use network::{NetworkBound, NetworkMarker};
+pub use opts::{OpretMatch, PolicyViolation, VerifyError, VerifyOpts};
+pub use ownership::{BoundAnchor, InputBinding, InputBindingId, OwnershipError, INPUT_BINDING_TAG};
+pub use proof::{
+    decode_canonical, DbcError, DbcProof, DynProof, Method, MethodParseError, Proof, StrictBytes,
+    PROOF_MAX_LEN,
+};
+pub use scan::{scan_block, scan_tx, scan_txs, ScanRecord};
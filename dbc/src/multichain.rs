@@ -0,0 +1,227 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Anchors for a multi-protocol commitment replicated to transactions on
+//! several chains.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::SmallOrdMap;
+use bc::{Tx, Txid};
+use commit_verify::mpc::{self, MerkleBlock, Message, ProtocolId};
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+
+use crate::container::Containerized;
+use crate::{
+    DbcAnchor, DbcAnchorError, DbcError, DbcProof, NetworkMarker, Redundancy, LIB_NAME_BPCORE,
+};
+
+/// Errors of multi-chain anchors.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum MultiAnchorError {
+    /// multi-chain anchor doesn't contain proofs for any chain.
+    NoChains,
+
+    /// anchor for {0} chain uses different multi-protocol commitment proof.
+    MpcMismatch(NetworkMarker),
+
+    /// multi-chain anchor already contains a proof for {0} chain.
+    Duplicate(NetworkMarker),
+
+    /// multi-chain anchor can't contain more proofs.
+    TooManyChains,
+
+    /// witness transaction for {0} chain is not provided.
+    NoTx(NetworkMarker),
+
+    /// invalid commitment on {0} chain. Details: {1}
+    Chain(NetworkMarker, DbcAnchorError<DbcError>),
+}
+
+/// Commitment proof for one of the chains of [`MultiAnchor`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct ChainProof {
+    /// Transaction on the chain containing deterministic bitcoin commitment.
+    pub txid: Txid,
+    /// Proof of the DBC commitment.
+    pub dbc_proof: DbcProof,
+}
+
+/// Anchor of a multi-protocol commitment embedded into transactions on more
+/// than one chain, like bitcoin mainnet and a sidechain, which are using
+/// bitcoin transaction format.
+///
+/// All the chains share the same multi-protocol commitment proof, and hold
+/// the witness transaction id and DBC proof specific for the chain.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct MultiAnchor<L: mpc::Proof + StrictDumb = mpc::MerkleProof> {
+    /// Structured multi-protocol LNPBP-4 data the transactions commit to.
+    pub mpc_proof: L,
+    /// Commitment proofs for each of the chains.
+    pub chains: SmallOrdMap<NetworkMarker, ChainProof>,
+}
+
+impl<L: mpc::Proof + StrictDumb> StrictSerialize for MultiAnchor<L> {}
+impl<L: mpc::Proof + StrictDumb> StrictDeserialize for MultiAnchor<L> {}
+
+impl Containerized for MultiAnchor {
+    const CONTAINER_TAG: u16 = 0x0140;
+}
+
+impl Containerized for MultiAnchor<MerkleBlock> {
+    const CONTAINER_TAG: u16 = 0x0141;
+}
+
+impl<L: mpc::Proof + StrictDumb + Clone + Eq> MultiAnchor<L> {
+    /// Constructs multi-chain anchor from the anchor on the first chain.
+    pub fn new(network: NetworkMarker, anchor: DbcAnchor<DbcProof, L>) -> Self {
+        let proof = ChainProof {
+            txid: anchor.txid,
+            dbc_proof: anchor.dbc_proof,
+        };
+        MultiAnchor {
+            mpc_proof: anchor.mpc_proof,
+            chains: SmallOrdMap::from_checked(bmap! { network => proof }),
+        }
+    }
+
+    /// Adds anchor on another chain, which must use the same multi-protocol
+    /// commitment proof.
+    pub fn add_chain(
+        &mut self,
+        network: NetworkMarker,
+        anchor: DbcAnchor<DbcProof, L>,
+    ) -> Result<(), MultiAnchorError> {
+        if anchor.mpc_proof != self.mpc_proof {
+            return Err(MultiAnchorError::MpcMismatch(network));
+        }
+        if self.chains.contains_key(&network) {
+            return Err(MultiAnchorError::Duplicate(network));
+        }
+        let proof = ChainProof {
+            txid: anchor.txid,
+            dbc_proof: anchor.dbc_proof,
+        };
+        self.chains.insert(network, proof).map_err(|_| MultiAnchorError::TooManyChains)?;
+        Ok(())
+    }
+
+    /// Returns chains the commitment is embedded into.
+    pub fn networks(&self) -> impl Iterator<Item = NetworkMarker> + '_ {
+        self.chains.keys().copied()
+    }
+
+    /// Returns anchor for a single chain.
+    pub fn anchor(&self, network: NetworkMarker) -> Option<DbcAnchor<DbcProof, L>> {
+        let proof = self.chains.get(&network)?;
+        Some(DbcAnchor {
+            txid: proof.txid,
+            mpc_proof: self.mpc_proof.clone(),
+            dbc_proof: proof.dbc_proof.clone(),
+        })
+    }
+}
+
+impl MultiAnchor {
+    /// Verifies that the transactions on the chains commit to the message
+    /// under the given protocol, returning the multi-protocol commitment.
+    ///
+    /// With [`Redundancy::All`] a transaction must be provided and valid for
+    /// each of the chains; with [`Redundancy::Any`] it is sufficient for the
+    /// commitment to be valid on a single chain, and chains without provided
+    /// transactions are skipped.
+    pub fn verify(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        txs: &BTreeMap<NetworkMarker, Tx>,
+        redundancy: Redundancy,
+    ) -> Result<mpc::Commitment, MultiAnchorError> {
+        let mut first_err = None;
+        let mut commitment = None;
+        for network in self.networks() {
+            let anchor = self.anchor(network).expect("network is present");
+            let res = match txs.get(&network) {
+                Some(tx) => anchor
+                    .verify(protocol_id, message, tx)
+                    .map_err(|err| MultiAnchorError::Chain(network, err)),
+                None if redundancy == Redundancy::Any => continue,
+                None => Err(MultiAnchorError::NoTx(network)),
+            };
+            match (res, redundancy) {
+                (Ok(c), Redundancy::Any) => return Ok(c),
+                (Ok(c), Redundancy::All) => commitment = Some(c),
+                (Err(err), Redundancy::All) => return Err(err),
+                (Err(err), Redundancy::Any) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        commitment.ok_or_else(|| first_err.unwrap_or(MultiAnchorError::NoChains))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::LockTime;
+
+    use super::*;
+    use crate::test_helpers::tx;
+    use crate::{commit_to_txs, CommitMethod};
+
+    #[test]
+    fn verify() {
+        let tx = tx([]);
+        let mut sidechain_tx = tx.clone();
+        sidechain_tx.lock_time = LockTime::from_consensus_u32(1);
+        let pid = ProtocolId::from([1u8; 32]);
+        let msg = Message::from([11u8; 32]);
+        let (txs, anchors) = commit_to_txs(bmap! { pid => msg }, [
+            (tx, CommitMethod::Opret),
+            (sidechain_tx, CommitMethod::Opret),
+        ])
+        .unwrap();
+
+        let bitcoin = NetworkMarker::bitcoin();
+        let signet = NetworkMarker::signet();
+        let anchor = |tx: &Tx| anchors.select(tx).unwrap().to_merkle_proof(pid).unwrap();
+        let mut multi = MultiAnchor::new(bitcoin, anchor(&txs[0]));
+        multi.add_chain(signet, anchor(&txs[1])).unwrap();
+        assert_eq!(
+            multi.add_chain(signet, anchor(&txs[1])),
+            Err(MultiAnchorError::Duplicate(signet))
+        );
+
+        let all = bmap! { bitcoin => txs[0].clone(), signet => txs[1].clone() };
+        let commitment = multi.verify(pid, msg, &all, Redundancy::All).unwrap();
+        let one = bmap! { signet => txs[1].clone() };
+        assert_eq!(multi.verify(pid, msg, &one, Redundancy::Any), Ok(commitment));
+        assert_eq!(
+            multi.verify(pid, msg, &one, Redundancy::All),
+            Err(MultiAnchorError::NoTx(bitcoin))
+        );
+    }
+}
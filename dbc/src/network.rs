@@ -0,0 +1,266 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Network markers protecting from using artifacts created for one bitcoin
+//! network (like testnet) with transactions of another network.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use bc::{BlockHash, Tx};
+use commit_verify::mpc::{self, Message, ProtocolId};
+use strict_encoding::{StrictDecode, StrictDumb, StrictEncode};
+
+use crate::{
+    DbcAnchor, DbcAnchorError, DbcError, DbcProof, PolicyViolation, Proof, VerifyError, VerifyOpts,
+    LIB_NAME_BPCORE,
+};
+
+// Genesis block hashes of the well-known networks, in the internal byte order
+const GENESIS_BITCOIN: [u8; 32] = [
+    0x6f, 0xe2, 0x8c, 0x0a, 0xb6, 0xf1, 0xb3, 0x72, 0xc1, 0xa6, 0xa2, 0x46, 0xae, 0x63, 0xf7, 0x4f,
+    0x93, 0x1e, 0x83, 0x65, 0xe1, 0x5a, 0x08, 0x9c, 0x68, 0xd6, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+const GENESIS_TESTNET3: [u8; 32] = [
+    0x43, 0x49, 0x7f, 0xd7, 0xf8, 0x26, 0x95, 0x71, 0x08, 0xf4, 0xa3, 0x0f, 0xd9, 0xce, 0xc3, 0xae,
+    0xba, 0x79, 0x97, 0x20, 0x84, 0xe9, 0x0e, 0xad, 0x01, 0xea, 0x33, 0x09, 0x00, 0x00, 0x00, 0x00,
+];
+const GENESIS_TESTNET4: [u8; 32] = [
+    0x43, 0xf0, 0x8b, 0xda, 0xb0, 0x50, 0xe3, 0x5b, 0x56, 0x7c, 0x86, 0x4b, 0x91, 0xf4, 0x7f, 0x50,
+    0xae, 0x72, 0x5a, 0xe2, 0xde, 0x53, 0xbc, 0xfb, 0xba, 0xf2, 0x84, 0xda, 0x00, 0x00, 0x00, 0x00,
+];
+const GENESIS_SIGNET: [u8; 32] = [
+    0xf6, 0x1e, 0xee, 0x3b, 0x63, 0xa3, 0x80, 0xa4, 0x77, 0xa0, 0x63, 0xaf, 0x32, 0xb2, 0xbb, 0xc9,
+    0x7c, 0x9f, 0xf9, 0xf0, 0x1f, 0x2c, 0x42, 0x25, 0xe9, 0x73, 0x98, 0x81, 0x08, 0x00, 0x00, 0x00,
+];
+const GENESIS_REGTEST: [u8; 32] = [
+    0x06, 0x22, 0x6e, 0x46, 0x11, 0x1a, 0x0b, 0x59, 0xca, 0xaf, 0x12, 0x60, 0x43, 0xeb, 0x5b, 0xbf,
+    0x28, 0xc3, 0x4f, 0x3a, 0x5e, 0x33, 0x2a, 0x1f, 0xc7, 0xb2, 0xb7, 0x3c, 0xf1, 0x88, 0x91, 0x0f,
+];
+
+const KNOWN_NETWORKS: [(&str, [u8; 32]); 5] = [
+    ("bitcoin", GENESIS_BITCOIN),
+    ("testnet3", GENESIS_TESTNET3),
+    ("testnet4", GENESIS_TESTNET4),
+    ("signet", GENESIS_SIGNET),
+    ("regtest", GENESIS_REGTEST),
+];
+
+/// Marker of a bitcoin network, identified by the hash of its genesis block.
+///
+/// Displays as the network name for the well-known networks and as the
+/// genesis block hash otherwise; parses from both representations.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NetworkMarker(BlockHash);
+
+impl NetworkMarker {
+    fn known(genesis: [u8; 32]) -> Self { NetworkMarker(BlockHash::from(genesis)) }
+
+    /// Marker of bitcoin mainnet.
+    pub fn bitcoin() -> Self { Self::known(GENESIS_BITCOIN) }
+
+    /// Marker of bitcoin testnet3.
+    pub fn testnet3() -> Self { Self::known(GENESIS_TESTNET3) }
+
+    /// Marker of bitcoin testnet4.
+    pub fn testnet4() -> Self { Self::known(GENESIS_TESTNET4) }
+
+    /// Marker of the default bitcoin signet.
+    pub fn signet() -> Self { Self::known(GENESIS_SIGNET) }
+
+    /// Marker of bitcoin regtest.
+    pub fn regtest() -> Self { Self::known(GENESIS_REGTEST) }
+
+    /// Constructs marker for a network with a given genesis block hash.
+    pub fn with_genesis(genesis_hash: BlockHash) -> Self { NetworkMarker(genesis_hash) }
+
+    /// Returns genesis block hash of the network.
+    pub fn genesis_hash(&self) -> BlockHash { self.0 }
+
+    /// Returns name of the network, if it is one of the well-known networks.
+    pub fn name(&self) -> Option<&'static str> {
+        KNOWN_NETWORKS
+            .iter()
+            .find(|(_, genesis)| self.0.as_slice() == genesis)
+            .map(|(name, _)| *name)
+    }
+}
+
+impl Display for NetworkMarker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => Display::fmt(&self.0, f),
+        }
+    }
+}
+
+impl FromStr for NetworkMarker {
+    type Err = <BlockHash as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match KNOWN_NETWORKS.iter().find(|(name, _)| *name == s) {
+            Some((_, genesis)) => Ok(Self::known(*genesis)),
+            None => BlockHash::from_str(s).map(NetworkMarker),
+        }
+    }
+}
+
+/// Data (like seal definition or anchor) marked with the network it is
+/// created for.
+///
+/// The marker is checked against [`VerifyOpts::network`] during verification,
+/// such that testnet artifacts can't be validated against mainnet
+/// transactions and vice versa.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+pub struct NetworkBound<T: StrictDumb + StrictEncode + StrictDecode> {
+    /// Network the data are created for.
+    pub network: NetworkMarker,
+    /// Data bound to the network.
+    pub data: T,
+}
+
+impl<T: StrictDumb + StrictEncode + StrictDecode> NetworkBound<T> {
+    /// Marks data with the network.
+    pub fn new(network: NetworkMarker, data: T) -> Self { NetworkBound { network, data } }
+
+    /// Checks that the data are created for the network required by the
+    /// verification options, if the options require any.
+    pub fn check_network(&self, opts: &VerifyOpts) -> Result<(), PolicyViolation> {
+        opts.check_network(self.network)
+    }
+
+    /// Releases the data, dropping the network marker.
+    pub fn into_inner(self) -> T { self.data }
+}
+
+impl<D: Proof> NetworkBound<DbcAnchor<D>> {
+    /// Verifies that the anchor is created for the network required by the
+    /// options and that the transaction commits to the message under the
+    /// given protocol.
+    ///
+    /// Unlike [`DbcAnchor::verify_with`], which rejects anchors if the options
+    /// require a network, the network requirement is satisfied by the
+    /// anchor marker. See [`DbcAnchor::verify_with`] for other details.
+    pub fn verify_with(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+        opts: &VerifyOpts,
+    ) -> Result<mpc::Commitment, DbcAnchorError<VerifyError<D::Error>>> {
+        self.check_network(opts).map_err(|v| DbcAnchorError::Dbc(VerifyError::Policy(v)))?;
+        self.data.verify_with(protocol_id, message, tx, &opts.without_network())
+    }
+}
+
+impl NetworkBound<DbcAnchor<DbcProof>> {
+    /// Verifies that the anchor is created for the network required by the
+    /// options and that the transaction commits to the message under the
+    /// given protocol.
+    ///
+    /// Unlike [`DbcAnchor::verify_with`], which rejects anchors if the options
+    /// require a network, the network requirement is satisfied by the
+    /// anchor marker. See [`DbcAnchor::verify_with`] for other details.
+    pub fn verify_with(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+        opts: &VerifyOpts,
+    ) -> Result<mpc::Commitment, DbcAnchorError<VerifyError<DbcError>>> {
+        self.check_network(opts).map_err(|v| DbcAnchorError::Dbc(VerifyError::Policy(v)))?;
+        self.data.verify_with(protocol_id, message, tx, &opts.without_network())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::tx_spending;
+    use crate::{commit_to_tx, CommitMethod};
+
+    #[test]
+    fn names() {
+        assert_eq!(NetworkMarker::bitcoin().to_string(), "bitcoin");
+        assert_eq!(NetworkMarker::from_str("signet").unwrap(), NetworkMarker::signet());
+        assert_eq!(
+            NetworkMarker::from_str(
+                "00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043"
+            )
+            .unwrap(),
+            NetworkMarker::testnet4()
+        );
+        assert_eq!(NetworkMarker::regtest().name(), Some("regtest"));
+
+        let custom = NetworkMarker::with_genesis(BlockHash::from([1u8; 32]));
+        assert_eq!(custom.name(), None);
+        assert_eq!(NetworkMarker::from_str(&custom.to_string()).unwrap(), custom);
+    }
+
+    #[test]
+    fn mismatch() {
+        let opts = VerifyOpts::default().with_network(NetworkMarker::bitcoin());
+        let seal = NetworkBound::new(NetworkMarker::testnet3(), 0u8);
+        assert_eq!(
+            seal.check_network(&opts),
+            Err(PolicyViolation::NetworkMismatch {
+                expected: NetworkMarker::bitcoin(),
+                actual: NetworkMarker::testnet3(),
+            })
+        );
+        assert_eq!(seal.check_network(&VerifyOpts::default()), Ok(()));
+        assert_eq!(NetworkBound::new(NetworkMarker::bitcoin(), 0u8).check_network(&opts), Ok(()));
+    }
+
+    #[test]
+    fn anchor() {
+        let tx = tx_spending([bc::Outpoint::coinbase()], []);
+        let pid = ProtocolId::from([1u8; 32]);
+        let msg = Message::from([2u8; 32]);
+        let (tx, anchor) = commit_to_tx(bmap! { pid => msg }, tx, CommitMethod::Opret).unwrap();
+        let anchor = anchor.to_merkle_proof(pid).unwrap();
+
+        let opts = VerifyOpts::default().with_network(NetworkMarker::regtest());
+        assert_eq!(
+            anchor.verify_with(pid, msg, &tx, &opts),
+            Err(DbcAnchorError::Dbc(VerifyError::Policy(PolicyViolation::NoNetwork(
+                NetworkMarker::regtest()
+            ))))
+        );
+        let commitment = anchor.verify_with(pid, msg, &tx, &VerifyOpts::default()).unwrap();
+
+        let bound = NetworkBound::new(NetworkMarker::regtest(), anchor);
+        assert_eq!(bound.verify_with(pid, msg, &tx, &opts), Ok(commitment));
+        let opts = VerifyOpts::default().with_network(NetworkMarker::bitcoin());
+        assert!(matches!(
+            bound.verify_with(pid, msg, &tx, &opts),
+            Err(DbcAnchorError::Dbc(VerifyError::Policy(PolicyViolation::NetworkMismatch { .. })))
+        ));
+    }
+}
@@ -24,14 +24,18 @@
 mod tx;
 mod txout;
 mod spk;
+mod payload;
 
-use bc::Tx;
+use amplify::ByteArray;
+use bc::opcodes::OP_PUSHBYTES_75;
+use bc::{ScriptPubkey, Tx, Vout};
 use commit_verify::mpc::Commitment;
-use commit_verify::{CommitmentProtocol, EmbedCommitVerify, EmbedVerifyError};
+use commit_verify::CommitmentProtocol;
+pub use payload::{OpretPayload, OpretPayloadError, OPRET_PAYLOAD_LEN};
 use strict_encoding::{StrictDeserialize, StrictSerialize};
 
 use crate::proof::Method;
-use crate::{Proof, LIB_NAME_BPCORE};
+use crate::{OpretMatch, Proof, VerifyError, VerifyOpts, LIB_NAME_BPCORE};
 
 /// Marker non-instantiable enum defining LNPBP-12 taproot OP_RETURN (`tapret`)
 /// protocol.
@@ -52,22 +56,193 @@ pub enum OpretError {
     InvalidOpretScript,
 }
 
-/// Empty type for use inside [`crate::Anchor`] for opret commitment scheme.
+/// Errors during opret commitment verification.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[display(doc_comments)]
+pub enum OpretVerifyError {
+    /// transaction doesn't contain OP_RETURN output.
+    NoOpretOutput,
+
+    /// the commitment is present in the output #{0}, which is not the first
+    /// OP_RETURN output of the transaction.
+    NotFirstOpret(Vout),
+
+    /// the first OP_RETURN output of the transaction doesn't contain a single
+    /// direct push of the commitment data.
+    NonCanonicalScript,
+
+    /// the first OP_RETURN output of the transaction contains payload of {0}
+    /// bytes, while a commitment must be exactly 32 bytes long.
+    PayloadLengthMismatch(usize),
+
+    /// the first OP_RETURN output of the transaction commits to {actual}
+    /// instead of the expected commitment {expected}.
+    PayloadMismatch {
+        /// Commitment which was expected to be present in the transaction.
+        expected: Commitment,
+        /// Commitment actually present in the first OP_RETURN output.
+        actual: Commitment,
+    },
+}
+
+/// Empty type for use inside [`crate::DbcAnchor`] for opret commitment scheme.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BPCORE)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 pub struct OpretProof(());
 
 impl StrictSerialize for OpretProof {}
 impl StrictDeserialize for OpretProof {}
 
+#[cfg(feature = "serde")]
+crate::impl_strict_serde!(OpretProof);
+
+impl OpretProof {
+    /// Extracts data pushed by an OP_RETURN script.
+    ///
+    /// Returns `None` if the script is not an `OP_RETURN` followed by a single
+    /// direct push of data.
+    pub(crate) fn payload(script: &ScriptPubkey) -> Option<&[u8]> {
+        match script.get(1) {
+            Some(len) if *len <= OP_PUSHBYTES_75 && script.len() == *len as usize + 2 => {
+                Some(&script[2..])
+            }
+            _ => None,
+        }
+    }
+}
+
 impl Proof for OpretProof {
-    type Error = EmbedVerifyError<OpretError>;
+    type Error = OpretVerifyError;
 
     const METHOD: Method = Method::OpretFirst;
 
-    fn verify(&self, msg: &Commitment, tx: &Tx) -> Result<(), EmbedVerifyError<OpretError>> {
-        tx.verify(msg, self)
+    fn verify(&self, msg: &Commitment, tx: &Tx) -> Result<(), OpretVerifyError> {
+        let mut oprets = tx
+            .outputs()
+            .enumerate()
+            .filter(|(_, txout)| txout.script_pubkey.is_op_return())
+            .map(|(vout, txout)| (vout, Self::payload(&txout.script_pubkey)));
+
+        let (_, payload) = oprets.next().ok_or(OpretVerifyError::NoOpretOutput)?;
+        if payload == Some(msg.as_slice()) {
+            return Ok(());
+        }
+        if let Some((vout, _)) = oprets.find(|(_, other)| *other == Some(msg.as_slice())) {
+            return Err(OpretVerifyError::NotFirstOpret(Vout::from_u32(vout as u32)));
+        }
+        let payload = payload.ok_or(OpretVerifyError::NonCanonicalScript)?;
+        let actual = <[u8; 32]>::try_from(payload)
+            .map_err(|_| OpretVerifyError::PayloadLengthMismatch(payload.len()))?;
+        Err(OpretVerifyError::PayloadMismatch {
+            expected: *msg,
+            actual: Commitment::from_byte_array(actual),
+        })
+    }
+
+    fn verify_with(
+        &self,
+        msg: &Commitment,
+        tx: &Tx,
+        opts: &VerifyOpts,
+    ) -> Result<(), VerifyError<OpretVerifyError>> {
+        let mut oprets =
+            tx.outputs().enumerate().filter(|(_, txout)| txout.script_pubkey.is_op_return());
+        let host = match opts.opret_match {
+            OpretMatch::First => {
+                self.verify(msg, tx).map_err(VerifyError::Proof)?;
+                oprets.next()
+            }
+            OpretMatch::Any => oprets
+                .find(|(_, txout)| Self::payload(&txout.script_pubkey) == Some(msg.as_slice())),
+        };
+        if host.is_none() {
+            let err = self.verify(msg, tx).err().unwrap_or(OpretVerifyError::NoOpretOutput);
+            return Err(VerifyError::Proof(err));
+        }
+        #[cfg(feature = "tracing")]
+        if let Some((vout, _)) = host {
+            tracing::trace!(vout, "opret commitment host output");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::opcodes::OP_RETURN;
+
+    use super::*;
+    use crate::test_helpers::tx;
+
+    #[test]
+    fn valid() {
+        let msg = Commitment::from([8u8; 32]);
+        let tx = tx([ScriptPubkey::p2sh([1u8; 20]), ScriptPubkey::op_return(msg.as_slice())]);
+        assert_eq!(OpretProof::default().verify(&msg, &tx), Ok(()));
+    }
+
+    #[test]
+    fn no_opret() {
+        let msg = Commitment::from([8u8; 32]);
+        let tx = tx([ScriptPubkey::p2sh([1u8; 20])]);
+        assert_eq!(OpretProof::default().verify(&msg, &tx), Err(OpretVerifyError::NoOpretOutput));
+    }
+
+    #[test]
+    fn not_first() {
+        let msg = Commitment::from([8u8; 32]);
+        let tx = tx([ScriptPubkey::op_return(&[]), ScriptPubkey::op_return(msg.as_slice())]);
+        assert_eq!(
+            OpretProof::default().verify(&msg, &tx),
+            Err(OpretVerifyError::NotFirstOpret(Vout::from_u32(1)))
+        );
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let msg = Commitment::from([8u8; 32]);
+        let tx = tx([ScriptPubkey::op_return(&[8u8; 33])]);
+        assert_eq!(
+            OpretProof::default().verify(&msg, &tx),
+            Err(OpretVerifyError::PayloadLengthMismatch(33))
+        );
+    }
+
+    #[test]
+    fn payload_mismatch() {
+        let msg = Commitment::from([8u8; 32]);
+        let tx = tx([ScriptPubkey::op_return(&[9u8; 32])]);
+        assert_eq!(
+            OpretProof::default().verify(&msg, &tx),
+            Err(OpretVerifyError::PayloadMismatch {
+                expected: msg,
+                actual: Commitment::from([9u8; 32])
+            })
+        );
+    }
+
+    #[test]
+    fn opts() {
+        let msg = Commitment::from([8u8; 32]);
+        let tx1 = tx([ScriptPubkey::op_return(&[]), ScriptPubkey::op_return(msg.as_slice())]);
+        let opts = VerifyOpts {
+            opret_match: OpretMatch::Any,
+            ..VerifyOpts::strict()
+        };
+        assert_eq!(OpretProof::default().verify_with(&msg, &tx1, &opts), Ok(()));
+
+        let mut script = vec![OP_RETURN];
+        script.extend(msg.as_slice());
+        let tx2 = tx([ScriptPubkey::from_unsafe(script)]);
+        assert_eq!(
+            OpretProof::default().verify(&msg, &tx2),
+            Err(OpretVerifyError::NonCanonicalScript)
+        );
+        assert_eq!(
+            OpretProof::default().verify_with(&msg, &tx2, &opts),
+            Err(VerifyError::Proof(OpretVerifyError::NonCanonicalScript))
+        );
     }
 }
@@ -0,0 +1,147 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use amplify::confinement::Confined;
+use amplify::num::u5;
+use bc::ScriptPubkey;
+use commit_verify::mpc;
+use strict_encoding::{DeserializeError, StrictDeserialize, StrictSerialize};
+
+use super::OpretProof;
+use crate::LIB_NAME_BPCORE;
+
+/// Length of the serialized [`OpretPayload`].
+pub const OPRET_PAYLOAD_LEN: usize = 42;
+
+/// Errors parsing [`OpretPayload`] from a scriptPubkey.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum OpretPayloadError {
+    /// scriptPubkey is not an OP_RETURN script with a single direct push of
+    /// data.
+    NotOpret,
+
+    /// OP_RETURN payload has length of {0} bytes, while multi-protocol payload
+    /// must be exactly 42 bytes long.
+    InvalidLength(usize),
+
+    /// invalid OP_RETURN payload data. Details: {0}
+    #[from]
+    InvalidData(DeserializeError),
+}
+
+/// Typed layout of an OP_RETURN payload, which bundles LNPBP-4 multi-protocol
+/// commitment with the metadata required by the verifiers of the
+/// commitment.
+///
+/// The payload uses the following fixed byte layout:
+/// - `0..32`: multi-protocol commitment;
+/// - `32`: multi-protocol commitment method;
+/// - `33`: depth of the multi-protocol commitment tree;
+/// - `34..42`: static entropy of the multi-protocol commitment tree, in little-endian byte order.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OpretPayload {
+    /// LNPBP-4 multi-protocol commitment.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub mpc: mpc::Commitment,
+    /// Method used to construct the multi-protocol commitment.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub method: mpc::Method,
+    /// Depth of the multi-protocol commitment tree.
+    #[cfg_attr(feature = "schemars", schemars(with = "u8"))]
+    pub depth: u5,
+    /// Static entropy used in the multi-protocol commitment tree.
+    pub entropy: u64,
+}
+
+impl StrictSerialize for OpretPayload {}
+impl StrictDeserialize for OpretPayload {}
+
+impl OpretPayload {
+    /// Constructs payload from the multi-protocol commitment and its
+    /// metadata.
+    pub fn new(mpc: mpc::Commitment, method: mpc::Method, depth: u5, entropy: u64) -> Self {
+        OpretPayload {
+            mpc,
+            method,
+            depth,
+            entropy,
+        }
+    }
+
+    /// Returns serialized representation of the payload.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.to_strict_serialized::<OPRET_PAYLOAD_LEN>().expect("exact size match").release()
+    }
+
+    /// Parses payload from a serialized data, which must be exactly
+    /// [`OPRET_PAYLOAD_LEN`] bytes long.
+    pub fn from_slice(data: impl AsRef<[u8]>) -> Result<Self, OpretPayloadError> {
+        let data = data.as_ref();
+        if data.len() != OPRET_PAYLOAD_LEN {
+            return Err(OpretPayloadError::InvalidLength(data.len()));
+        }
+        let data = Confined::try_from(data.to_vec()).expect("exact size match");
+        Self::from_strict_serialized::<OPRET_PAYLOAD_LEN>(data).map_err(OpretPayloadError::from)
+    }
+
+    /// Constructs OP_RETURN scriptPubkey containing the payload.
+    pub fn to_script_pubkey(&self) -> ScriptPubkey { ScriptPubkey::op_return(&self.to_vec()) }
+
+    /// Parses payload from an OP_RETURN scriptPubkey.
+    pub fn from_script_pubkey(script_pubkey: &ScriptPubkey) -> Result<Self, OpretPayloadError> {
+        if !script_pubkey.is_op_return() {
+            return Err(OpretPayloadError::NotOpret);
+        }
+        Self::from_slice(OpretProof::payload(script_pubkey).ok_or(OpretPayloadError::NotOpret)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layout() {
+        let payload = OpretPayload::new(
+            mpc::Commitment::from([0xAC; 32]),
+            mpc::Method::Sha256t,
+            u5::with(3),
+            0x0102030405060708,
+        );
+        let data = payload.to_vec();
+        assert_eq!(data.len(), OPRET_PAYLOAD_LEN);
+        assert_eq!(&data[..32], &[0xAC; 32]);
+        assert_eq!(data[33], 3);
+        assert_eq!(&data[34..], &[8, 7, 6, 5, 4, 3, 2, 1]);
+
+        let script = payload.to_script_pubkey();
+        assert_eq!(OpretPayload::from_script_pubkey(&script), Ok(payload));
+        assert_eq!(
+            OpretPayload::from_script_pubkey(&ScriptPubkey::op_return(&[0xAC; 32])),
+            Err(OpretPayloadError::InvalidLength(32))
+        );
+    }
+}
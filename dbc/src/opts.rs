@@ -0,0 +1,170 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Policy options used during verification of deterministic bitcoin
+//! commitments.
+
+use std::error::Error;
+
+use bc::Vout;
+
+use crate::NetworkMarker;
+
+/// Selection of OP_RETURN outputs which may contain opret commitment.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, Default)]
+#[display(lowercase)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum OpretMatch {
+    /// Commitment must be present in the first OP_RETURN output, as required
+    /// by the opret1st method.
+    #[default]
+    First,
+
+    /// Commitment may be present in any of the OP_RETURN outputs.
+    Any,
+}
+
+/// Options defining verification policy.
+///
+/// Proofs and transactions do not contain information about the bitcoin
+/// network, so the network of the witness transaction must be checked by the
+/// caller when the transaction is resolved. Data marked with the network
+/// using [`crate::NetworkBound`] are checked against [`VerifyOpts::network`],
+/// while unmarked anchors are rejected if the options require a network.
+///
+/// Default options match the rules applied by [`crate::Proof::verify`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VerifyOpts {
+    /// Whether to accept commitments in non-standard scripts, like OP_RETURN
+    /// outputs not using a direct push of the commitment data.
+    pub allow_nonstandard: bool,
+    /// Maximal depth of the tapret commitment leaf in the taproot script
+    /// tree.
+    pub max_tapret_depth: u8,
+    /// Selection of OP_RETURN outputs which may contain opret commitment.
+    pub opret_match: OpretMatch,
+    /// Network which data must be created for; if `None`, the network markers
+    /// are not checked and unmarked data are accepted.
+    pub network: Option<NetworkMarker>,
+}
+
+impl Default for VerifyOpts {
+    fn default() -> Self {
+        VerifyOpts {
+            allow_nonstandard: false,
+            max_tapret_depth: 1,
+            opret_match: OpretMatch::First,
+            network: None,
+        }
+    }
+}
+
+impl VerifyOpts {
+    /// Constructs the most restrictive options: standard scripts only and
+    /// commitment in the first OP_RETURN output.
+    ///
+    /// These are the same as the default options.
+    #[inline]
+    pub fn strict() -> Self { default!() }
+
+    /// Requires network-bound data to be created for a given network.
+    pub fn with_network(mut self, network: NetworkMarker) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Returns the same options without the network requirement, used after
+    /// the network marker of the data is checked.
+    pub fn without_network(mut self) -> Self {
+        self.network = None;
+        self
+    }
+
+    /// Checks that the options don't require a network, for the data which
+    /// are not marked with one.
+    pub fn check_unmarked(&self) -> Result<(), PolicyViolation> {
+        match self.network {
+            Some(expected) => Err(PolicyViolation::NoNetwork(expected)),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that the network of the data matches the required network, if
+    /// any.
+    pub fn check_network(&self, network: NetworkMarker) -> Result<(), PolicyViolation> {
+        match self.network {
+            Some(expected) if expected != network => Err(PolicyViolation::NetworkMismatch {
+                expected,
+                actual: network,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Violations of the verification policy defined by [`VerifyOpts`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum PolicyViolation {
+    /// commitment in output {0} uses non-standard script.
+    NonStandardScript(Vout),
+
+    /// tapret commitment has depth {0} exceeding the maximal allowed depth
+    /// {1}.
+    TapretDepth(u8, u8),
+
+    /// data are created for {actual} network, while {expected} network is
+    /// required.
+    NetworkMismatch {
+        /// Network required by the verification options.
+        expected: NetworkMarker,
+        /// Network the data are created for.
+        actual: NetworkMarker,
+    },
+
+    /// data are not marked with a network, while {0} network is required.
+    NoNetwork(NetworkMarker),
+}
+
+/// Errors verifying proofs with [`VerifyOpts`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(inner)]
+pub enum VerifyError<E: Error> {
+    /// Verification policy is violated.
+    #[from]
+    Policy(PolicyViolation),
+
+    /// Invalid commitment.
+    Proof(E),
+}
+
+impl<E: Error> VerifyError<E> {
+    /// Converts proof verification error, keeping policy violations intact.
+    pub fn map_proof<F: Error>(self, f: impl FnOnce(E) -> F) -> VerifyError<F> {
+        match self {
+            VerifyError::Policy(violation) => VerifyError::Policy(violation),
+            VerifyError::Proof(err) => VerifyError::Proof(f(err)),
+        }
+    }
+}
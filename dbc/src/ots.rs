@@ -0,0 +1,779 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! OpenTimestamps attestations over anchor ids, proving that an anchor existed
+//! before a given bitcoin block independently from the confirmation of its
+//! witness transaction.
+//!
+//! Timestamps use the binary format of the OpenTimestamps protocol, so they
+//! can be exported into `.ots` files and checked with the standard clients.
+//! Only SHA256, append and prepend operations are supported, which are the
+//! only operations used by the public calendars and bitcoin attestations.
+
+use amplify::confinement::SmallBlob;
+use amplify::hex::ToHex;
+use amplify::ByteArray;
+use bc::BlockMerkleRoot;
+use commit_verify::{DigestExt, Sha256};
+use strict_encoding::{StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize};
+
+use crate::container::Containerized;
+use crate::{AnchorId, DbcAnchor, DbcProof, Proof, LIB_NAME_BPCORE};
+
+/// Magic bytes starting OpenTimestamps proof files.
+pub const OTS_FILE_MAGIC: [u8; 31] =
+    *b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+/// Version of OpenTimestamps proof files.
+pub const OTS_FILE_VERSION: u8 = 1;
+/// Tag of the bitcoin block header attestation.
+pub const OTS_BITCOIN_TAG: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+/// Tag of the attestation pending at a calendar server.
+pub const OTS_PENDING_TAG: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+/// Public OpenTimestamps calendar servers.
+pub const OTS_CALENDARS: [&str; 2] = [
+    "https://alice.btc.calendar.opentimestamps.org",
+    "https://bob.btc.calendar.opentimestamps.org",
+];
+
+const OTS_MIME: &str = "application/vnd.opentimestamps.v1";
+const OTS_TIMEOUT: u64 = 30;
+
+const TAG_ATTESTATION: u8 = 0x00;
+const TAG_SHA256: u8 = 0x08;
+const TAG_APPEND: u8 = 0xf0;
+const TAG_PREPEND: u8 = 0xf1;
+const TAG_FORK: u8 = 0xff;
+
+const MAX_DEPTH: usize = 256;
+const MAX_MSG_LEN: usize = 4096;
+const MAX_PAYLOAD_LEN: usize = 8192;
+
+/// Errors of OpenTimestamps processing.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum OtsError {
+    /// OpenTimestamps data are truncated.
+    Truncated,
+
+    /// OpenTimestamps data contain unsupported operation with tag {0}.
+    UnsupportedOp(u8),
+
+    /// OpenTimestamps data exceed the maximal allowed size or depth.
+    TooLarge,
+
+    /// OpenTimestamps data contain extra bytes after the timestamp.
+    ExtraData,
+
+    /// OpenTimestamps file has invalid header.
+    InvalidFile,
+
+    /// OpenTimestamps file timestamps other data than the anchor id.
+    DigestMismatch,
+
+    /// attestation of block {0} doesn't match the block merkle root.
+    MerkleRootMismatch(u32),
+
+    /// timestamp doesn't contain any bitcoin attestation which can be checked
+    /// against the known blocks.
+    Unverified,
+
+    /// OpenTimestamps calendar request has failed. Details: {0}
+    Calendar(String),
+
+    /// timestamp doesn't contain any attestations.
+    Empty,
+}
+
+/// HTTP client used to request OpenTimestamps calendar servers.
+pub trait OtsCalendar {
+    /// Sends POST request with the body, returning HTTP status code and the
+    /// response body.
+    fn post(&self, url: &str, body: Vec<u8>) -> Result<(i32, Vec<u8>), String>;
+
+    /// Sends GET request, returning HTTP status code and the response body.
+    fn get(&self, url: &str) -> Result<(i32, Vec<u8>), String>;
+}
+
+/// Calendar client performing HTTP requests with [`minreq`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct OtsHttpClient;
+
+impl OtsCalendar for OtsHttpClient {
+    fn post(&self, url: &str, body: Vec<u8>) -> Result<(i32, Vec<u8>), String> {
+        let resp = minreq::post(url)
+            .with_header("Accept", OTS_MIME)
+            .with_body(body)
+            .with_timeout(OTS_TIMEOUT)
+            .send()
+            .map_err(|err| err.to_string())?;
+        Ok((resp.status_code, resp.into_bytes()))
+    }
+
+    fn get(&self, url: &str) -> Result<(i32, Vec<u8>), String> {
+        let resp = minreq::get(url)
+            .with_header("Accept", OTS_MIME)
+            .with_timeout(OTS_TIMEOUT)
+            .send()
+            .map_err(|err| err.to_string())?;
+        Ok((resp.status_code, resp.into_bytes()))
+    }
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], OtsError> {
+        let end = self.pos.checked_add(len).ok_or(OtsError::Truncated)?;
+        let bytes = self.data.get(self.pos..end).ok_or(OtsError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, OtsError> { self.bytes(1).map(|bytes| bytes[0]) }
+
+    fn varuint(&mut self) -> Result<u64, OtsError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(OtsError::TooLarge)
+    }
+
+    fn varbytes(&mut self, max_len: usize) -> Result<Vec<u8>, OtsError> {
+        let len = self.varuint()?;
+        if len > max_len as u64 {
+            return Err(OtsError::TooLarge);
+        }
+        self.bytes(len as usize).map(<[u8]>::to_vec)
+    }
+
+    fn is_empty(&self) -> bool { self.pos == self.data.len() }
+}
+
+fn write_varuint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_varbytes(buf: &mut Vec<u8>, data: &[u8]) {
+    write_varuint(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+/// Operation transforming a message in the OpenTimestamps proof.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum OtsOp {
+    /// Appends data to the message.
+    Append(Vec<u8>),
+    /// Prepends data to the message.
+    Prepend(Vec<u8>),
+    /// Hashes the message with SHA256.
+    Sha256,
+}
+
+impl OtsOp {
+    /// Applies the operation to the message.
+    pub fn apply(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            OtsOp::Append(data) => [msg, data].concat(),
+            OtsOp::Prepend(data) => [data, msg].concat(),
+            OtsOp::Sha256 => {
+                let mut engine = Sha256::default();
+                engine.input_raw(msg);
+                engine.finish().to_vec()
+            }
+        }
+    }
+
+    fn read(tag: u8, cursor: &mut Cursor) -> Result<Self, OtsError> {
+        match tag {
+            TAG_SHA256 => Ok(OtsOp::Sha256),
+            TAG_APPEND => cursor.varbytes(MAX_MSG_LEN).map(OtsOp::Append),
+            TAG_PREPEND => cursor.varbytes(MAX_MSG_LEN).map(OtsOp::Prepend),
+            _ => Err(OtsError::UnsupportedOp(tag)),
+        }
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        match self {
+            OtsOp::Append(data) => {
+                buf.push(TAG_APPEND);
+                write_varbytes(buf, data);
+            }
+            OtsOp::Prepend(data) => {
+                buf.push(TAG_PREPEND);
+                write_varbytes(buf, data);
+            }
+            OtsOp::Sha256 => buf.push(TAG_SHA256),
+        }
+    }
+}
+
+/// Attestation that a message existed at some moment of time.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum OtsAttestation {
+    /// Message is the merkle root of the bitcoin block at a given height.
+    Bitcoin(u32),
+    /// Message is submitted to the calendar server with a given URI and
+    /// waits for being included into a bitcoin block.
+    Pending(String),
+    /// Attestation of unknown type, kept as is.
+    Unknown([u8; 8], Vec<u8>),
+}
+
+impl OtsAttestation {
+    fn read(cursor: &mut Cursor) -> Result<Self, OtsError> {
+        let mut tag = [0u8; 8];
+        tag.copy_from_slice(cursor.bytes(8)?);
+        let payload = cursor.varbytes(MAX_PAYLOAD_LEN)?;
+        let mut inner = Cursor {
+            data: &payload,
+            pos: 0,
+        };
+        let attestation = match tag {
+            OTS_BITCOIN_TAG => {
+                let height = inner.varuint()?;
+                OtsAttestation::Bitcoin(u32::try_from(height).map_err(|_| OtsError::TooLarge)?)
+            }
+            OTS_PENDING_TAG => {
+                let uri = inner.varbytes(MAX_PAYLOAD_LEN)?;
+                OtsAttestation::Pending(String::from_utf8(uri).map_err(|_| OtsError::InvalidFile)?)
+            }
+            _ => return Ok(OtsAttestation::Unknown(tag, payload)),
+        };
+        if !inner.is_empty() {
+            return Err(OtsError::ExtraData);
+        }
+        Ok(attestation)
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        let mut payload = vec![];
+        let tag = match self {
+            OtsAttestation::Bitcoin(height) => {
+                write_varuint(&mut payload, *height as u64);
+                OTS_BITCOIN_TAG
+            }
+            OtsAttestation::Pending(uri) => {
+                write_varbytes(&mut payload, uri.as_bytes());
+                OTS_PENDING_TAG
+            }
+            OtsAttestation::Unknown(tag, data) => {
+                payload.extend_from_slice(data);
+                *tag
+            }
+        };
+        buf.extend_from_slice(&tag);
+        write_varbytes(buf, &payload);
+    }
+}
+
+/// OpenTimestamps timestamp: a tree of operations starting from a message
+/// and leading to the attestations.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct OtsTimestamp {
+    msg: Vec<u8>,
+    ops: Vec<(OtsOp, OtsTimestamp)>,
+    attestations: Vec<OtsAttestation>,
+}
+
+impl OtsTimestamp {
+    /// Constructs empty timestamp for a message.
+    pub fn new(msg: impl Into<Vec<u8>>) -> Self {
+        OtsTimestamp {
+            msg: msg.into(),
+            ops: vec![],
+            attestations: vec![],
+        }
+    }
+
+    /// Requests timestamp over the anchor id from a calendar server, like one
+    /// of [`OTS_CALENDARS`].
+    ///
+    /// The returned timestamp contains pending attestation, which must be
+    /// upgraded with [`OtsTimestamp::upgrade`] once the calendar commits to
+    /// it in a bitcoin transaction (usually in a few hours).
+    pub fn submit(calendar: &str, anchor_id: AnchorId) -> Result<Self, OtsError> {
+        Self::submit_with(&OtsHttpClient, calendar, anchor_id)
+    }
+
+    /// Requests timestamp over the anchor id from a calendar server using the
+    /// provided calendar client.
+    ///
+    /// See [`OtsTimestamp::submit`] for the details.
+    pub fn submit_with(
+        client: &impl OtsCalendar,
+        calendar: &str,
+        anchor_id: AnchorId,
+    ) -> Result<Self, OtsError> {
+        let digest = anchor_id.to_byte_array();
+        let url = format!("{}/digest", calendar.trim_end_matches('/'));
+        let (status, body) = client.post(&url, digest.to_vec()).map_err(OtsError::Calendar)?;
+        if status != 200 {
+            return Err(OtsError::Calendar(format!("HTTP status {status}")));
+        }
+        Self::deserialize(digest, &body)
+    }
+
+    /// Returns message timestamped by the timestamp.
+    pub fn msg(&self) -> &[u8] { &self.msg }
+
+    /// Deserializes timestamp over a message from the OpenTimestamps binary
+    /// format.
+    pub fn deserialize(msg: impl Into<Vec<u8>>, data: &[u8]) -> Result<Self, OtsError> {
+        let mut cursor = Cursor { data, pos: 0 };
+        let timestamp = Self::read(msg.into(), &mut cursor, 0)?;
+        if !cursor.is_empty() {
+            return Err(OtsError::ExtraData);
+        }
+        Ok(timestamp)
+    }
+
+    /// Serializes timestamp into the OpenTimestamps binary format.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.write(&mut buf);
+        buf
+    }
+
+    /// Parses `.ots` file timestamping SHA256 digest.
+    pub fn from_ots_file(data: &[u8]) -> Result<Self, OtsError> {
+        let mut cursor = Cursor { data, pos: 0 };
+        if cursor.bytes(OTS_FILE_MAGIC.len())? != OTS_FILE_MAGIC
+            || cursor.varuint()? != OTS_FILE_VERSION as u64
+            || cursor.byte()? != TAG_SHA256
+        {
+            return Err(OtsError::InvalidFile);
+        }
+        let digest = cursor.bytes(32)?.to_vec();
+        let timestamp = Self::read(digest, &mut cursor, 0)?;
+        if !cursor.is_empty() {
+            return Err(OtsError::ExtraData);
+        }
+        Ok(timestamp)
+    }
+
+    /// Serializes timestamp into the `.ots` file format, treating the message
+    /// as SHA256 digest.
+    pub fn to_ots_file(&self) -> Vec<u8> {
+        let mut buf = OTS_FILE_MAGIC.to_vec();
+        write_varuint(&mut buf, OTS_FILE_VERSION as u64);
+        buf.push(TAG_SHA256);
+        buf.extend_from_slice(&self.msg);
+        self.write(&mut buf);
+        buf
+    }
+
+    fn read(msg: Vec<u8>, cursor: &mut Cursor, depth: usize) -> Result<Self, OtsError> {
+        if depth > MAX_DEPTH {
+            return Err(OtsError::TooLarge);
+        }
+        let mut timestamp = OtsTimestamp::new(msg);
+        loop {
+            let tag = cursor.byte()?;
+            let (tag, last) = match tag {
+                TAG_FORK => (cursor.byte()?, false),
+                tag => (tag, true),
+            };
+            if tag == TAG_ATTESTATION {
+                timestamp.attestations.push(OtsAttestation::read(cursor)?);
+            } else {
+                let op = OtsOp::read(tag, cursor)?;
+                let result = op.apply(&timestamp.msg);
+                if result.len() > MAX_MSG_LEN {
+                    return Err(OtsError::TooLarge);
+                }
+                let stamp = Self::read(result, cursor, depth + 1)?;
+                timestamp.ops.push((op, stamp));
+            }
+            if last {
+                return Ok(timestamp);
+            }
+        }
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        let mut remaining = self.attestations.len() + self.ops.len();
+        for attestation in &self.attestations {
+            remaining -= 1;
+            if remaining > 0 {
+                buf.push(TAG_FORK);
+            }
+            buf.push(TAG_ATTESTATION);
+            attestation.write(buf);
+        }
+        for (op, stamp) in &self.ops {
+            remaining -= 1;
+            if remaining > 0 {
+                buf.push(TAG_FORK);
+            }
+            op.write(buf);
+            stamp.write(buf);
+        }
+    }
+
+    /// Returns all attestations in the timestamp together with the messages
+    /// they attest.
+    pub fn attestations(&self) -> Vec<(&[u8], &OtsAttestation)> {
+        let mut list =
+            self.attestations.iter().map(|a| (self.msg.as_slice(), a)).collect::<Vec<_>>();
+        for (_, stamp) in &self.ops {
+            list.extend(stamp.attestations());
+        }
+        list
+    }
+
+    /// Detects whether the timestamp has at least one bitcoin attestation.
+    pub fn is_complete(&self) -> bool {
+        self.attestations().iter().any(|(_, a)| matches!(a, OtsAttestation::Bitcoin(_)))
+    }
+
+    /// Verifies bitcoin attestations against the merkle roots of the blocks
+    /// provided by `merkle_root_at` for a given block height, returning the
+    /// height of the earliest attesting block.
+    ///
+    /// Attestations for blocks unknown to `merkle_root_at` are skipped; if no
+    /// attestation can be checked, [`OtsError::Unverified`] is returned.
+    pub fn verify(
+        &self,
+        merkle_root_at: impl Fn(u32) -> Option<BlockMerkleRoot>,
+    ) -> Result<u32, OtsError> {
+        let mut earliest = None;
+        for (msg, attestation) in self.attestations() {
+            let OtsAttestation::Bitcoin(height) = *attestation else {
+                continue;
+            };
+            let Some(merkle_root) = merkle_root_at(height) else {
+                continue;
+            };
+            let attested = <[u8; 32]>::try_from(msg).map(BlockMerkleRoot::from);
+            if attested != Ok(merkle_root) {
+                return Err(OtsError::MerkleRootMismatch(height));
+            }
+            earliest = Some(earliest.map_or(height, |h: u32| h.min(height)));
+        }
+        earliest.ok_or(OtsError::Unverified)
+    }
+
+    /// Merges other timestamp over the same message into this one.
+    fn merge(&mut self, other: OtsTimestamp) {
+        debug_assert_eq!(self.msg, other.msg);
+        for attestation in other.attestations {
+            if !self.attestations.contains(&attestation) {
+                self.attestations.push(attestation);
+            }
+        }
+        for (op, stamp) in other.ops {
+            match self.ops.iter_mut().find(|(o, _)| *o == op) {
+                Some((_, existing)) => existing.merge(stamp),
+                None => self.ops.push((op, stamp)),
+            }
+        }
+    }
+
+    /// Requests calendar servers of the pending attestations for the
+    /// completed timestamps, replacing pending attestations with bitcoin
+    /// ones.
+    ///
+    /// Only the calendars from the `calendars` allow-list (like
+    /// [`OTS_CALENDARS`]) are requested; pending attestations of other
+    /// calendars are kept intact, since the calendar URIs come from the
+    /// timestamp data and may point to arbitrary hosts. If any of the
+    /// requests fails, the timestamp is left unmodified.
+    ///
+    /// Returns whether any of the attestations was upgraded.
+    pub fn upgrade(&mut self, calendars: &[&str]) -> Result<bool, OtsError> {
+        self.upgrade_with(&OtsHttpClient, calendars)
+    }
+
+    /// Upgrades pending attestations using the provided calendar client.
+    ///
+    /// See [`OtsTimestamp::upgrade`] for the details.
+    pub fn upgrade_with(
+        &mut self,
+        client: &impl OtsCalendar,
+        calendars: &[&str],
+    ) -> Result<bool, OtsError> {
+        let mut timestamp = self.clone();
+        let upgraded = timestamp.upgrade_pending(client, calendars)?;
+        *self = timestamp;
+        Ok(upgraded)
+    }
+
+    fn upgrade_pending(
+        &mut self,
+        client: &impl OtsCalendar,
+        calendars: &[&str],
+    ) -> Result<bool, OtsError> {
+        let mut upgraded = false;
+        for (_, stamp) in &mut self.ops {
+            upgraded |= stamp.upgrade_pending(client, calendars)?;
+        }
+        let pending = self
+            .attestations
+            .iter()
+            .filter_map(|a| match a {
+                OtsAttestation::Pending(uri) => Some(uri.clone()),
+                _ => None,
+            })
+            .filter(|uri| {
+                let uri = uri.trim_end_matches('/');
+                calendars.iter().any(|calendar| calendar.trim_end_matches('/') == uri)
+            })
+            .collect::<Vec<_>>();
+        for uri in pending {
+            let url = format!("{}/timestamp/{}", uri.trim_end_matches('/'), self.msg.to_hex());
+            let (status, body) = client.get(&url).map_err(OtsError::Calendar)?;
+            match status {
+                // The calendar has not yet committed to the message
+                404 => continue,
+                200 => {}
+                code => return Err(OtsError::Calendar(format!("HTTP status {code}"))),
+            }
+            let stamp = Self::deserialize(self.msg.clone(), &body)?;
+            if !stamp.is_complete() {
+                continue;
+            }
+            self.attestations.retain(|a| *a != OtsAttestation::Pending(uri.clone()));
+            self.merge(stamp);
+            upgraded = true;
+        }
+        Ok(upgraded)
+    }
+}
+
+/// Anchor with an attached OpenTimestamps attestation over its id.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct TimestampedAnchor<D: StrictDumb + StrictEncode + StrictDecode> {
+    /// The anchor.
+    pub anchor: DbcAnchor<D>,
+    /// Timestamp over the anchor id, serialized in the OpenTimestamps binary
+    /// format.
+    pub ots: SmallBlob,
+}
+
+impl<D: StrictDumb + StrictEncode + StrictDecode> StrictSerialize for TimestampedAnchor<D> {}
+impl<D: StrictDumb + StrictEncode + StrictDecode> StrictDeserialize for TimestampedAnchor<D> {}
+
+impl<D: Proof> Containerized for TimestampedAnchor<D> {
+    const CONTAINER_TAG: u16 = 0x0130 | D::METHOD as u16;
+}
+
+impl Containerized for TimestampedAnchor<DbcProof> {
+    const CONTAINER_TAG: u16 = 0x013F;
+}
+
+impl<D: Clone + Eq + StrictDumb + StrictEncode + StrictDecode> TimestampedAnchor<D> {
+    /// Attaches timestamp to the anchor.
+    ///
+    /// Errors if the timestamp is made over other data than the anchor id,
+    /// doesn't contain any attestations or if the serialized timestamp
+    /// exceeds 64 kiB.
+    pub fn new(anchor: DbcAnchor<D>, timestamp: &OtsTimestamp) -> Result<Self, OtsError> {
+        if timestamp.msg() != anchor.anchor_id().to_byte_array() {
+            return Err(OtsError::DigestMismatch);
+        }
+        if timestamp.attestations().is_empty() {
+            return Err(OtsError::Empty);
+        }
+        let ots = SmallBlob::try_from(timestamp.serialize()).map_err(|_| OtsError::TooLarge)?;
+        Ok(TimestampedAnchor { anchor, ots })
+    }
+
+    /// Returns the timestamp over the anchor id.
+    pub fn timestamp(&self) -> Result<OtsTimestamp, OtsError> {
+        OtsTimestamp::deserialize(self.anchor.anchor_id().to_byte_array(), &self.ots)
+    }
+
+    /// Verifies the attached timestamp, returning the height of the earliest
+    /// block attesting the anchor.
+    ///
+    /// See [`OtsTimestamp::verify`] for the details.
+    pub fn verify_timestamp(
+        &self,
+        merkle_root_at: impl Fn(u32) -> Option<BlockMerkleRoot>,
+    ) -> Result<u32, OtsError> {
+        self.timestamp()?.verify(merkle_root_at)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::opret::OpretProof;
+
+    #[test]
+    fn roundtrip() {
+        let digest = [0x11u8; 32];
+        let mut timestamp = OtsTimestamp::new(digest);
+        let appended = OtsOp::Append(vec![0xAA; 16]).apply(&digest);
+        let mut leaf = OtsTimestamp::new(OtsOp::Sha256.apply(&appended));
+        leaf.attestations.push(OtsAttestation::Bitcoin(800_000));
+        let mut branch = OtsTimestamp::new(appended);
+        branch.ops.push((OtsOp::Sha256, leaf.clone()));
+        timestamp.ops.push((OtsOp::Append(vec![0xAA; 16]), branch));
+        timestamp.attestations.push(OtsAttestation::Pending(s!("https://alice.example")));
+
+        let data = timestamp.to_ots_file();
+        let decoded = OtsTimestamp::from_ots_file(&data).unwrap();
+        assert_eq!(decoded, timestamp);
+        assert_eq!(OtsTimestamp::deserialize(digest, &timestamp.serialize()).unwrap(), timestamp);
+        assert!(decoded.is_complete());
+
+        let merkle_root = BlockMerkleRoot::from(<[u8; 32]>::try_from(leaf.msg()).unwrap());
+        assert_eq!(decoded.verify(|h| (h == 800_000).then_some(merkle_root)), Ok(800_000));
+        assert_eq!(
+            decoded.verify(|_| Some(BlockMerkleRoot::from([0u8; 32]))),
+            Err(OtsError::MerkleRootMismatch(800_000))
+        );
+        assert_eq!(decoded.verify(|_| None), Err(OtsError::Unverified));
+    }
+
+    #[test]
+    fn ots_file() {
+        // Timestamp of the genesis block coinbase transaction: the SHA256 of
+        // its txid digest is the merkle root of the genesis block
+        let data = include_bytes!("../tests/data/genesis.ots");
+        let timestamp = OtsTimestamp::from_ots_file(data).unwrap();
+        assert_eq!(
+            timestamp.msg().to_hex(),
+            "27362e66e032c731c1c8519f43063fe0e5d070db1c0c3552bb04afa18a31c6bf"
+        );
+        assert!(timestamp.is_complete());
+        assert_eq!(timestamp.to_ots_file(), data);
+
+        let genesis_merkle_root = BlockMerkleRoot::from_str(
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+        )
+        .unwrap();
+        assert_eq!(timestamp.verify(|h| (h == 0).then_some(genesis_merkle_root)), Ok(0));
+    }
+
+    /// Calendar client answering requests from a fixed map of URLs to
+    /// responses and recording the requested URLs.
+    #[derive(Default)]
+    struct StubCalendar {
+        responses: BTreeMap<String, (i32, Vec<u8>)>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl OtsCalendar for StubCalendar {
+        fn post(&self, url: &str, _body: Vec<u8>) -> Result<(i32, Vec<u8>), String> {
+            self.get(url)
+        }
+
+        fn get(&self, url: &str) -> Result<(i32, Vec<u8>), String> {
+            self.requests.borrow_mut().push(url.to_owned());
+            self.responses.get(url).cloned().ok_or_else(|| s!("connection refused"))
+        }
+    }
+
+    const CALENDAR: &str = "https://calendar.example";
+
+    fn pending() -> OtsTimestamp {
+        let mut timestamp = OtsTimestamp::new([0x11u8; 32]);
+        timestamp.attestations.push(OtsAttestation::Pending(s!(CALENDAR)));
+        timestamp
+    }
+
+    fn upgrade_url() -> String { format!("{CALENDAR}/timestamp/{}", [0x11u8; 32].to_hex()) }
+
+    #[test]
+    fn upgrade_allow_list() {
+        let client = StubCalendar::default();
+        let mut timestamp = pending();
+        let original = timestamp.clone();
+        // Calendars outside of the allow-list are never requested
+        assert_eq!(timestamp.upgrade_with(&client, &OTS_CALENDARS), Ok(false));
+        assert_eq!(timestamp, original);
+        assert!(client.requests.borrow().is_empty());
+        // Failed requests leave the timestamp unmodified
+        assert!(matches!(
+            timestamp.upgrade_with(&client, &[&format!("{CALENDAR}/")]),
+            Err(OtsError::Calendar(_))
+        ));
+        assert_eq!(timestamp, original);
+        assert_eq!(*client.requests.borrow(), vec![upgrade_url()]);
+    }
+
+    #[test]
+    fn upgrade() {
+        let msg = [0x11u8; 32];
+        let mut leaf = OtsTimestamp::new(OtsOp::Sha256.apply(&msg));
+        leaf.attestations.push(OtsAttestation::Bitcoin(800_000));
+        let mut complete = OtsTimestamp::new(msg);
+        complete.ops.push((OtsOp::Sha256, leaf));
+
+        let mut client = StubCalendar::default();
+        client.responses.insert(upgrade_url(), (404, vec![]));
+        let mut timestamp = pending();
+        assert_eq!(timestamp.upgrade_with(&client, &[CALENDAR]), Ok(false));
+        assert_eq!(timestamp, pending());
+
+        client.responses.insert(upgrade_url(), (200, complete.serialize()));
+        assert_eq!(timestamp.upgrade_with(&client, &[CALENDAR]), Ok(true));
+        assert_eq!(timestamp, complete);
+        assert!(timestamp.is_complete());
+    }
+
+    #[test]
+    fn submit() {
+        let anchor_id = DbcAnchor::<OpretProof>::strict_dumb().anchor_id();
+        let mut client = StubCalendar::default();
+        let url = format!("{CALENDAR}/digest");
+        let mut timestamp = OtsTimestamp::new(anchor_id.to_byte_array());
+        timestamp.attestations.push(OtsAttestation::Pending(s!(CALENDAR)));
+        client.responses.insert(url.clone(), (200, timestamp.serialize()));
+        assert_eq!(OtsTimestamp::submit_with(&client, CALENDAR, anchor_id), Ok(timestamp));
+
+        client.responses.insert(url, (500, vec![]));
+        assert_eq!(
+            OtsTimestamp::submit_with(&client, CALENDAR, anchor_id),
+            Err(OtsError::Calendar(s!("HTTP status 500")))
+        );
+    }
+
+    #[test]
+    fn empty_timestamp() {
+        let anchor = DbcAnchor::<OpretProof>::strict_dumb();
+        let timestamp = OtsTimestamp::new(anchor.anchor_id().to_byte_array());
+        assert_eq!(TimestampedAnchor::new(anchor.clone(), &timestamp), Err(OtsError::Empty));
+        let mut timestamp = timestamp;
+        timestamp.attestations.push(OtsAttestation::Bitcoin(1));
+        assert!(TimestampedAnchor::new(anchor, &timestamp).is_ok());
+    }
+}
@@ -0,0 +1,258 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Binding of deterministic bitcoin commitments to the transaction inputs
+//! owned by the party creating the commitment.
+//!
+//! In multi-party transactions (like CoinJoin) a single output hosts the
+//! commitment for the whole transaction, and any of the participants may claim
+//! it as their own. To prevent this, the party creating the commitment adds
+//! an [`InputBinding`] to the multi-protocol commitment under a dedicated
+//! protocol id, declaring the inputs it owns. Since the multi-protocol
+//! commitment may contain only a single message per protocol, the binding is
+//! unique for the transaction.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::SmallOrdSet;
+use amplify::{ByteArray, Bytes32};
+use bc::{Outpoint, Tx, Txid};
+use commit_verify::mpc::{self, Message, ProtocolId};
+use commit_verify::{CommitId, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{DbcAnchor, DbcAnchorError, DbcError, DbcProof, LIB_NAME_BPCORE};
+
+/// Tag of the hashes used for the input binding protocol id and messages.
+pub const INPUT_BINDING_TAG: &str = "urn:lnp-bp:dbc:input-binding#2024-12-05";
+
+/// Errors verifying commitment ownership.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum OwnershipError {
+    /// invalid commitment. Details: {0}
+    #[from]
+    DbcAnchor(DbcAnchorError<DbcError>),
+
+    /// input binding is not a part of the multi-protocol commitment.
+    NotBound,
+
+    /// witness transaction {0} doesn't spend bound input {1}.
+    NoInput(Txid, Outpoint),
+
+    /// input {0} is not declared as owning the commitment.
+    NotOwner(Outpoint),
+
+    /// no owned inputs are provided for the ownership check.
+    NoOwnedInputs,
+}
+
+/// Identifier of an [`InputBinding`], used as the multi-protocol commitment
+/// message for the binding.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct InputBindingId(
+    #[from]
+    #[from([u8; 32])]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    Bytes32,
+);
+
+impl CommitmentId for InputBindingId {
+    const TAG: &'static str = INPUT_BINDING_TAG;
+}
+
+impl From<Sha256> for InputBindingId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Declaration of the transaction inputs owned by the party creating the
+/// commitment.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = InputBindingId)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct InputBinding {
+    /// Inputs owned by the party creating the commitment.
+    #[cfg_attr(feature = "schemars", schemars(with = "std::collections::BTreeSet<Outpoint>"))]
+    pub inputs: SmallOrdSet<Outpoint>,
+}
+
+impl StrictSerialize for InputBinding {}
+impl StrictDeserialize for InputBinding {}
+
+impl InputBinding {
+    /// Constructs binding to the given inputs.
+    ///
+    /// # Panics
+    ///
+    /// If the number of inputs exceeds `u16::MAX`.
+    pub fn new(inputs: impl IntoIterator<Item = Outpoint>) -> Self {
+        InputBinding {
+            inputs: SmallOrdSet::from_iter_checked(inputs),
+        }
+    }
+
+    /// Returns protocol id under which the binding is committed.
+    pub fn protocol_id() -> ProtocolId {
+        ProtocolId::from_byte_array(Sha256::from_tag(INPUT_BINDING_TAG).finish())
+    }
+
+    /// Returns identifier of the binding.
+    pub fn binding_id(&self) -> InputBindingId { self.commit_id() }
+
+    /// Computes multi-protocol commitment message for the binding, which is
+    /// the binding identifier.
+    pub fn message(&self) -> Message { Message::from_byte_array(self.binding_id().to_byte_array()) }
+
+    /// Adds the binding to the messages of the multi-protocol commitment.
+    pub fn add_to(&self, messages: &mut BTreeMap<ProtocolId, Message>) {
+        messages.insert(Self::protocol_id(), self.message());
+    }
+
+    /// Checks that the transaction spends all the bound inputs.
+    pub fn check_tx(&self, tx: &Tx) -> Result<(), OwnershipError> {
+        for outpoint in &self.inputs {
+            if !tx.inputs().any(|txin| txin.prev_output == *outpoint) {
+                return Err(OwnershipError::NoInput(tx.txid(), *outpoint));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Anchor extended with the binding of the commitment to the inputs of the
+/// witness transaction.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct BoundAnchor {
+    /// Anchor of the commitment.
+    pub anchor: DbcAnchor<DbcProof>,
+    /// Inputs owned by the party creating the commitment.
+    pub binding: InputBinding,
+    /// Proof of the binding inclusion into the multi-protocol commitment.
+    pub binding_proof: mpc::MerkleProof,
+}
+
+impl StrictSerialize for BoundAnchor {}
+impl StrictDeserialize for BoundAnchor {}
+
+impl BoundAnchor {
+    /// Constructs bound anchor for a protocol from the anchor revealing both
+    /// the protocol and the binding.
+    pub fn new(
+        anchor: &DbcAnchor<DbcProof, mpc::MerkleBlock>,
+        protocol_id: ProtocolId,
+        binding: InputBinding,
+    ) -> Result<Self, mpc::LeafNotKnown> {
+        Ok(BoundAnchor {
+            anchor: anchor.to_merkle_proof(protocol_id)?,
+            binding_proof: anchor.mpc_proof.to_merkle_proof(InputBinding::protocol_id())?,
+            binding,
+        })
+    }
+
+    /// Verifies that the transaction commits to the message under the given
+    /// protocol, that the same commitment contains the input binding and that
+    /// the transaction spends all the bound inputs.
+    pub fn verify(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+    ) -> Result<mpc::Commitment, OwnershipError> {
+        let commitment = self.anchor.verify(protocol_id, message, tx)?;
+        let bound = self
+            .binding_proof
+            .convolve(InputBinding::protocol_id(), self.binding.message())
+            .map_err(|_| OwnershipError::NotBound)?;
+        if bound != commitment {
+            return Err(OwnershipError::NotBound);
+        }
+        self.binding.check_tx(tx)?;
+        Ok(commitment)
+    }
+
+    /// Verifies the commitment like [`Self::verify`], additionally checking
+    /// that the commitment is owned by the party spending the given inputs.
+    ///
+    /// Errors if no owned inputs are provided, since an empty set of inputs
+    /// can't prove the ownership.
+    pub fn verify_owner(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        tx: &Tx,
+        owned: &[Outpoint],
+    ) -> Result<mpc::Commitment, OwnershipError> {
+        if owned.is_empty() {
+            return Err(OwnershipError::NoOwnedInputs);
+        }
+        let commitment = self.verify(protocol_id, message, tx)?;
+        if let Some(outpoint) = owned.iter().find(|o| !self.binding.inputs.contains(*o)) {
+            return Err(OwnershipError::NotOwner(*outpoint));
+        }
+        Ok(commitment)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::Vout;
+
+    use super::*;
+    use crate::test_helpers::tx_spending;
+    use crate::{commit_to_tx, CommitMethod};
+
+    #[test]
+    fn ownership() {
+        let mine = Outpoint::new(Txid::from([1u8; 32]), Vout::from_u32(0));
+        let theirs = Outpoint::new(Txid::from([2u8; 32]), Vout::from_u32(0));
+        let tx = tx_spending([mine, theirs], []);
+        let pid = ProtocolId::from([3u8; 32]);
+        let msg = Message::from([4u8; 32]);
+        let binding = InputBinding::new([mine]);
+        let mut messages = bmap! { pid => msg };
+        binding.add_to(&mut messages);
+        let (tx, anchor) = commit_to_tx(messages, tx, CommitMethod::Opret).unwrap();
+
+        let bound = BoundAnchor::new(&anchor, pid, binding).unwrap();
+        let commitment = bound.verify_owner(pid, msg, &tx, &[mine]).unwrap();
+        assert_eq!(bound.verify(pid, msg, &tx), Ok(commitment));
+        assert_eq!(
+            bound.verify_owner(pid, msg, &tx, &[theirs]),
+            Err(OwnershipError::NotOwner(theirs))
+        );
+        assert_eq!(bound.verify_owner(pid, msg, &tx, &[]), Err(OwnershipError::NoOwnedInputs));
+
+        let mut forged = bound.clone();
+        forged.binding = InputBinding::new([theirs]);
+        assert_eq!(forged.verify(pid, msg, &tx), Err(OwnershipError::NotBound));
+    }
+}
@@ -20,14 +20,21 @@
 // limitations under the License.
 
 use std::error::Error;
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io;
 use std::str::FromStr;
 
+use amplify::confinement::{Confined, U24};
 use bc::Tx;
-use commit_verify::mpc;
-use strict_encoding::{StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize};
+use commit_verify::{mpc, ConvolveVerifyError};
+use strict_encoding::{
+    DecodeError, DeserializeError, SerializeError, StreamReader, StreamWriter, StrictDecode,
+    StrictDeserialize, StrictDumb, StrictEncode, StrictReader, StrictSerialize, StrictWriter,
+};
 
-use crate::LIB_NAME_BPCORE;
+use crate::opret::{OpretProof, OpretVerifyError};
+use crate::tapret::TapretProof;
+use crate::{VerifyError, VerifyOpts, LIB_NAME_BPCORE};
 
 /// wrong deterministic bitcoin commitment closing method id '{0}'.
 #[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
@@ -42,6 +49,7 @@ pub struct MethodParseError(pub String);
 /// - used hash functions.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BPCORE, tags = repr, into_u8, try_from_u8)]
 #[repr(u8)]
@@ -80,4 +88,266 @@ pub trait Proof: Clone + Eq + Debug + StrictSerialize + StrictDeserialize + Stri
 
     /// Verifies DBC proof against the provided transaction.
     fn verify(&self, msg: &mpc::Commitment, tx: &Tx) -> Result<(), Self::Error>;
+
+    /// Verifies DBC proof against the provided transaction, applying
+    /// verification policy defined by the options.
+    ///
+    /// The default implementation verifies the proof with [`Proof::verify`]
+    /// and applies no method-specific options; proof types supporting such
+    /// options must override it.
+    fn verify_with(
+        &self,
+        msg: &mpc::Commitment,
+        tx: &Tx,
+        _opts: &VerifyOpts,
+    ) -> Result<(), VerifyError<Self::Error>> {
+        self.verify(msg, tx).map_err(VerifyError::Proof)
+    }
+}
+
+/// Object-safe version of the [`Proof`] trait, allowing to keep proofs of
+/// different types behind `dyn DynProof`.
+///
+/// The trait is implemented for all [`Proof`] types with sendable errors and
+/// for [`DbcProof`].
+pub trait DynProof: Debug {
+    /// Returns a single-use seal closing method used by the DBC proof.
+    fn method(&self) -> Method;
+
+    /// Verifies DBC proof against the provided transaction.
+    fn verify_dyn(
+        &self,
+        msg: &mpc::Commitment,
+        tx: &Tx,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+impl<P: Proof> DynProof for P
+where P::Error: Send + Sync + 'static
+{
+    #[inline]
+    fn method(&self) -> Method { P::METHOD }
+
+    fn verify_dyn(
+        &self,
+        msg: &mpc::Commitment,
+        tx: &Tx,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Proof::verify(self, msg, tx).map_err(Box::from)
+    }
+}
+
+impl DynProof for DbcProof {
+    #[inline]
+    fn method(&self) -> Method { DbcProof::method(self) }
+
+    fn verify_dyn(
+        &self,
+        msg: &mpc::Commitment,
+        tx: &Tx,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        DbcProof::verify(self, msg, tx).map_err(Box::from)
+    }
+}
+
+/// Maximal length of a serialized DBC proof accepted by [`decode_canonical`].
+pub const PROOF_MAX_LEN: usize = U24;
+
+/// Deserializes proof, anchor or other data structure, ensuring that the data
+/// are not longer than [`PROOF_MAX_LEN`], are entirely consumed and represent
+/// the canonical encoding of the decoded value (i.e. re-encoding it produces
+/// the same bytes).
+///
+/// Guarantees that two different byte strings can't be decoded into the same
+/// value.
+pub fn decode_canonical<T: StrictSerialize + StrictDeserialize>(
+    data: impl AsRef<[u8]>,
+) -> Result<T, DeserializeError> {
+    let data = data.as_ref();
+    if data.len() > PROOF_MAX_LEN {
+        return Err(DecodeError::DataIntegrityError(s!("data exceed maximal proof length")).into());
+    }
+    let confined = Confined::<Vec<u8>, 0, PROOF_MAX_LEN>::from_checked(data.to_vec());
+    let value = T::from_strict_serialized::<PROOF_MAX_LEN>(confined)?;
+    let reencoded = value
+        .to_strict_serialized::<PROOF_MAX_LEN>()
+        .map_err(|_| DecodeError::DataIntegrityError(s!("decoded data can't be re-encoded")))?;
+    if reencoded.as_slice() != data {
+        return Err(DecodeError::DataIntegrityError(s!("non-canonical data encoding")).into());
+    }
+    Ok(value)
+}
+
+/// Conversion of seals, proofs, anchors and other strict-encodable data from
+/// and to byte slices, for use in databases and network messages.
+///
+/// Implemented for all types supporting strict serialization.
+pub trait StrictBytes: StrictSerialize + StrictDeserialize {
+    /// Serializes data into a byte vector.
+    ///
+    /// # Panics
+    ///
+    /// If the serialized data exceed [`PROOF_MAX_LEN`].
+    fn to_vec(&self) -> Vec<u8> {
+        self.to_strict_serialized::<PROOF_MAX_LEN>()
+            .expect("data exceed maximal proof length")
+            .release()
+    }
+
+    /// Deserializes data from a byte slice, requiring the slice to be
+    /// entirely consumed and to contain the canonical encoding of the data.
+    ///
+    /// See [`decode_canonical`] for the details.
+    #[inline]
+    fn from_slice(data: &[u8]) -> Result<Self, DeserializeError> { decode_canonical(data) }
+
+    /// Serializes data into a fixed-size buffer, returning the length of the
+    /// serialized data.
+    ///
+    /// Doesn't allocate unless the type itself contains heap-allocated
+    /// collections, which makes it usable by embedded verifiers for seals and
+    /// proofs. Errors if the data don't fit the buffer.
+    fn to_buf<const LEN: usize>(&self, buf: &mut [u8; LEN]) -> Result<usize, SerializeError> {
+        let mut cursor = io::Cursor::new(&mut buf[..]);
+        self.strict_encode(StrictWriter::with(StreamWriter::new::<LEN>(&mut cursor)))?;
+        Ok(cursor.position() as usize)
+    }
+
+    /// Deserializes data from a byte slice without heap allocations (unless
+    /// the type itself contains heap-allocated collections), requiring the
+    /// slice to be entirely consumed.
+    ///
+    /// Unlike [`StrictBytes::from_slice`], doesn't re-encode the data to check
+    /// the canonicity of the encoding, which is guaranteed for the types of a
+    /// fixed size.
+    fn from_buf(data: &[u8]) -> Result<Self, DeserializeError> {
+        let mut cursor = io::Cursor::new(data);
+        let value = {
+            let mut reader = StrictReader::with(StreamReader::new::<PROOF_MAX_LEN>(&mut cursor));
+            Self::strict_decode(&mut reader)?
+        };
+        if cursor.position() as usize != data.len() {
+            return Err(DeserializeError::DataNotEntirelyConsumed);
+        }
+        Ok(value)
+    }
+}
+
+impl<T: StrictSerialize + StrictDeserialize> StrictBytes for T {}
+
+/// Errors verifying [`DbcProof`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(inner)]
+pub enum DbcError {
+    /// Invalid tapret commitment.
+    #[from]
+    Tapret(ConvolveVerifyError),
+
+    /// Invalid opret commitment.
+    #[from]
+    Opret(OpretVerifyError),
+}
+
+/// Deterministic bitcoin commitment proof for any of the supported methods.
+///
+/// Allows handling proofs without knowing the method used by the commitment
+/// at the compile time.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, From)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE, tags = custom, dumb = Self::Opret(strict_dumb!()))]
+pub enum DbcProof {
+    /// Opret commitment proof.
+    #[from]
+    #[strict_type(tag = 0x00)]
+    Opret(OpretProof),
+
+    /// Tapret commitment proof.
+    #[from]
+    #[strict_type(tag = 0x01)]
+    Tapret(TapretProof),
+}
+
+impl StrictSerialize for DbcProof {}
+impl StrictDeserialize for DbcProof {}
+
+#[cfg(feature = "serde")]
+crate::impl_strict_serde!(DbcProof);
+
+impl Display for DbcProof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DbcProof::Opret(_) => Display::fmt(&Method::OpretFirst, f),
+            DbcProof::Tapret(proof) => write!(
+                f,
+                "{}:{}#{}",
+                Method::TapretFirst,
+                proof.internal_pk,
+                proof.path_proof.nonce()
+            ),
+        }
+    }
+}
+
+impl DbcProof {
+    /// Returns a single-use seal closing method used by the DBC proof.
+    pub fn method(&self) -> Method {
+        match self {
+            DbcProof::Opret(_) => OpretProof::METHOD,
+            DbcProof::Tapret(_) => TapretProof::METHOD,
+        }
+    }
+
+    /// Verifies DBC proof against the provided transaction.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(method = %self.method(), txid = %tx.txid()),
+        err(level = "debug")
+    ))]
+    pub fn verify(&self, msg: &mpc::Commitment, tx: &Tx) -> Result<(), DbcError> {
+        match self {
+            DbcProof::Opret(proof) => Proof::verify(proof, msg, tx).map_err(DbcError::from),
+            DbcProof::Tapret(proof) => Proof::verify(proof, msg, tx).map_err(DbcError::from),
+        }
+    }
+
+    /// Verifies DBC proof against the provided transaction, applying
+    /// verification policy defined by the options.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(method = %self.method(), txid = %tx.txid()),
+        err(level = "debug")
+    ))]
+    pub fn verify_with(
+        &self,
+        msg: &mpc::Commitment,
+        tx: &Tx,
+        opts: &VerifyOpts,
+    ) -> Result<(), VerifyError<DbcError>> {
+        match self {
+            DbcProof::Opret(proof) => {
+                proof.verify_with(msg, tx, opts).map_err(|err| err.map_proof(DbcError::from))
+            }
+            DbcProof::Tapret(proof) => {
+                proof.verify_with(msg, tx, opts).map_err(|err| err.map_proof(DbcError::from))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fixed_buf() {
+        let proof = DbcProof::Opret(OpretProof::default());
+        let mut buf = [0u8; 8];
+        let len = proof.to_buf(&mut buf).unwrap();
+        assert_eq!(&buf[..len], proof.to_vec().as_slice());
+        assert_eq!(DbcProof::from_buf(&buf[..len]).unwrap(), proof);
+        assert!(DbcProof::from_buf(&buf[..len + 1]).is_err());
+        assert!(proof.to_buf(&mut [0u8; 0]).is_err());
+    }
 }
@@ -0,0 +1,118 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification against transaction outputs only, for light clients and
+//! pruned indexers which do not store full transactions.
+//!
+//! Both opret and tapret commitments are contained in the transaction
+//! outputs, so the inputs are not required for the verification. Since the
+//! transaction id can't be computed from the outputs, the caller is
+//! responsible for the outputs really belonging to the transaction with the
+//! provided id.
+
+use bc::{LockTime, Tx, TxOut, TxVer, Txid, VarIntArray};
+use commit_verify::mpc::{self, Message, ProtocolId};
+
+use crate::{DbcAnchor, DbcAnchorError, DbcError, DbcProof, Proof};
+
+fn outputs_tx(outputs: &[TxOut]) -> Tx {
+    Tx {
+        version: TxVer::V2,
+        inputs: empty!(),
+        outputs: VarIntArray::from_iter_checked(outputs.iter().cloned()),
+        lock_time: LockTime::ZERO,
+    }
+}
+
+impl DbcProof {
+    /// Verifies DBC proof against the outputs of a transaction.
+    pub fn verify_outputs(&self, msg: &mpc::Commitment, outputs: &[TxOut]) -> Result<(), DbcError> {
+        self.verify(msg, &outputs_tx(outputs))
+    }
+}
+
+impl<D: Proof> DbcAnchor<D> {
+    /// Verifies that the transaction with the given id and outputs commits to
+    /// the message under the given protocol, returning the multi-protocol
+    /// commitment contained in the transaction.
+    pub fn verify_outputs(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        txid: Txid,
+        outputs: &[TxOut],
+    ) -> Result<mpc::Commitment, DbcAnchorError<D::Error>> {
+        check_txid(self.txid, txid)?;
+        let commitment = self.convolve(protocol_id, message)?;
+        self.dbc_proof.verify(&commitment, &outputs_tx(outputs)).map_err(DbcAnchorError::Dbc)?;
+        Ok(commitment)
+    }
+}
+
+impl DbcAnchor<DbcProof> {
+    /// Verifies that the transaction with the given id and outputs commits to
+    /// the message under the given protocol, returning the multi-protocol
+    /// commitment contained in the transaction.
+    pub fn verify_outputs(
+        &self,
+        protocol_id: ProtocolId,
+        message: Message,
+        txid: Txid,
+        outputs: &[TxOut],
+    ) -> Result<mpc::Commitment, DbcAnchorError<DbcError>> {
+        check_txid(self.txid, txid)?;
+        let commitment = self.convolve(protocol_id, message)?;
+        self.dbc_proof.verify_outputs(&commitment, outputs).map_err(DbcAnchorError::Dbc)?;
+        Ok(commitment)
+    }
+}
+
+fn check_txid<E: std::error::Error>(expected: Txid, actual: Txid) -> Result<(), DbcAnchorError<E>> {
+    if expected != actual {
+        return Err(DbcAnchorError::TxidMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_helpers::tx;
+    use crate::{commit_to_tx, CommitMethod};
+
+    #[test]
+    fn outputs_only() {
+        let tx = tx([]);
+        let pid = ProtocolId::from([1u8; 32]);
+        let msg = Message::from([11u8; 32]);
+        let (tx, anchor) = commit_to_tx(bmap! { pid => msg }, tx, CommitMethod::Opret).unwrap();
+        let anchor = anchor.to_merkle_proof(pid).unwrap();
+
+        let outputs = tx.outputs.to_vec();
+        let commitment = anchor.verify_outputs(pid, msg, tx.txid(), &outputs).unwrap();
+        assert_eq!(anchor.verify(pid, msg, &tx), Ok(commitment));
+        assert!(matches!(
+            anchor.verify_outputs(pid, msg, Txid::from([0u8; 32]), &outputs),
+            Err(DbcAnchorError::TxidMismatch { .. })
+        ));
+        assert!(anchor.verify_outputs(pid, msg, tx.txid(), &[]).is_err());
+    }
+}
@@ -0,0 +1,561 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use amplify::confinement::MediumOrdMap;
+use amplify::num::u5;
+use bc::{InternalPk, ScriptPubkey, Tx, Vout};
+use commit_verify::mpc::{self, Message, ProtocolId, MPC_MINIMAL_DEPTH};
+use commit_verify::{CommitId, ConvolveCommit, EmbedCommitVerify, TryCommitVerify};
+
+use super::{DbcPsbtOutput, ProprietaryMap, PsbtKeyError};
+use crate::opret::{OpretError, OpretProof};
+use crate::tapret::{TapretCommitment, TapretNodePartner, TapretPathProof};
+use crate::{DbcAnchor, DbcError, DbcProof, Method};
+
+/// Errors in marking PSBT outputs as commitment hosts.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PsbtHostError {
+    /// PSBT has no outputs marked as a commitment host.
+    NoHost,
+
+    /// PSBT doesn't have output #{0}.
+    UnknownOutput(Vout),
+
+    /// both outputs #{0} and #{1} are marked as commitment hosts.
+    MultipleHosts(Vout, Vout),
+
+    /// output #{0} is marked both as opret and tapret commitment host.
+    ConflictingMarks(Vout),
+
+    /// output #{0} is marked as a tapret host, but it is not a taproot output.
+    NonTaprootHost(Vout),
+
+    /// output #{0} is marked as an opret host, but it is not an empty OP_RETURN
+    /// output.
+    NonEmptyOpret(Vout),
+
+    /// {0}
+    #[from]
+    Key(PsbtKeyError),
+}
+
+/// Errors embedding commitments into PSBTs and producing anchors from them.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PsbtDbcError {
+    /// invalid commitment host. Details: {0}
+    #[from]
+    Host(PsbtHostError),
+
+    /// output #{0} already contains a commitment.
+    AlreadyCommitted(Vout),
+
+    /// output #{0} doesn't contain a commitment.
+    NotCommitted(Vout),
+
+    /// tapret host output #{0} doesn't provide taproot internal key.
+    NoInternalKey(Vout),
+
+    /// script tree of the tapret host output #{0} can't host a tapret commitment.
+    TapretUnsuitable(Vout),
+
+    /// tapret host output #{0} doesn't contain tapret proof.
+    NoTapretProof(Vout),
+
+    /// multi-protocol commitment data in output #{0} don't match the commitment.
+    CommitmentMismatch(Vout),
+
+    /// {0}
+    #[from]
+    Key(PsbtKeyError),
+
+    /// invalid multi-protocol commitment. Details: {0}
+    #[from]
+    Mpc(mpc::Error),
+
+    /// invalid opret host. Details: {0}
+    #[from]
+    Opret(OpretError),
+
+    /// finalized transaction doesn't contain the commitment. Details: {0}
+    #[from]
+    Dbc(DbcError),
+
+    /// PSBT v2 doesn't allow modification of its outputs.
+    OutputsNotModifiable,
+
+    /// PSBT is not finalized.
+    NotFinalized,
+}
+
+/// Version of a PSBT.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, Default)]
+pub enum PsbtVer {
+    /// PSBT version 0 (BIP-174), containing unsigned transaction in the global
+    /// map.
+    #[default]
+    #[display("v0")]
+    V0 = 0,
+
+    /// PSBT version 2 (BIP-370), keeping transaction data in independent input
+    /// and output maps.
+    #[display("v2")]
+    V2 = 2,
+}
+
+/// Flag of BIP-370 `PSBT_GLOBAL_TX_MODIFIABLE` field allowing modification of
+/// the PSBT outputs.
+pub const PSBT_TX_MODIFIABLE_OUTPUTS: u8 = 0x02;
+
+/// PSBT output providing access to the data required for hosting deterministic
+/// bitcoin commitments.
+pub trait PsbtOutput: ProprietaryMap + Clone {
+    /// Returns scriptPubkey of the output.
+    fn script_pubkey(&self) -> &ScriptPubkey;
+
+    /// Replaces scriptPubkey of the output.
+    fn set_script_pubkey(&mut self, script_pubkey: ScriptPubkey);
+
+    /// Returns taproot internal key of the output, if known.
+    fn tap_internal_key(&self) -> Option<InternalPk>;
+
+    /// Returns partner node of the tapret commitment derived from the taproot
+    /// script tree of the output, or `None` if the output has no script tree.
+    fn tapret_partner(&self) -> Option<TapretNodePartner>;
+}
+
+/// PSBT providing access to its outputs.
+pub trait DbcPsbt {
+    /// Type of PSBT outputs.
+    type Output: PsbtOutput;
+
+    /// Iterates over PSBT outputs, in the order of the transaction outputs.
+    fn outputs(&self) -> impl Iterator<Item = &Self::Output>;
+
+    /// Returns mutable reference to an output with a given number.
+    fn output_mut(&mut self, vout: Vout) -> Option<&mut Self::Output>;
+
+    /// Returns version of the PSBT.
+    fn version(&self) -> PsbtVer { PsbtVer::V0 }
+
+    /// Returns value of BIP-370 `PSBT_GLOBAL_TX_MODIFIABLE` field for PSBT v2.
+    ///
+    /// Not used for PSBT v0.
+    fn tx_modifiable(&self) -> u8 { 0 }
+
+    /// Extracts finalized transaction, returning `None` if the PSBT is not
+    /// finalized or the implementation doesn't support extraction.
+    fn finalized_tx(&self) -> Option<Tx> { None }
+}
+
+/// High-level API embedding deterministic bitcoin commitments into PSBTs and
+/// producing anchors once the PSBTs are finalized.
+///
+/// Works with both PSBT v0 and v2, since it accesses only the output maps of
+/// the PSBT; PSBT v0 implementations of [`PsbtOutput::set_script_pubkey`] must
+/// update the unsigned transaction as well.
+///
+/// The commitment host is selected among the outputs marked with
+/// [`DbcPsbtOutput::set_opret_host`] or [`DbcPsbtOutput::set_tapret_host`].
+/// All data required to produce the anchor are kept in the proprietary keys of
+/// the host output, so the commitment and anchor production may happen in
+/// different processes.
+#[derive(Debug)]
+pub struct DbcPsbtFacade<'psbt, P: DbcPsbt> {
+    psbt: &'psbt mut P,
+}
+
+impl<'psbt, P: DbcPsbt> DbcPsbtFacade<'psbt, P> {
+    /// Constructs facade for a PSBT.
+    pub fn new(psbt: &'psbt mut P) -> Self { DbcPsbtFacade { psbt } }
+
+    /// Marks output as the host of the commitment using a given method,
+    /// ensuring that the output is suitable for the method and there are no
+    /// other hosts.
+    pub fn mark_host(&mut self, vout: Vout, method: Method) -> Result<(), PsbtHostError> {
+        if let Some((other, _)) = psbt_hosts(&*self.psbt).find(|(other, _)| *other != vout) {
+            return Err(PsbtHostError::MultipleHosts(other, vout));
+        }
+        let output = self.psbt.output_mut(vout).ok_or(PsbtHostError::UnknownOutput(vout))?;
+        let key = match method {
+            Method::OpretFirst => super::PropKey::opret_host(),
+            Method::TapretFirst => super::PropKey::tapret_host(),
+        };
+        if output.set_proprietary(key.clone(), vec![])?.is_some() {
+            return self.host().map(|_| ());
+        }
+        self.host().map(|_| ()).inspect_err(|_| {
+            // Do not leave unsuitable output marked
+            let output = self.psbt.output_mut(vout).expect("output is present");
+            output.remove_proprietary(&key);
+        })
+    }
+
+    /// Returns host output of the commitment and the commitment method,
+    /// validating that there is a single host and that it is structurally
+    /// suitable for the commitment method.
+    #[inline]
+    pub fn host(&self) -> Result<(Vout, Method), PsbtHostError> { psbt_host(&*self.psbt) }
+
+    /// Builds multi-protocol commitment tree for the messages and embeds its
+    /// commitment into the host output, returning the commitment.
+    ///
+    /// Messages already present in the host output are committed to as well.
+    /// PSBT v2 must allow modification of its outputs. The commitment is
+    /// produced on a copy of the host output, thus on error the PSBT is left
+    /// unmodified.
+    pub fn commit(
+        &mut self,
+        messages: BTreeMap<ProtocolId, Message>,
+    ) -> Result<mpc::Commitment, PsbtDbcError> {
+        if self.psbt.version() == PsbtVer::V2
+            && self.psbt.tx_modifiable() & PSBT_TX_MODIFIABLE_OUTPUTS == 0
+        {
+            return Err(PsbtDbcError::OutputsNotModifiable);
+        }
+        let (vout, method) = self.host()?;
+        let host = self.psbt.output_mut(vout).expect("host output is present");
+        let mut output = host.clone();
+        if output.mpc_commitment()?.is_some() {
+            return Err(PsbtDbcError::AlreadyCommitted(vout));
+        }
+        for (protocol_id, message) in messages {
+            output.set_mpc_message(protocol_id, message)?;
+        }
+        if output.mpc_entropy()?.is_none() {
+            output.set_mpc_entropy(secp256k1::rand::random())?;
+        }
+        let tree = mpc_tree(&output)?;
+        let commitment = tree.commit_id();
+
+        match method {
+            Method::OpretFirst => {
+                let mut script_pubkey = output.script_pubkey().clone();
+                script_pubkey.embed_commit(&commitment)?;
+                output.set_script_pubkey(script_pubkey);
+                output.set_opret_commitment(commitment)?;
+            }
+            Method::TapretFirst => {
+                let internal_pk =
+                    output.tap_internal_key().ok_or(PsbtDbcError::NoInternalKey(vout))?;
+                let partner = output.tapret_partner();
+                let (output_pk, proof) = (0..=u8::MAX)
+                    .find_map(|nonce| {
+                        let path_proof = match &partner {
+                            None => TapretPathProof::root(nonce),
+                            Some(partner) => TapretPathProof::with(partner.clone(), nonce).ok()?,
+                        };
+                        internal_pk.convolve_commit(&path_proof, &commitment).ok()
+                    })
+                    .ok_or(PsbtDbcError::TapretUnsuitable(vout))?;
+                let tapret = TapretCommitment::with(commitment, proof.path_proof.nonce());
+                output.set_script_pubkey(ScriptPubkey::p2tr_tweaked(output_pk));
+                output.set_tapret_commitment(&tapret)?;
+                output.set_tapret_proof(&proof)?;
+            }
+        }
+        output.set_mpc_commitment(commitment)?;
+        *host = output;
+        Ok(commitment)
+    }
+
+    /// Produces anchor for the finalized transaction, which must be the PSBT
+    /// transaction with the commitment embedded by [`DbcPsbtFacade::commit`].
+    #[inline]
+    pub fn anchor(&self, tx: &Tx) -> Result<DbcAnchor<DbcProof, mpc::MerkleBlock>, PsbtDbcError> {
+        psbt_anchor(&*self.psbt, tx)
+    }
+}
+
+impl DbcAnchor<DbcProof, mpc::MerkleBlock> {
+    /// Reconstructs anchor from the proprietary keys and the transaction of a
+    /// finalized PSBT, validating consistency of the commitment data.
+    pub fn from_finalized_psbt(psbt: &impl DbcPsbt) -> Result<Self, PsbtDbcError> {
+        let tx = psbt.finalized_tx().ok_or(PsbtDbcError::NotFinalized)?;
+        psbt_anchor(psbt, &tx)
+    }
+}
+
+/// Iterates over all PSBT outputs marked as commitment hosts.
+fn psbt_hosts<P: DbcPsbt>(psbt: &P) -> impl Iterator<Item = (Vout, &P::Output)> {
+    psbt.outputs()
+        .enumerate()
+        .map(|(vout, output)| (Vout::from_u32(vout as u32), output))
+        .filter(|(_, output)| output.is_opret_host() || output.is_tapret_host())
+}
+
+fn psbt_host<P: DbcPsbt>(psbt: &P) -> Result<(Vout, Method), PsbtHostError> {
+    let mut hosts = psbt_hosts(psbt);
+    let (vout, output) = hosts.next().ok_or(PsbtHostError::NoHost)?;
+    if let Some((other, _)) = hosts.next() {
+        return Err(PsbtHostError::MultipleHosts(vout, other));
+    }
+    let method = match (output.is_opret_host(), output.is_tapret_host()) {
+        (true, true) => return Err(PsbtHostError::ConflictingMarks(vout)),
+        (true, false) => Method::OpretFirst,
+        _ => Method::TapretFirst,
+    };
+    let committed = output.proprietary(&super::PropKey::mpc_commitment()).is_some();
+    let script_pubkey = output.script_pubkey();
+    match method {
+        Method::TapretFirst if !script_pubkey.is_p2tr() => Err(PsbtHostError::NonTaprootHost(vout)),
+        Method::OpretFirst
+            if !script_pubkey.is_op_return() || (!committed && script_pubkey.len() != 1) =>
+        {
+            Err(PsbtHostError::NonEmptyOpret(vout))
+        }
+        _ => Ok((vout, method)),
+    }
+}
+
+fn psbt_anchor<P: DbcPsbt>(
+    psbt: &P,
+    tx: &Tx,
+) -> Result<DbcAnchor<DbcProof, mpc::MerkleBlock>, PsbtDbcError> {
+    let (vout, method) = psbt_host(psbt)?;
+    let output = psbt.outputs().nth(vout.to_usize()).expect("host output is present");
+    let commitment = output.mpc_commitment()?.ok_or(PsbtDbcError::NotCommitted(vout))?;
+    if output.mpc_entropy()?.is_none() {
+        return Err(PsbtDbcError::NotCommitted(vout));
+    }
+    let tree = mpc_tree(output)?;
+    if tree.commit_id() != commitment {
+        return Err(PsbtDbcError::CommitmentMismatch(vout));
+    }
+    let dbc_proof = match method {
+        Method::OpretFirst => {
+            if output.opret_commitment()? != Some(commitment) {
+                return Err(PsbtDbcError::CommitmentMismatch(vout));
+            }
+            DbcProof::Opret(OpretProof::default())
+        }
+        Method::TapretFirst => {
+            let proof = output.tapret_proof()?.ok_or(PsbtDbcError::NoTapretProof(vout))?;
+            let tapret = TapretCommitment::with(commitment, proof.path_proof.nonce());
+            if output.tapret_commitment()? != Some(tapret) {
+                return Err(PsbtDbcError::CommitmentMismatch(vout));
+            }
+            DbcProof::Tapret(proof)
+        }
+    };
+    dbc_proof.verify(&commitment, tx)?;
+    Ok(DbcAnchor {
+        txid: tx.txid(),
+        mpc_proof: mpc::MerkleBlock::from(&tree),
+        dbc_proof,
+    })
+}
+
+/// Builds multi-protocol commitment tree from the data kept in the output.
+fn mpc_tree(output: &impl PsbtOutput) -> Result<mpc::MerkleTree, PsbtDbcError> {
+    let min_depth = match output.mpc_min_tree_depth()? {
+        Some(depth) => u5::try_from(depth).map_err(|_| {
+            PsbtDbcError::Key(PsbtKeyError::InvalidValue(super::PropKey::mpc_min_tree_depth()))
+        })?,
+        None => MPC_MINIMAL_DEPTH,
+    };
+    let source = mpc::MultiSource {
+        method: mpc::Method::Sha256t,
+        min_depth,
+        messages: MediumOrdMap::from_iter_checked(output.mpc_messages()?),
+        static_entropy: output.mpc_entropy()?,
+    };
+    Ok(mpc::MerkleTree::try_commit(&source)?)
+}
+
+#[cfg(test)]
+mod test {
+    use bc::opcodes::OP_RETURN;
+    use bc::{TapNodeHash, TapScript};
+    use commit_verify::CommitVerify;
+
+    use super::super::PropKey;
+    use super::*;
+    use crate::test_helpers::{internal_pk, p2tr, tx};
+    use crate::Proof;
+
+    #[derive(Clone, Debug, Default)]
+    struct Output {
+        script_pubkey: ScriptPubkey,
+        internal_pk: Option<InternalPk>,
+        partner: Option<TapretNodePartner>,
+        proprietary: BTreeMap<PropKey, Vec<u8>>,
+    }
+
+    impl ProprietaryMap for Output {
+        fn proprietary(&self, key: &PropKey) -> Option<&[u8]> { self.proprietary.proprietary(key) }
+        fn set_proprietary(
+            &mut self,
+            key: PropKey,
+            value: Vec<u8>,
+        ) -> Result<Option<Vec<u8>>, PsbtKeyError> {
+            self.proprietary.set_proprietary(key, value)
+        }
+        fn remove_proprietary(&mut self, key: &PropKey) -> Option<Vec<u8>> {
+            self.proprietary.remove_proprietary(key)
+        }
+        fn proprietary_keys(&self) -> impl Iterator<Item = PropKey> + '_ {
+            self.proprietary.proprietary_keys()
+        }
+    }
+
+    impl PsbtOutput for Output {
+        fn script_pubkey(&self) -> &ScriptPubkey { &self.script_pubkey }
+        fn set_script_pubkey(&mut self, script_pubkey: ScriptPubkey) {
+            self.script_pubkey = script_pubkey
+        }
+        fn tap_internal_key(&self) -> Option<InternalPk> { self.internal_pk }
+        fn tapret_partner(&self) -> Option<TapretNodePartner> { self.partner.clone() }
+    }
+
+    impl DbcPsbt for Vec<Output> {
+        type Output = Output;
+        fn outputs(&self) -> impl Iterator<Item = &Output> { self.iter() }
+        fn output_mut(&mut self, vout: Vout) -> Option<&mut Output> {
+            self.get_mut(vout.to_usize())
+        }
+        fn finalized_tx(&self) -> Option<Tx> {
+            Some(tx(self.iter().map(|output| output.script_pubkey.clone())))
+        }
+    }
+
+    struct PsbtV2 {
+        outputs: Vec<Output>,
+        tx_modifiable: u8,
+    }
+
+    impl DbcPsbt for PsbtV2 {
+        type Output = Output;
+        fn outputs(&self) -> impl Iterator<Item = &Output> { self.outputs.iter() }
+        fn output_mut(&mut self, vout: Vout) -> Option<&mut Output> {
+            self.outputs.get_mut(vout.to_usize())
+        }
+        fn version(&self) -> PsbtVer { PsbtVer::V2 }
+        fn tx_modifiable(&self) -> u8 { self.tx_modifiable }
+    }
+
+    #[test]
+    fn opret() {
+        let host = Output {
+            script_pubkey: ScriptPubkey::from_unsafe(vec![OP_RETURN]),
+            ..default!()
+        };
+        let mut psbt = vec![Output::default(), host];
+        let mut facade = DbcPsbtFacade::new(&mut psbt);
+        assert_eq!(
+            facade.mark_host(Vout::from_u32(0), Method::OpretFirst),
+            Err(PsbtHostError::NonEmptyOpret(Vout::from_u32(0)))
+        );
+        facade.mark_host(Vout::from_u32(1), Method::OpretFirst).unwrap();
+        let pid = ProtocolId::from([1u8; 32]);
+        let msg = Message::from([2u8; 32]);
+        assert_eq!(facade.host(), Ok((Vout::from_u32(1), Method::OpretFirst)));
+        assert_eq!(
+            facade.mark_host(Vout::from_u32(0), Method::OpretFirst),
+            Err(PsbtHostError::MultipleHosts(Vout::from_u32(1), Vout::from_u32(0)))
+        );
+        facade.commit(bmap! { pid => msg }).unwrap();
+        assert_eq!(facade.commit(none!()), Err(PsbtDbcError::AlreadyCommitted(Vout::from_u32(1))));
+
+        let tx = psbt.finalized_tx().unwrap();
+        let anchor = DbcAnchor::from_finalized_psbt(&psbt).unwrap();
+        assert_eq!(DbcPsbtFacade::new(&mut psbt).anchor(&tx), Ok(anchor.clone()));
+        let anchor = anchor.to_merkle_proof(pid).unwrap();
+        assert!(anchor.verify(pid, msg, &tx).is_ok());
+    }
+
+    #[test]
+    fn v2() {
+        let host = Output {
+            script_pubkey: ScriptPubkey::from_unsafe(vec![OP_RETURN]),
+            ..default!()
+        };
+        let mut psbt = PsbtV2 {
+            outputs: vec![host],
+            tx_modifiable: 0,
+        };
+        let mut facade = DbcPsbtFacade::new(&mut psbt);
+        facade.mark_host(Vout::from_u32(0), Method::OpretFirst).unwrap();
+        assert_eq!(facade.commit(none!()), Err(PsbtDbcError::OutputsNotModifiable));
+        psbt.tx_modifiable = PSBT_TX_MODIFIABLE_OUTPUTS;
+        let messages = bmap! { ProtocolId::from([1u8; 32]) => Message::from([2u8; 32]) };
+        assert!(DbcPsbtFacade::new(&mut psbt).commit(messages).is_ok());
+    }
+
+    #[test]
+    fn failed_commit() {
+        let host = Output {
+            script_pubkey: p2tr(1),
+            ..default!()
+        };
+        let mut psbt = vec![host];
+        let mut facade = DbcPsbtFacade::new(&mut psbt);
+        facade.mark_host(Vout::from_u32(0), Method::TapretFirst).unwrap();
+        let messages = bmap! { ProtocolId::from([1u8; 32]) => Message::from([2u8; 32]) };
+        assert_eq!(facade.commit(messages), Err(PsbtDbcError::NoInternalKey(Vout::from_u32(0))));
+        assert_eq!(psbt[0].script_pubkey, p2tr(1));
+        assert!(psbt[0].mpc_messages().unwrap().is_empty());
+        assert_eq!(psbt[0].mpc_entropy(), Ok(None));
+        assert!(psbt[0].is_tapret_host());
+    }
+
+    #[test]
+    fn tapret() {
+        let mut host = Output {
+            script_pubkey: p2tr(1),
+            internal_pk: Some(internal_pk()),
+            ..default!()
+        };
+        host.set_mpc_message(ProtocolId::from([1u8; 32]), Message::from([2u8; 32])).unwrap();
+        host.set_mpc_entropy(0xA5A5).unwrap();
+        let commitment = mpc_tree(&host).unwrap().commit_id();
+
+        // Partner node ordered after the commitment leaf with nonce 0, so the
+        // facade has to iterate nonces until the ordering is satisfied
+        let leaf = |nonce| -> TapNodeHash {
+            TapScript::commit(&TapretCommitment::with(commitment, nonce)).tap_leaf_hash().into()
+        };
+        let nonce = (1..=u8::MAX).find(|nonce| leaf(*nonce) > leaf(0)).unwrap();
+        host.partner = Some(TapretNodePartner::LeftNode(leaf(nonce)));
+
+        let mut psbt = vec![host];
+        let mut facade = DbcPsbtFacade::new(&mut psbt);
+        facade.mark_host(Vout::from_u32(0), Method::TapretFirst).unwrap();
+        assert_eq!(facade.commit(none!()), Ok(commitment));
+
+        let proof = psbt[0].tapret_proof().unwrap().unwrap();
+        assert_eq!(proof.path_proof.nonce(), nonce);
+        assert_eq!(proof.internal_pk, internal_pk());
+        assert_eq!(
+            psbt[0].tapret_commitment(),
+            Ok(Some(TapretCommitment::with(commitment, nonce)))
+        );
+        let tx = psbt.finalized_tx().unwrap();
+        assert_ne!(psbt[0].script_pubkey, p2tr(1));
+        assert!(Proof::verify(&proof, &commitment, &tx).is_ok());
+
+        let anchor = DbcAnchor::from_finalized_psbt(&psbt).unwrap();
+        assert_eq!(anchor.dbc_proof, DbcProof::Tapret(proof));
+    }
+}
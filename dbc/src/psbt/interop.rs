@@ -0,0 +1,203 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interoperability with PSBT implementation from `rust-bitcoin`.
+//!
+//! Provides access to the deterministic bitcoin commitment proprietary keys in
+//! [`bitcoin::psbt::Psbt`] outputs and checks that the keys survive the
+//! processing of the PSBT by external software (signers, hardware wallets and
+//! other PSBT implementations), which may strip unknown fields.
+
+use std::collections::BTreeMap;
+
+use bc::Vout;
+use bitcoin::psbt::raw::ProprietaryKey;
+use bitcoin::psbt::{self, Psbt};
+
+use super::{
+    PropKey, ProprietaryMap, PsbtKeyError, PSBT_MPC_PREFIX, PSBT_OPRET_PREFIX, PSBT_TAPRET_PREFIX,
+};
+
+/// Errors detected during PSBT round-trips through external software.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum InteropError {
+    /// PSBT has {actual} outputs after the round-trip, while {expected} outputs
+    /// were expected.
+    OutputCountMismatch {
+        /// Number of outputs before the round-trip.
+        expected: usize,
+        /// Number of outputs after the round-trip.
+        actual: usize,
+    },
+
+    /// proprietary key {key} of output #{vout} was stripped during the
+    /// round-trip, thus the commitment can't be verified.
+    Stripped {
+        /// Output which has lost the key.
+        vout: Vout,
+        /// Stripped proprietary key.
+        key: PropKey,
+    },
+
+    /// value of proprietary key {key} of output #{vout} was modified during the
+    /// round-trip.
+    Modified {
+        /// Output containing the modified key.
+        vout: Vout,
+        /// Proprietary key with the modified value.
+        key: PropKey,
+    },
+
+    /// PSBT can't be processed by rust-bitcoin. Details: {0}
+    Bitcoin(String),
+}
+
+fn to_bitcoin_key(key: &PropKey) -> Option<ProprietaryKey> {
+    Some(ProprietaryKey {
+        prefix: key.identifier.as_bytes().to_vec(),
+        subtype: u8::try_from(key.subtype).ok()?,
+        key: key.data.clone(),
+    })
+}
+
+fn from_bitcoin_key(key: &ProprietaryKey) -> Option<PropKey> {
+    Some(PropKey {
+        identifier: String::from_utf8(key.prefix.clone()).ok()?,
+        subtype: key.subtype as u64,
+        data: key.key.clone(),
+    })
+}
+
+/// NB: rust-bitcoin supports only single-byte proprietary key subtypes, thus
+/// keys with larger subtypes are not accessible and can't be set; all
+/// deterministic bitcoin commitment keys fit the limit.
+impl ProprietaryMap for psbt::Output {
+    fn proprietary(&self, key: &PropKey) -> Option<&[u8]> {
+        to_bitcoin_key(key).and_then(|key| self.proprietary.get(&key)).map(Vec::as_slice)
+    }
+
+    fn set_proprietary(
+        &mut self,
+        key: PropKey,
+        value: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, PsbtKeyError> {
+        let key = to_bitcoin_key(&key).ok_or(PsbtKeyError::UnsupportedKey(key))?;
+        Ok(self.proprietary.insert(key, value))
+    }
+
+    fn remove_proprietary(&mut self, key: &PropKey) -> Option<Vec<u8>> {
+        to_bitcoin_key(key).and_then(|key| self.proprietary.remove(&key))
+    }
+
+    fn proprietary_keys(&self) -> impl Iterator<Item = PropKey> + '_ {
+        self.proprietary.keys().filter_map(from_bitcoin_key)
+    }
+}
+
+/// Returns all deterministic bitcoin commitment proprietary keys with their
+/// values.
+pub fn dbc_fields(map: &impl ProprietaryMap) -> BTreeMap<PropKey, Vec<u8>> {
+    map.proprietary_keys()
+        .filter(|key| {
+            [PSBT_MPC_PREFIX, PSBT_OPRET_PREFIX, PSBT_TAPRET_PREFIX]
+                .contains(&key.identifier.as_str())
+        })
+        .filter_map(|key| {
+            let value = map.proprietary(&key)?.to_vec();
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// Checks that all deterministic bitcoin commitment proprietary keys of the
+/// output were preserved.
+pub fn check_output_roundtrip(
+    vout: Vout,
+    before: &impl ProprietaryMap,
+    after: &impl ProprietaryMap,
+) -> Result<(), InteropError> {
+    for (key, value) in dbc_fields(before) {
+        match after.proprietary(&key) {
+            None => return Err(InteropError::Stripped { vout, key }),
+            Some(other) if other != value.as_slice() => {
+                return Err(InteropError::Modified { vout, key })
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks that all deterministic bitcoin commitment proprietary keys of the
+/// PSBT outputs were preserved after processing by external software.
+pub fn check_roundtrip(before: &Psbt, after: &Psbt) -> Result<(), InteropError> {
+    if before.outputs.len() != after.outputs.len() {
+        return Err(InteropError::OutputCountMismatch {
+            expected: before.outputs.len(),
+            actual: after.outputs.len(),
+        });
+    }
+    for (vout, (before, after)) in before.outputs.iter().zip(&after.outputs).enumerate() {
+        check_output_roundtrip(Vout::from_u32(vout as u32), before, after)?;
+    }
+    Ok(())
+}
+
+/// Serializes PSBT and parses it back with rust-bitcoin, checking that all
+/// deterministic bitcoin commitment proprietary keys were preserved.
+pub fn roundtrip(psbt: &Psbt) -> Result<Psbt, InteropError> {
+    let data = psbt.serialize();
+    let parsed = Psbt::deserialize(&data).map_err(|err| InteropError::Bitcoin(err.to_string()))?;
+    check_roundtrip(psbt, &parsed)?;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::psbt::DbcPsbtOutput;
+
+    #[test]
+    fn stripped() {
+        let mut before = psbt::Output::default();
+        before.set_tapret_host().unwrap();
+        before.set_mpc_entropy(5).unwrap();
+        assert_eq!(dbc_fields(&before).len(), 2);
+
+        let mut after = before.clone();
+        assert_eq!(check_output_roundtrip(Vout::from_u32(0), &before, &after), Ok(()));
+        after.remove_proprietary(&PropKey::mpc_entropy());
+        assert_eq!(
+            check_output_roundtrip(Vout::from_u32(0), &before, &after),
+            Err(InteropError::Stripped {
+                vout: Vout::from_u32(0),
+                key: PropKey::mpc_entropy()
+            })
+        );
+
+        let key = PropKey::new(PSBT_MPC_PREFIX, 0x100);
+        assert_eq!(
+            before.set_proprietary(key.clone(), vec![]),
+            Err(PsbtKeyError::UnsupportedKey(key))
+        );
+    }
+}
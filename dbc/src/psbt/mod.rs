@@ -0,0 +1,454 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! PSBT proprietary keys for deterministic bitcoin commitments.
+//!
+//! Defines proprietary keys (BIP-174 `PSBT_*_PROPRIETARY` type `0xFC`) used to
+//! pass information about the commitments between the PSBT constructor, signer
+//! and finalizer roles, and typed accessors for them, which work with any PSBT
+//! implementation exposing proprietary key-value maps via [`ProprietaryMap`].
+
+mod facade;
+#[cfg(feature = "interop")]
+pub mod interop;
+
+use std::collections::BTreeMap;
+
+use commit_verify::mpc::{self, ProtocolId};
+pub use facade::{
+    DbcPsbt, DbcPsbtFacade, PsbtDbcError, PsbtHostError, PsbtOutput, PsbtVer,
+    PSBT_TX_MODIFIABLE_OUTPUTS,
+};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::opret::OpretProof;
+use crate::tapret::{TapretCommitment, TapretProof};
+use crate::{decode_canonical, PROOF_MAX_LEN};
+
+/// Type of the proprietary PSBT key-value pairs.
+pub const PSBT_PROPRIETARY_TYPE: u8 = 0xFC;
+
+/// Proprietary key prefix used for multi-protocol commitment data.
+pub const PSBT_MPC_PREFIX: &str = "MPC";
+/// Proprietary key subtype for a message under a given protocol, which is
+/// provided as the key data.
+pub const PSBT_OUT_MPC_MESSAGE: u64 = 0x00;
+/// Proprietary key subtype for the static entropy of the multi-protocol
+/// commitment tree.
+pub const PSBT_OUT_MPC_ENTROPY: u64 = 0x01;
+/// Proprietary key subtype for the minimal depth of the multi-protocol
+/// commitment tree.
+pub const PSBT_OUT_MPC_MIN_TREE_DEPTH: u64 = 0x04;
+/// Proprietary key subtype for the multi-protocol commitment.
+pub const PSBT_OUT_MPC_COMMITMENT: u64 = 0x10;
+
+/// Proprietary key prefix used for opret commitment data.
+pub const PSBT_OPRET_PREFIX: &str = "OPRET";
+/// Proprietary key subtype marking an output as the opret commitment host.
+pub const PSBT_OUT_OPRET_HOST: u64 = 0x00;
+/// Proprietary key subtype for the opret commitment.
+pub const PSBT_OUT_OPRET_COMMITMENT: u64 = 0x01;
+
+/// Proprietary key prefix used for tapret commitment data.
+pub const PSBT_TAPRET_PREFIX: &str = "TAPRET";
+/// Proprietary key subtype marking an output as the tapret commitment host.
+pub const PSBT_OUT_TAPRET_HOST: u64 = 0x00;
+/// Proprietary key subtype for the tapret commitment.
+pub const PSBT_OUT_TAPRET_COMMITMENT: u64 = 0x01;
+/// Proprietary key subtype for the tapret proof.
+pub const PSBT_OUT_TAPRET_PROOF: u64 = 0x02;
+
+/// Errors parsing values of the proprietary PSBT keys.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum PsbtKeyError {
+    /// proprietary key is not a valid BIP-174 proprietary key.
+    InvalidKey,
+
+    /// invalid value of the proprietary key {0}.
+    InvalidValue(PropKey),
+
+    /// proprietary key {0} can't be represented by the PSBT implementation.
+    UnsupportedKey(PropKey),
+}
+
+/// Proprietary PSBT key, as defined in BIP-174.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{identifier}:{subtype}")]
+pub struct PropKey {
+    /// Identifier prefix of the key.
+    pub identifier: String,
+    /// Subtype of the key.
+    pub subtype: u64,
+    /// Additional key data.
+    pub data: Vec<u8>,
+}
+
+impl PropKey {
+    /// Constructs key with a given prefix and subtype, without additional data.
+    pub fn new(identifier: &str, subtype: u64) -> Self {
+        PropKey {
+            identifier: identifier.to_owned(),
+            subtype,
+            data: vec![],
+        }
+    }
+
+    /// Constructs key with a given prefix, subtype and additional key data.
+    pub fn with_data(identifier: &str, subtype: u64, data: impl Into<Vec<u8>>) -> Self {
+        PropKey {
+            identifier: identifier.to_owned(),
+            subtype,
+            data: data.into(),
+        }
+    }
+
+    /// Key for the message under a given protocol.
+    pub fn mpc_message(protocol_id: ProtocolId) -> Self {
+        Self::with_data(PSBT_MPC_PREFIX, PSBT_OUT_MPC_MESSAGE, protocol_id.to_vec())
+    }
+    /// Key for the static entropy of the multi-protocol commitment tree.
+    pub fn mpc_entropy() -> Self { Self::new(PSBT_MPC_PREFIX, PSBT_OUT_MPC_ENTROPY) }
+    /// Key for the minimal depth of the multi-protocol commitment tree.
+    pub fn mpc_min_tree_depth() -> Self { Self::new(PSBT_MPC_PREFIX, PSBT_OUT_MPC_MIN_TREE_DEPTH) }
+    /// Key for the multi-protocol commitment.
+    pub fn mpc_commitment() -> Self { Self::new(PSBT_MPC_PREFIX, PSBT_OUT_MPC_COMMITMENT) }
+    /// Key marking opret commitment host.
+    pub fn opret_host() -> Self { Self::new(PSBT_OPRET_PREFIX, PSBT_OUT_OPRET_HOST) }
+    /// Key for the opret commitment.
+    pub fn opret_commitment() -> Self { Self::new(PSBT_OPRET_PREFIX, PSBT_OUT_OPRET_COMMITMENT) }
+    /// Key marking tapret commitment host.
+    pub fn tapret_host() -> Self { Self::new(PSBT_TAPRET_PREFIX, PSBT_OUT_TAPRET_HOST) }
+    /// Key for the tapret commitment.
+    pub fn tapret_commitment() -> Self { Self::new(PSBT_TAPRET_PREFIX, PSBT_OUT_TAPRET_COMMITMENT) }
+    /// Key for the tapret proof.
+    pub fn tapret_proof() -> Self { Self::new(PSBT_TAPRET_PREFIX, PSBT_OUT_TAPRET_PROOF) }
+
+    /// Serializes the key according to BIP-174, including the `0xFC` key type.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = vec![PSBT_PROPRIETARY_TYPE];
+        write_compact_size(&mut data, self.identifier.len() as u64);
+        data.extend(self.identifier.as_bytes());
+        write_compact_size(&mut data, self.subtype);
+        data.extend(&self.data);
+        data
+    }
+
+    /// Parses the key serialized according to BIP-174, including the `0xFC` key
+    /// type.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, PsbtKeyError> {
+        let (ty, mut data) = data.split_first().ok_or(PsbtKeyError::InvalidKey)?;
+        if *ty != PSBT_PROPRIETARY_TYPE {
+            return Err(PsbtKeyError::InvalidKey);
+        }
+        let len = read_compact_size(&mut data).ok_or(PsbtKeyError::InvalidKey)? as usize;
+        if data.len() < len {
+            return Err(PsbtKeyError::InvalidKey);
+        }
+        let (identifier, mut data) = data.split_at(len);
+        let identifier =
+            String::from_utf8(identifier.to_vec()).map_err(|_| PsbtKeyError::InvalidKey)?;
+        let subtype = read_compact_size(&mut data).ok_or(PsbtKeyError::InvalidKey)?;
+        Ok(PropKey {
+            identifier,
+            subtype,
+            data: data.to_vec(),
+        })
+    }
+}
+
+fn write_compact_size(data: &mut Vec<u8>, val: u64) {
+    match val {
+        0..=0xFC => data.push(val as u8),
+        0xFD..=0xFFFF => {
+            data.push(0xFD);
+            data.extend((val as u16).to_le_bytes());
+        }
+        0x10000..=0xFFFF_FFFF => {
+            data.push(0xFE);
+            data.extend((val as u32).to_le_bytes());
+        }
+        _ => {
+            data.push(0xFF);
+            data.extend(val.to_le_bytes());
+        }
+    }
+}
+
+fn read_compact_size(data: &mut &[u8]) -> Option<u64> {
+    let (prefix, rest) = data.split_first()?;
+    let len = match prefix {
+        0xFD => 2,
+        0xFE => 4,
+        0xFF => 8,
+        _ => {
+            *data = rest;
+            return Some(*prefix as u64);
+        }
+    };
+    if rest.len() < len {
+        return None;
+    }
+    let mut buf = [0u8; 8];
+    buf[..len].copy_from_slice(&rest[..len]);
+    *data = &rest[len..];
+    Some(u64::from_le_bytes(buf))
+}
+
+/// Map of proprietary key-value pairs of a PSBT global map, input or output.
+///
+/// The trait must be implemented by PSBT libraries to allow the use of the
+/// typed accessors defined by [`DbcPsbtOutput`].
+pub trait ProprietaryMap {
+    /// Returns value for the proprietary key, if present.
+    fn proprietary(&self, key: &PropKey) -> Option<&[u8]>;
+
+    /// Sets value for the proprietary key, returning the previous value.
+    ///
+    /// Errors with [`PsbtKeyError::UnsupportedKey`] if the key can't be
+    /// represented by the PSBT implementation.
+    fn set_proprietary(
+        &mut self,
+        key: PropKey,
+        value: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, PsbtKeyError>;
+
+    /// Removes the proprietary key, returning its value.
+    fn remove_proprietary(&mut self, key: &PropKey) -> Option<Vec<u8>>;
+
+    /// Iterates over all proprietary keys in the map.
+    fn proprietary_keys(&self) -> impl Iterator<Item = PropKey> + '_;
+}
+
+impl ProprietaryMap for BTreeMap<PropKey, Vec<u8>> {
+    fn proprietary(&self, key: &PropKey) -> Option<&[u8]> { self.get(key).map(Vec::as_slice) }
+
+    fn set_proprietary(
+        &mut self,
+        key: PropKey,
+        value: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, PsbtKeyError> {
+        Ok(self.insert(key, value))
+    }
+
+    fn remove_proprietary(&mut self, key: &PropKey) -> Option<Vec<u8>> { self.remove(key) }
+
+    fn proprietary_keys(&self) -> impl Iterator<Item = PropKey> + '_ { self.keys().cloned() }
+}
+
+/// Typed accessors for the deterministic bitcoin commitment data stored in PSBT
+/// output proprietary keys.
+pub trait DbcPsbtOutput: ProprietaryMap {
+    /// Detects whether the output is marked as opret commitment host.
+    fn is_opret_host(&self) -> bool { self.proprietary(&PropKey::opret_host()).is_some() }
+
+    /// Marks the output as opret commitment host.
+    fn set_opret_host(&mut self) -> Result<(), PsbtKeyError> {
+        self.set_proprietary(PropKey::opret_host(), vec![]).map(|_| ())
+    }
+
+    /// Detects whether the output is marked as tapret commitment host.
+    fn is_tapret_host(&self) -> bool { self.proprietary(&PropKey::tapret_host()).is_some() }
+
+    /// Marks the output as tapret commitment host.
+    fn set_tapret_host(&mut self) -> Result<(), PsbtKeyError> {
+        self.set_proprietary(PropKey::tapret_host(), vec![]).map(|_| ())
+    }
+
+    /// Returns message under a given protocol to be committed to by the output.
+    fn mpc_message(&self, protocol_id: ProtocolId) -> Result<Option<mpc::Message>, PsbtKeyError> {
+        let key = PropKey::mpc_message(protocol_id);
+        self.proprietary(&key)
+            .map(|val| {
+                <[u8; 32]>::try_from(val)
+                    .map(mpc::Message::from)
+                    .map_err(|_| PsbtKeyError::InvalidValue(key.clone()))
+            })
+            .transpose()
+    }
+
+    /// Sets message under a given protocol to be committed to by the output,
+    /// returning whether a message for the protocol was already present.
+    fn set_mpc_message(
+        &mut self,
+        protocol_id: ProtocolId,
+        message: mpc::Message,
+    ) -> Result<bool, PsbtKeyError> {
+        self.set_proprietary(PropKey::mpc_message(protocol_id), message.to_vec())
+            .map(|prev| prev.is_some())
+    }
+
+    /// Returns all messages to be committed to by the output.
+    fn mpc_messages(&self) -> Result<BTreeMap<ProtocolId, mpc::Message>, PsbtKeyError> {
+        let keys = self
+            .proprietary_keys()
+            .filter(|key| key.identifier == PSBT_MPC_PREFIX && key.subtype == PSBT_OUT_MPC_MESSAGE)
+            .collect::<Vec<_>>();
+        let mut messages = BTreeMap::new();
+        for key in keys {
+            let protocol_id = <[u8; 32]>::try_from(key.data.as_slice())
+                .map(ProtocolId::from)
+                .map_err(|_| PsbtKeyError::InvalidValue(key.clone()))?;
+            let message = self.mpc_message(protocol_id)?.expect("key is present");
+            messages.insert(protocol_id, message);
+        }
+        Ok(messages)
+    }
+
+    /// Returns static entropy of the multi-protocol commitment tree.
+    fn mpc_entropy(&self) -> Result<Option<u64>, PsbtKeyError> {
+        self.proprietary(&PropKey::mpc_entropy())
+            .map(|val| {
+                <[u8; 8]>::try_from(val)
+                    .map(u64::from_le_bytes)
+                    .map_err(|_| PsbtKeyError::InvalidValue(PropKey::mpc_entropy()))
+            })
+            .transpose()
+    }
+
+    /// Sets static entropy of the multi-protocol commitment tree.
+    fn set_mpc_entropy(&mut self, entropy: u64) -> Result<(), PsbtKeyError> {
+        self.set_proprietary(PropKey::mpc_entropy(), entropy.to_le_bytes().to_vec()).map(|_| ())
+    }
+
+    /// Returns minimal depth of the multi-protocol commitment tree.
+    fn mpc_min_tree_depth(&self) -> Result<Option<u8>, PsbtKeyError> {
+        self.proprietary(&PropKey::mpc_min_tree_depth())
+            .map(|val| match val {
+                [depth] => Ok(*depth),
+                _ => Err(PsbtKeyError::InvalidValue(PropKey::mpc_min_tree_depth())),
+            })
+            .transpose()
+    }
+
+    /// Sets minimal depth of the multi-protocol commitment tree.
+    fn set_mpc_min_tree_depth(&mut self, depth: u8) -> Result<(), PsbtKeyError> {
+        self.set_proprietary(PropKey::mpc_min_tree_depth(), vec![depth]).map(|_| ())
+    }
+
+    /// Returns multi-protocol commitment embedded into the output.
+    fn mpc_commitment(&self) -> Result<Option<mpc::Commitment>, PsbtKeyError> {
+        self.proprietary(&PropKey::mpc_commitment())
+            .map(|val| {
+                <[u8; 32]>::try_from(val)
+                    .map(mpc::Commitment::from)
+                    .map_err(|_| PsbtKeyError::InvalidValue(PropKey::mpc_commitment()))
+            })
+            .transpose()
+    }
+
+    /// Sets multi-protocol commitment embedded into the output.
+    fn set_mpc_commitment(&mut self, commitment: mpc::Commitment) -> Result<(), PsbtKeyError> {
+        self.set_proprietary(PropKey::mpc_commitment(), commitment.to_vec()).map(|_| ())
+    }
+
+    /// Returns opret commitment embedded into the output.
+    fn opret_commitment(&self) -> Result<Option<mpc::Commitment>, PsbtKeyError> {
+        self.proprietary(&PropKey::opret_commitment())
+            .map(|val| {
+                <[u8; 32]>::try_from(val)
+                    .map(mpc::Commitment::from)
+                    .map_err(|_| PsbtKeyError::InvalidValue(PropKey::opret_commitment()))
+            })
+            .transpose()
+    }
+
+    /// Sets opret commitment embedded into the output.
+    fn set_opret_commitment(&mut self, commitment: mpc::Commitment) -> Result<(), PsbtKeyError> {
+        self.set_proprietary(PropKey::opret_commitment(), commitment.to_vec()).map(|_| ())
+    }
+
+    /// Returns opret proof, if the output contains opret commitment.
+    fn opret_proof(&self) -> Result<Option<OpretProof>, PsbtKeyError> {
+        Ok(self.opret_commitment()?.map(|_| OpretProof::default()))
+    }
+
+    /// Returns tapret commitment embedded into the output.
+    fn tapret_commitment(&self) -> Result<Option<TapretCommitment>, PsbtKeyError> {
+        self.decode_proprietary(PropKey::tapret_commitment())
+    }
+
+    /// Sets tapret commitment embedded into the output.
+    fn set_tapret_commitment(&mut self, commitment: &TapretCommitment) -> Result<(), PsbtKeyError> {
+        self.encode_proprietary(PropKey::tapret_commitment(), commitment)
+    }
+
+    /// Returns proof of the tapret commitment embedded into the output.
+    fn tapret_proof(&self) -> Result<Option<TapretProof>, PsbtKeyError> {
+        self.decode_proprietary(PropKey::tapret_proof())
+    }
+
+    /// Sets proof of the tapret commitment embedded into the output.
+    fn set_tapret_proof(&mut self, proof: &TapretProof) -> Result<(), PsbtKeyError> {
+        self.encode_proprietary(PropKey::tapret_proof(), proof)
+    }
+
+    #[doc(hidden)]
+    fn decode_proprietary<T: StrictSerialize + StrictDeserialize>(
+        &self,
+        key: PropKey,
+    ) -> Result<Option<T>, PsbtKeyError> {
+        self.proprietary(&key)
+            .map(|val| decode_canonical(val).map_err(|_| PsbtKeyError::InvalidValue(key.clone())))
+            .transpose()
+    }
+
+    #[doc(hidden)]
+    fn encode_proprietary(
+        &mut self,
+        key: PropKey,
+        value: &impl StrictSerialize,
+    ) -> Result<(), PsbtKeyError> {
+        let value = value
+            .to_strict_serialized::<PROOF_MAX_LEN>()
+            .expect("proof data exceed maximal proof length")
+            .release();
+        self.set_proprietary(key, value).map(|_| ())
+    }
+}
+
+impl<M: ProprietaryMap + ?Sized> DbcPsbtOutput for M {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        let key = PropKey::tapret_proof();
+        let data = key.to_bytes();
+        assert_eq!(data, b"\xFC\x06TAPRET\x02");
+        assert_eq!(PropKey::from_bytes(&data), Ok(key));
+
+        let mut output = BTreeMap::<PropKey, Vec<u8>>::new();
+        assert!(!output.is_tapret_host());
+        output.set_tapret_host().unwrap();
+        output.set_mpc_entropy(0x0102030405060708).unwrap();
+        output.set_mpc_message(ProtocolId::from([1u8; 32]), mpc::Message::from([2u8; 32])).unwrap();
+        assert!(output.is_tapret_host());
+        assert_eq!(output.mpc_entropy(), Ok(Some(0x0102030405060708)));
+        assert_eq!(
+            output.mpc_messages(),
+            Ok(bmap! { ProtocolId::from([1u8; 32]) => mpc::Message::from([2u8; 32]) })
+        );
+        assert_eq!(output.tapret_proof(), Ok(None));
+    }
+}
@@ -0,0 +1,141 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scanning of block transactions for deterministic bitcoin commitments, for
+//! use by indexers.
+
+use amplify::ByteArray;
+use bc::{Block, Tx, Txid, Vout};
+use commit_verify::mpc;
+
+use crate::opret::OpretProof;
+use crate::Method;
+
+/// Index record describing a commitment (or a commitment host candidate)
+/// found in a transaction.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ScanRecord {
+    /// Id of the transaction containing the commitment.
+    pub txid: Txid,
+    /// Transaction output hosting the commitment.
+    pub vout: Vout,
+    /// Commitment method.
+    pub method: Method,
+    /// Committed multi-protocol commitment, if it is publicly visible.
+    ///
+    /// Tapret commitments are hidden inside the output key and can't be
+    /// extracted without the proof; for them the record only marks the output
+    /// which may host the commitment, and the value is always `None`.
+    ///
+    /// Such records are not filtered: any taproot output is reported as a
+    /// candidate, and only a tapret proof provided by the client can tell
+    /// whether it actually contains a commitment.
+    pub commitment: Option<mpc::Commitment>,
+}
+
+/// Scans a transaction for deterministic bitcoin commitments.
+///
+/// Returns a record for the first OP_RETURN output if its payload has the
+/// size of a multi-protocol commitment, and a record for the first taproot
+/// output as a possible tapret commitment host.
+///
+/// Tapret records are unfiltered candidates: the scanner reports the first
+/// taproot output of every transaction, whether it contains a commitment or
+/// not, since this can't be detected without the proof.
+pub fn scan_tx(tx: &Tx) -> Vec<ScanRecord> {
+    let txid = tx.txid();
+    let mut records = Vec::with_capacity(2);
+    let outputs = || tx.outputs().enumerate();
+
+    if let Some((vout, txout)) = outputs().find(|(_, txout)| txout.script_pubkey.is_op_return()) {
+        let payload = OpretProof::payload(&txout.script_pubkey);
+        if let Some(Ok(data)) = payload.map(<[u8; 32]>::try_from) {
+            records.push(ScanRecord {
+                txid,
+                vout: Vout::from_u32(vout as u32),
+                method: Method::OpretFirst,
+                commitment: Some(mpc::Commitment::from_byte_array(data)),
+            });
+        }
+    }
+    if let Some((vout, _)) = outputs().find(|(_, txout)| txout.script_pubkey.is_p2tr()) {
+        records.push(ScanRecord {
+            txid,
+            vout: Vout::from_u32(vout as u32),
+            method: Method::TapretFirst,
+            commitment: None,
+        });
+    }
+    records
+}
+
+/// Scans all transactions of a block for deterministic bitcoin commitments,
+/// returning index records in the order of the transactions.
+///
+/// See [`scan_tx`] for the details.
+pub fn scan_block(block: &Block) -> Vec<ScanRecord> { scan_txs(&block.transactions) }
+
+/// Scans a sequence of transactions for deterministic bitcoin commitments,
+/// returning index records in the order of the transactions.
+///
+/// See [`scan_tx`] for the details.
+pub fn scan_txs<'tx>(txs: impl IntoIterator<Item = &'tx Tx>) -> Vec<ScanRecord> {
+    txs.into_iter().flat_map(scan_tx).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bc::{BlockHeader, ScriptPubkey};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::test_helpers::{p2tr, tx};
+
+    #[test]
+    fn scan() {
+        let msg = mpc::Commitment::from([8u8; 32]);
+        let tx1 = tx([ScriptPubkey::p2sh([1u8; 20]), ScriptPubkey::op_return(msg.as_slice())]);
+        let tx2 = tx([ScriptPubkey::op_return(&[1u8; 20])]);
+        let tx3 = tx([p2tr(2)]);
+
+        let block = Block {
+            header: BlockHeader::strict_dumb(),
+            transactions: Confined::from_iter_checked([tx1.clone(), tx2, tx3.clone()]),
+        };
+        let records = scan_block(&block);
+        assert_eq!(records, vec![
+            ScanRecord {
+                txid: tx1.txid(),
+                vout: Vout::from_u32(1),
+                method: Method::OpretFirst,
+                commitment: Some(msg),
+            },
+            ScanRecord {
+                txid: tx3.txid(),
+                vout: Vout::from_u32(0),
+                method: Method::TapretFirst,
+                commitment: None,
+            },
+        ]);
+        assert_eq!(scan_txs(&block.transactions), records);
+    }
+}
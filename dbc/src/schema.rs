@@ -0,0 +1,55 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON schema helpers for the data types from external libraries.
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+
+/// Schema placeholder for the data types from external libraries which don't
+/// provide JSON schema, describing them as arbitrary JSON values.
+///
+/// Used with `#[schemars(with = "Opaque")]` field attribute.
+pub struct Opaque;
+
+impl JsonSchema for Opaque {
+    fn schema_name() -> String { s!("Opaque") }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema { Schema::Bool(true) }
+}
+
+#[cfg(test)]
+mod test {
+    use schemars::schema::{InstanceType, SingleOrVec};
+    use schemars::schema_for;
+
+    use crate::{DbcAnchor, DbcProof};
+
+    #[test]
+    fn anchor() {
+        let schema = schema_for!(DbcAnchor<DbcProof>);
+        assert_eq!(
+            schema.schema.instance_type,
+            Some(SingleOrVec::Single(Box::new(InstanceType::String)))
+        );
+    }
+}
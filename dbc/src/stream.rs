@@ -0,0 +1,159 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming strict encoding of large collections of seals, proofs and
+//! anchors.
+//!
+//! A stream is a plain concatenation of strict-encoded items, which are
+//! written directly into [`io::Write`] and read back one-by-one from
+//! [`io::BufRead`], such that the peak memory doesn't depend on the number of
+//! the items.
+
+use std::io;
+use std::marker::PhantomData;
+
+use strict_encoding::{
+    DecodeError, DeserializeError, SerializeError, StreamReader, StreamWriter, StrictDecode,
+    StrictEncode, StrictReader, StrictWriter,
+};
+
+use crate::PROOF_MAX_LEN;
+
+/// Writer of strict-encoded items into a stream.
+#[derive(Debug)]
+pub struct StrictStreamWriter<W: io::Write> {
+    writer: W,
+    count: usize,
+}
+
+impl<W: io::Write> StrictStreamWriter<W> {
+    /// Constructs stream writer on top of an [`io::Write`].
+    pub fn new(writer: W) -> Self { StrictStreamWriter { writer, count: 0 } }
+
+    /// Writes a single item into the stream.
+    ///
+    /// Errors if the serialized item exceeds [`PROOF_MAX_LEN`] or on I/O
+    /// errors.
+    pub fn write<T: StrictEncode>(&mut self, item: &T) -> Result<(), SerializeError> {
+        item.strict_encode(StrictWriter::with(StreamWriter::new::<PROOF_MAX_LEN>(
+            &mut self.writer,
+        )))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Writes all items from an iterator into the stream.
+    pub fn write_all<'a, T: StrictEncode + 'a>(
+        &mut self,
+        items: impl IntoIterator<Item = &'a T>,
+    ) -> Result<(), SerializeError> {
+        items.into_iter().try_for_each(|item| self.write(item))
+    }
+
+    /// Returns number of items written into the stream.
+    pub fn count(&self) -> usize { self.count }
+
+    /// Flushes and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Fallible iterator over strict-encoded items read from a stream.
+///
+/// Stops at the end of the stream or after the first error.
+#[derive(Debug)]
+pub struct StrictStreamReader<T: StrictDecode, R: io::BufRead> {
+    reader: R,
+    failed: bool,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: StrictDecode, R: io::BufRead> StrictStreamReader<T, R> {
+    /// Constructs stream reader on top of an [`io::BufRead`].
+    pub fn new(reader: R) -> Self {
+        StrictStreamReader {
+            reader,
+            failed: false,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R { self.reader }
+
+    fn read(&mut self) -> Result<Option<T>, DeserializeError> {
+        if self.reader.fill_buf().map_err(DecodeError::from)?.is_empty() {
+            return Ok(None);
+        }
+        let mut reader = StrictReader::with(StreamReader::new::<PROOF_MAX_LEN>(&mut self.reader));
+        Ok(Some(T::strict_decode(&mut reader)?))
+    }
+}
+
+impl<T: StrictDecode, R: io::BufRead> Iterator for StrictStreamReader<T, R> {
+    type Item = Result<T, DeserializeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let res = self.read().transpose();
+        self.failed = matches!(res, Some(Err(_)));
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::opret::OpretProof;
+    use crate::test_helpers::tapret_proof;
+    use crate::DbcProof;
+
+    #[test]
+    fn roundtrip() {
+        let proofs = (0u8..100)
+            .map(|nonce| match nonce % 2 {
+                0 => DbcProof::Opret(OpretProof::default()),
+                _ => DbcProof::Tapret(tapret_proof(nonce)),
+            })
+            .collect::<Vec<_>>();
+
+        let mut writer = StrictStreamWriter::new(vec![]);
+        writer.write_all(&proofs).unwrap();
+        assert_eq!(writer.count(), 100);
+        let data = writer.finish().unwrap();
+
+        let decoded = StrictStreamReader::<DbcProof, _>::new(data.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(decoded, proofs);
+
+        let mut reader = StrictStreamReader::<DbcProof, _>::new(&data[..data.len() - 1]);
+        for proof in &proofs[..99] {
+            assert_eq!(&reader.next().unwrap().unwrap(), proof);
+        }
+        assert!(matches!(reader.next(), Some(Err(DeserializeError::Decode(DecodeError::Io(_))))));
+        assert!(reader.next().is_none());
+    }
+}
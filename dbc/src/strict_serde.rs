@@ -0,0 +1,175 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serde support for strict-encodable data, using a compact canonical string
+//! (strict-serialized data encoded with Baid64 alphabet) in human-readable
+//! formats and the strict-serialized bytes in binary formats.
+//!
+//! Proofs and anchors implement serde traits using this module. Other
+//! strict-encodable types may use it with `#[serde(with = "dbc::strict_serde")]`
+//! attribute or [`impl_strict_serde`] macro.
+
+use std::fmt::{self, Formatter};
+
+use baid64::BAID64_ALPHABET;
+use base64::alphabet::Alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, NO_PAD};
+use base64::Engine;
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serializer};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{decode_canonical, PROOF_MAX_LEN};
+
+fn engine() -> GeneralPurpose {
+    let alphabet = Alphabet::new(BAID64_ALPHABET).expect("invalid Baid64 alphabet");
+    GeneralPurpose::new(&alphabet, NO_PAD)
+}
+
+/// Serializes strict-encodable data.
+pub fn serialize<T: StrictSerialize, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let data = value
+        .to_strict_serialized::<PROOF_MAX_LEN>()
+        .map_err(|_| serde::ser::Error::custom("data exceed maximal proof length"))?;
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&engine().encode(data.as_slice()))
+    } else {
+        serializer.serialize_bytes(&data)
+    }
+}
+
+/// Deserializes strict-encodable data, requiring canonical encoding.
+pub fn deserialize<'de, T: StrictSerialize + StrictDeserialize, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let data = if deserializer.is_human_readable() {
+        let s = String::deserialize(deserializer)?;
+        engine().decode(s).map_err(|_| D::Error::custom("invalid Baid64 encoding"))?
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)?
+    };
+    decode_canonical(data).map_err(D::Error::custom)
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result { f.write_str("strict-encoded bytes") }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> { Ok(v.to_vec()) }
+
+    fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> { Ok(v) }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut data = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(byte) = seq.next_element()? {
+            data.push(byte);
+        }
+        Ok(data)
+    }
+}
+
+/// Implements serde traits (and JSON schema, if `schemars` feature is
+/// enabled) for strict-encodable types using [`strict_serde`](self)
+/// representation.
+#[macro_export]
+macro_rules! impl_strict_serde {
+    ($($ty:ty),+ $(,)?) => {$(
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                $crate::strict_serde::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                $crate::strict_serde::deserialize(deserializer)
+            }
+        }
+
+        #[cfg(feature = "schemars")]
+        impl schemars::JsonSchema for $ty {
+            fn schema_name() -> String { stringify!($ty).replace(' ', "") }
+
+            fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+                <String as schemars::JsonSchema>::json_schema(gen)
+            }
+        }
+    )+};
+}
+
+#[cfg(test)]
+mod test {
+    use commit_verify::mpc::{Message, ProtocolId};
+
+    use crate::opret::OpretProof;
+    use crate::tapret::{TapretPathProof, TapretProof};
+    use crate::test_helpers::{tapret_proof, tx};
+    use crate::{commit_to_tx, CommitMethod, DbcAnchor, DbcProof, DualProof};
+
+    fn anchor() -> DbcAnchor<DbcProof> {
+        let pid = ProtocolId::from([1u8; 32]);
+        let msg = Message::from([2u8; 32]);
+        let (_, anchor) = commit_to_tx(bmap! { pid => msg }, tx([]), CommitMethod::Opret).unwrap();
+        anchor.to_merkle_proof(pid).unwrap()
+    }
+
+    fn roundtrip<T>(value: &T)
+    where T: serde::Serialize + serde::de::DeserializeOwned + Eq + std::fmt::Debug {
+        let s = serde_json::to_string(value).unwrap();
+        assert!(s.starts_with('"') && s.ends_with('"'));
+        assert_eq!(&serde_json::from_str::<T>(&s).unwrap(), value);
+    }
+
+    #[test]
+    fn json() {
+        roundtrip(&OpretProof::default());
+        roundtrip(&TapretPathProof::root(1));
+        roundtrip(&tapret_proof(0));
+        roundtrip(&DbcProof::Tapret(tapret_proof(2)));
+        roundtrip(&DualProof::new(OpretProof::default(), tapret_proof(3)));
+        roundtrip(&anchor());
+
+        // Non-canonical and truncated data are rejected
+        let s = serde_json::to_string(&tapret_proof(0)).unwrap();
+        let truncated = format!("{}\"", &s[..s.len() - 3]);
+        assert!(serde_json::from_str::<TapretProof>(&truncated).is_err());
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor() {
+        let anchor = anchor();
+        let data = serde_ipld_dagcbor::to_vec(&anchor).unwrap();
+        // Binary formats use strict-serialized bytes, encoded as CBOR byte string
+        assert_eq!(data[0] & 0xE0, 0x40);
+        assert_eq!(serde_ipld_dagcbor::from_slice::<DbcAnchor<DbcProof>>(&data).unwrap(), anchor);
+
+        let proof = DbcProof::Tapret(tapret_proof(0));
+        let data = serde_ipld_dagcbor::to_vec(&proof).unwrap();
+        assert_eq!(serde_ipld_dagcbor::from_slice::<DbcProof>(&data).unwrap(), proof);
+    }
+}
@@ -67,16 +67,23 @@ mod txout;
 mod spk;
 mod xonlypk;
 
-use bc::{InternalPk, IntoTapHash, LeafScript, ScriptPubkey, TapBranchHash, TapNodeHash, Tx};
+use bc::{
+    InternalPk, IntoTapHash, LeafScript, ScriptBytes, ScriptPubkey, TapBranchHash, TapNodeHash, Tx,
+};
 use commit_verify::mpc::Commitment;
 use commit_verify::{CommitmentProtocol, ConvolveCommitProof, ConvolveVerifyError};
-use strict_encoding::{StrictDeserialize, StrictSerialize};
-pub use tapscript::{TapretCommitment, TAPRET_SCRIPT_COMMITMENT_PREFIX};
+use strict_encoding::{
+    DecodeError, ReadStruct, ReadTuple, ReadUnion, StrictDecode, StrictDeserialize,
+    StrictSerialize, TypedRead,
+};
+pub use tapscript::{
+    TapretCommitment, TAPRET_SCRIPT_COMMITMENT_LEN, TAPRET_SCRIPT_COMMITMENT_PREFIX,
+};
 pub use tx::TapretError;
 pub use xonlypk::TapretKeyError;
 
 use crate::proof::Method;
-use crate::{Proof, LIB_NAME_BPCORE};
+use crate::{PolicyViolation, Proof, VerifyError, VerifyOpts, LIB_NAME_BPCORE};
 
 /// Marker non-instantiable enum defining LNPBP-12 taproot OP_RETURN (`tapret`)
 /// protocol.
@@ -101,9 +108,10 @@ pub enum TapretPathError {
 /// [`TapretNodePartner::RightBranch`] to ensure correct consensus ordering of
 /// the child elements.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[derive(StrictType, StrictDumb, StrictEncode)]
 #[strict_type(lib = LIB_NAME_BPCORE)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[display("{left_node_hash}:{right_node_hash}")]
 pub struct TapretRightBranch {
     left_node_hash: TapNodeHash,
@@ -137,26 +145,31 @@ impl TapretRightBranch {
     }
 }
 
-/*
 impl StrictDecode for TapretRightBranch {
-    fn strict_decode<D: Read>(
-        mut d: D,
-    ) -> Result<Self, strict_encoding::Error> {
-        let left_node_hash = StrictDecode::strict_decode(&mut d)?;
-        let right_node_hash = StrictDecode::strict_decode(d)?;
-        if left_node_hash > right_node_hash {
-            Err(strict_encoding::Error::DataIntegrityError(s!(
-                "non-cosensus ordering of hashes in TapretRightBranch"
-            )))
-        } else {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let left_node_hash: TapNodeHash = r.read_field(fname!("leftNodeHash"))?;
+            let right_node_hash: TapNodeHash = r.read_field(fname!("rightNodeHash"))?;
+            if left_node_hash > right_node_hash {
+                return Err(DecodeError::DataIntegrityError(s!(
+                    "non-consensus ordering of hashes in TapretRightBranch"
+                )));
+            }
             Ok(TapretRightBranch {
                 left_node_hash,
                 right_node_hash,
             })
-        }
+        })
     }
 }
- */
+
+/// Maximal length of the script in [`TapretNodePartner::RightLeaf`] accepted by
+/// the decoder.
+///
+/// Equals `MAX_STANDARD_TX_WEIGHT` of Bitcoin Core (400 000 weight units): a
+/// tapscript leaf is revealed in the witness, where each byte weighs a single
+/// unit, so no standard transaction may spend a longer leaf script.
+pub const TAPRET_PARTNER_SCRIPT_MAX_LEN: usize = 400_000;
 
 /// Information proving step of a tapret path in determined way within a given
 /// tap tree.
@@ -164,9 +177,10 @@ impl StrictDecode for TapretRightBranch {
 /// The structure hosts proofs that the right-side partner at the taproot script
 /// tree node does not contain an alternative OP-RETURN commitment script.
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[derive(StrictType, StrictDumb, StrictEncode)]
 #[strict_type(lib = LIB_NAME_BPCORE, tags = order, dumb = Self::RightLeaf(default!()))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[display(inner)]
 pub enum TapretNodePartner {
     /// Tapret commitment is on the right side of the tree; i.e the node
@@ -187,6 +201,35 @@ pub enum TapretNodePartner {
     RightBranch(TapretRightBranch),
 }
 
+impl StrictDecode for TapretNodePartner {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_union(|variant, u| match variant.as_str() {
+            "leftNode" => u.read_tuple(|r| Ok(Self::LeftNode(r.read_field()?))),
+            "rightLeaf" => u.read_tuple(|r| {
+                let version = r.read_field()?;
+                // The script is a byte string with a 32-bit length prefix; we
+                // check the declared length before allocating the memory.
+                let len = r.read_field::<u32>()? as usize;
+                if len > TAPRET_PARTNER_SCRIPT_MAX_LEN {
+                    return Err(DecodeError::DataIntegrityError(format!(
+                        "tapret partner script length {len} exceeds the limit"
+                    )));
+                }
+                let mut script = Vec::with_capacity(len);
+                for _ in 0..len {
+                    script.push(r.read_field::<u8>()?);
+                }
+                Ok(Self::RightLeaf(LeafScript {
+                    version,
+                    script: ScriptBytes::from_unsafe(script),
+                }))
+            }),
+            "rightBranch" => u.read_tuple(|r| Ok(Self::RightBranch(r.read_field()?))),
+            _ => unreachable!("all variants of TapretNodePartner are enumerated"),
+        })
+    }
+}
+
 impl TapretNodePartner {
     /// Constructs right-side tapret branch proof structuring `a` and `b`
     /// children node hashes in the correct consensus order (i.e.
@@ -247,10 +290,14 @@ impl TapretNodePartner {
 ///
 /// Holds information about the sibling at level 1 of the tree in form of
 /// [`TapretNodePartner`].
+///
+/// The proof consists of a single level of the tree, so its depth is bounded
+/// by the structure itself. Decoding fails for proofs which have partner node
+/// containing an alternative commitment, or a script longer than
+/// [`TAPRET_PARTNER_SCRIPT_MAX_LEN`].
 #[derive(Getters, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[derive(StrictType, StrictDumb, StrictEncode)]
 #[strict_type(lib = LIB_NAME_BPCORE)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 pub struct TapretPathProof {
     /// Information about the sibling at level 1 of the tree
     partner_node: Option<TapretNodePartner>,
@@ -261,10 +308,33 @@ pub struct TapretPathProof {
     nonce: u8,
 }
 
+impl StrictDecode for TapretPathProof {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let partner_node: Option<TapretNodePartner> = r.read_field(fname!("partnerNode"))?;
+            let nonce = r.read_field(fname!("nonce"))?;
+            if let Some(partner) = &partner_node {
+                if !partner.check_no_commitment() {
+                    return Err(DecodeError::DataIntegrityError(format!(
+                        "tapret node partner {partner} contains alternative commitment"
+                    )));
+                }
+            }
+            Ok(TapretPathProof {
+                partner_node,
+                nonce,
+            })
+        })
+    }
+}
+
 // Used by PSBT tapret keys
 impl StrictSerialize for TapretPathProof {}
 impl StrictDeserialize for TapretPathProof {}
 
+#[cfg(feature = "serde")]
+crate::impl_strict_serde!(TapretPathProof);
+
 impl TapretPathProof {
     /// Construct new empty path proof.
     #[inline]
@@ -332,7 +402,6 @@ impl<'data> IntoIterator for &'data TapretPathProof {
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_BPCORE)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 pub struct TapretProof {
     /// A merkle path to the commitment inside the taproot script tree. For
     /// each node it also must hold information about the sibling in form of
@@ -349,6 +418,9 @@ pub struct TapretProof {
 impl StrictSerialize for TapretProof {}
 impl StrictDeserialize for TapretProof {}
 
+#[cfg(feature = "serde")]
+crate::impl_strict_serde!(TapretProof);
+
 impl TapretProof {
     /// Restores original scripPubkey before deterministic bitcoin commitment
     /// applied.
@@ -367,4 +439,67 @@ impl Proof for TapretProof {
     fn verify(&self, msg: &Commitment, tx: &Tx) -> Result<(), ConvolveVerifyError> {
         ConvolveCommitProof::<_, Tx, _>::verify(self, msg, tx)
     }
+
+    fn verify_with(
+        &self,
+        msg: &Commitment,
+        tx: &Tx,
+        opts: &VerifyOpts,
+    ) -> Result<(), VerifyError<ConvolveVerifyError>> {
+        Proof::verify(self, msg, tx).map_err(VerifyError::Proof)?;
+        let depth = self.path_proof.partner_node.is_some() as u8;
+        if depth > opts.max_tapret_depth {
+            return Err(PolicyViolation::TapretDepth(depth, opts.max_tapret_depth).into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, U16};
+    use strict_encoding::DeserializeError;
+
+    use super::*;
+
+    fn path_proof_data(a: [u8; 32], b: [u8; 32]) -> Vec<u8> {
+        // Some(RightBranch(a, b)), nonce 0
+        let mut data = vec![0x01, 0x02];
+        data.extend(a);
+        data.extend(b);
+        data.push(0);
+        data
+    }
+
+    #[test]
+    fn right_branch_ordering() {
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+
+        let partner = TapretNodePartner::right_branch(b.into(), a.into());
+        let proof = TapretPathProof::with(partner, 0).unwrap();
+        let data = path_proof_data(a, b);
+        assert_eq!(proof.to_strict_serialized::<U16>().unwrap().as_slice(), &data[..]);
+        assert_eq!(crate::decode_canonical::<TapretPathProof>(&data).unwrap(), proof);
+
+        let data = Confined::try_from(path_proof_data(b, a)).unwrap();
+        assert!(TapretPathProof::from_strict_serialized::<U16>(data).is_err());
+    }
+
+    #[test]
+    fn partner_script_limit() {
+        // Some(RightLeaf(LeafScript)) with the declared script length above
+        // the limit and no script data following
+        let mut data = vec![0x01, 0x01, 0xC0];
+        data.extend((TAPRET_PARTNER_SCRIPT_MAX_LEN as u32 + 1).to_le_bytes());
+        let data = Confined::try_from(data).unwrap();
+        assert!(matches!(
+            TapretPathProof::from_strict_serialized::<U16>(data),
+            Err(DeserializeError::Decode(DecodeError::DataIntegrityError(_)))
+        ));
+
+        let partner = TapretNodePartner::RightLeaf(LeafScript::from_tap_script(default!()));
+        let proof = TapretPathProof::with(partner, 1).unwrap();
+        let data = proof.to_strict_serialized::<U16>().unwrap();
+        assert_eq!(TapretPathProof::from_strict_serialized::<U16>(data).unwrap(), proof);
+    }
 }
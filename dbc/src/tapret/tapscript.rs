@@ -23,11 +23,10 @@ use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
 use amplify::confinement::Confined;
-use bc::{TapCode, TapScript};
+use amplify::ByteArray;
+use bc::TapScript;
 use commit_verify::{mpc, CommitVerify};
-use strict_encoding::{
-    DecodeError, DeserializeError, StreamWriter, StrictDeserialize, StrictEncode, StrictSerialize,
-};
+use strict_encoding::{DecodeError, DeserializeError, StrictDeserialize, StrictSerialize};
 
 use super::TapretFirst;
 use crate::LIB_NAME_BPCORE;
@@ -39,6 +38,10 @@ pub const TAPRET_SCRIPT_COMMITMENT_PREFIX: [u8; 31] = [
     0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x50, 0x6a, 0x21,
 ];
 
+/// Length of the tapret commitment script: [`TAPRET_SCRIPT_COMMITMENT_PREFIX`]
+/// followed by 33 bytes of the serialized commitment data.
+pub const TAPRET_SCRIPT_COMMITMENT_LEN: usize = 64;
+
 /// Information about tapret commitment.
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -62,8 +65,24 @@ impl From<[u8; 33]> for TapretCommitment {
 
 impl TapretCommitment {
     /// Returns serialized representation of the commitment data.
-    pub fn to_vec(&self) -> Vec<u8> {
-        self.to_strict_serialized::<33>().expect("exact size match").release()
+    pub fn to_vec(&self) -> Vec<u8> { self.to_array().to_vec() }
+
+    /// Returns serialized representation of the commitment data as a
+    /// fixed-size array, matching its strict encoding.
+    pub fn to_array(&self) -> [u8; 33] {
+        let mut data = [0u8; 33];
+        data[..32].copy_from_slice(self.mpc.as_slice());
+        data[32] = self.nonce;
+        data
+    }
+
+    /// Constructs tapret commitment script bytes without heap allocations.
+    pub fn script_bytes(&self) -> [u8; TAPRET_SCRIPT_COMMITMENT_LEN] {
+        let prefix_len = TAPRET_SCRIPT_COMMITMENT_PREFIX.len();
+        let mut script = [0u8; TAPRET_SCRIPT_COMMITMENT_LEN];
+        script[..prefix_len].copy_from_slice(&TAPRET_SCRIPT_COMMITMENT_PREFIX);
+        script[prefix_len..].copy_from_slice(&self.to_array());
+        script
     }
 }
 
@@ -97,17 +116,7 @@ impl CommitVerify<TapretCommitment, TapretFirst> for TapScript {
     /// `OP_RETURN`, `OP_PUSHBYTES_33` and serialized commitment data (MPC
     /// commitment + nonce as a single slice).
     fn commit(commitment: &TapretCommitment) -> Self {
-        let mut tapret = TapScript::with_capacity(64);
-        for _ in 0..29 {
-            tapret.push_opcode(TapCode::Reserved);
-        }
-        tapret.push_opcode(TapCode::Return);
-        let mut writer = StreamWriter::in_memory::<33>();
-        commitment.strict_write(&mut writer).expect("tapret commitment must be fitting 33 bytes");
-        let data = writer.unconfine();
-        debug_assert_eq!(data.len(), 33, "tapret commitment must take exactly 33 bytes");
-        tapret.push_slice(&data);
-        tapret
+        TapScript::from_unsafe(commitment.script_bytes().to_vec())
     }
 }
 
@@ -173,6 +182,21 @@ mod test {
         assert_eq!(&script[31..63], commitment.mpc.as_slice());
     }
 
+    #[test]
+    pub fn script_matches_strict_encoding() {
+        let commitment = commitment();
+        let data = commitment.to_strict_serialized::<33>().unwrap().release();
+        assert_eq!(commitment.to_array().as_slice(), data.as_slice());
+
+        let mut script = TapScript::with_capacity(64);
+        for _ in 0..29 {
+            script.push_opcode(bc::TapCode::Reserved);
+        }
+        script.push_opcode(bc::TapCode::Return);
+        script.push_slice(&data);
+        assert_eq!(TapScript::commit(&commitment), script);
+    }
+
     #[test]
     pub fn tapret_commitment_baid64() {
         let commitment = commitment();
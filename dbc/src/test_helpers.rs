@@ -0,0 +1,76 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fixtures shared by the unit tests of the crate.
+
+use std::str::FromStr;
+
+use bc::opcodes::{OP_PUSHBYTES_32, OP_PUSHNUM_1};
+use bc::{
+    InternalPk, LockTime, Outpoint, Sats, ScriptPubkey, SeqNo, Tx, TxIn, TxOut, TxVer, VarIntArray,
+};
+
+use crate::tapret::{TapretPathProof, TapretProof};
+
+/// Internal key used by the tapret test vectors.
+pub const INTERNAL_PK: &str = "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3";
+
+pub fn internal_pk() -> InternalPk { InternalPk::from_str(INTERNAL_PK).unwrap() }
+
+/// Tapret proof with no partner node and the given nonce.
+pub fn tapret_proof(nonce: u8) -> TapretProof {
+    TapretProof {
+        path_proof: TapretPathProof::root(nonce),
+        internal_pk: internal_pk(),
+    }
+}
+
+/// Taproot scriptPubkey with the output key filled with the given byte.
+pub fn p2tr(byte: u8) -> ScriptPubkey {
+    let mut script = vec![OP_PUSHNUM_1, OP_PUSHBYTES_32];
+    script.extend([byte; 32]);
+    ScriptPubkey::from_unsafe(script)
+}
+
+/// Transaction without inputs having zero-value outputs with the given
+/// scripts.
+pub fn tx(scripts: impl IntoIterator<Item = ScriptPubkey>) -> Tx { tx_spending([], scripts) }
+
+/// Transaction spending the outpoints with zero sequence numbers and having
+/// zero-value outputs with the given scripts.
+pub fn tx_spending(
+    inputs: impl IntoIterator<Item = Outpoint>,
+    scripts: impl IntoIterator<Item = ScriptPubkey>,
+) -> Tx {
+    Tx {
+        version: TxVer::V2,
+        inputs: VarIntArray::from_iter_checked(inputs.into_iter().map(|prev_output| TxIn {
+            prev_output,
+            sig_script: none!(),
+            sequence: SeqNo::ZERO,
+            witness: none!(),
+        })),
+        outputs: VarIntArray::from_iter_checked(
+            scripts.into_iter().map(|spk| TxOut::new(spk, Sats::ZERO)),
+        ),
+        lock_time: LockTime::ZERO,
+    }
+}
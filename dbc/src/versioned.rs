@@ -0,0 +1,214 @@
+// Deterministic bitcoin commitments library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioned serialization of DBC proofs with a policy for handling data
+//! produced with the older versions of the proof layout.
+//!
+//! Versioned data are strict-encoded [`VersionedProof`], holding the layout
+//! version and the strict-encoded proof.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Formatter};
+
+use amplify::confinement::{MediumBlob, U24, U32};
+use strict_encoding::{DeserializeError, StrictDeserialize, StrictSerialize};
+
+use crate::opret::OpretProof;
+use crate::tapret::TapretProof;
+use crate::{decode_canonical, DbcProof, LIB_NAME_BPCORE};
+
+/// Errors decoding versioned proof.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum VersionError {
+    /// no versioned data are provided.
+    Empty,
+
+    /// proof has version {0} which is newer than the versions known to this
+    /// library.
+    Unknown(u8),
+
+    /// proof version {0} is rejected by the decoding policy.
+    Rejected(u8),
+
+    /// invalid proof data. Details: {0}
+    #[from]
+    Decode(DeserializeError),
+}
+
+/// Function converting data in an old layout into the current proof
+/// structure.
+pub type Migration<P> = fn(&[u8]) -> Result<P, DeserializeError>;
+
+/// Rule defining how the proofs of some version are handled by the decoder.
+pub enum VersionRule<P> {
+    /// Decode data as if they were in the current layout.
+    Accept,
+
+    /// Refuse to decode the data.
+    Reject,
+
+    /// Convert the data into the current layout with the provided function.
+    Migrate(Migration<P>),
+}
+
+impl<P> Clone for VersionRule<P> {
+    fn clone(&self) -> Self {
+        match self {
+            VersionRule::Accept => VersionRule::Accept,
+            VersionRule::Reject => VersionRule::Reject,
+            VersionRule::Migrate(migration) => VersionRule::Migrate(*migration),
+        }
+    }
+}
+
+impl<P> Debug for VersionRule<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionRule::Accept => f.write_str("Accept"),
+            VersionRule::Reject => f.write_str("Reject"),
+            VersionRule::Migrate(_) => f.write_str("Migrate(..)"),
+        }
+    }
+}
+
+/// Policy defining how the decoder handles proofs of the versions older than
+/// the current one.
+///
+/// Default policy rejects all older versions.
+#[derive(Clone, Debug)]
+pub struct VersionPolicy<P> {
+    rules: BTreeMap<u8, VersionRule<P>>,
+}
+
+impl<P> Default for VersionPolicy<P> {
+    fn default() -> Self { VersionPolicy::strict() }
+}
+
+impl<P> VersionPolicy<P> {
+    /// Constructs policy rejecting all older versions.
+    pub fn strict() -> Self { VersionPolicy { rules: empty!() } }
+
+    /// Adds rule for a specific proof version to the policy.
+    pub fn with(mut self, version: u8, rule: VersionRule<P>) -> Self {
+        self.rules.insert(version, rule);
+        self
+    }
+
+    /// Returns rule used for a specific proof version.
+    pub fn rule(&self, version: u8) -> VersionRule<P> {
+        self.rules.get(&version).cloned().unwrap_or(VersionRule::Reject)
+    }
+}
+
+/// Strict-encoded proof together with the version of its layout.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_BPCORE)]
+pub struct VersionedProof {
+    /// Version of the proof layout.
+    pub version: u8,
+    /// Proof strict-encoded using the layout of the given version.
+    pub proof: MediumBlob,
+}
+
+impl StrictSerialize for VersionedProof {}
+impl StrictDeserialize for VersionedProof {}
+
+/// Proofs serialized together with the version of their layout.
+pub trait Versioned: StrictSerialize + StrictDeserialize {
+    /// Current version of the proof layout.
+    const VERSION: u8;
+
+    /// Serializes proof as [`VersionedProof`] with its current layout version.
+    fn to_versioned(&self) -> Vec<u8> {
+        let versioned = VersionedProof {
+            version: Self::VERSION,
+            proof: self.to_strict_serialized::<U24>().expect("proof exceeds 16MB"),
+        };
+        versioned.to_strict_serialized::<U32>().expect("proof exceeds 4GB").release()
+    }
+
+    /// Deserializes proof, applying the policy to the proofs having an older
+    /// layout version.
+    fn from_versioned(
+        data: impl AsRef<[u8]>,
+        policy: &VersionPolicy<Self>,
+    ) -> Result<Self, VersionError> {
+        if data.as_ref().is_empty() {
+            return Err(VersionError::Empty);
+        }
+        let VersionedProof { version, proof } = decode_canonical(data.as_ref())?;
+        let payload = proof.as_slice();
+        if version > Self::VERSION {
+            return Err(VersionError::Unknown(version));
+        }
+        if version == Self::VERSION {
+            return decode_canonical(payload).map_err(VersionError::from);
+        }
+        match policy.rule(version) {
+            VersionRule::Accept => decode_canonical(payload).map_err(VersionError::from),
+            VersionRule::Reject => Err(VersionError::Rejected(version)),
+            VersionRule::Migrate(migration) => migration(payload).map_err(VersionError::from),
+        }
+    }
+}
+
+impl Versioned for OpretProof {
+    const VERSION: u8 = 1;
+}
+
+impl Versioned for TapretProof {
+    const VERSION: u8 = 1;
+}
+
+impl Versioned for DbcProof {
+    const VERSION: u8 = 1;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn policy() {
+        let proof = DbcProof::Opret(OpretProof::default());
+        let mut data = proof.to_versioned();
+        let versioned = VersionedProof {
+            version: 1,
+            proof: proof.to_strict_serialized::<U24>().unwrap(),
+        };
+        assert_eq!(data, versioned.to_strict_serialized::<U32>().unwrap().release());
+        assert_eq!(DbcProof::from_versioned(b"", &default!()), Err(VersionError::Empty));
+        assert_eq!(DbcProof::from_versioned(&data, &default!()), Ok(proof.clone()));
+
+        data[0] = 2;
+        assert_eq!(DbcProof::from_versioned(&data, &default!()), Err(VersionError::Unknown(2)));
+
+        data[0] = 0;
+        assert_eq!(DbcProof::from_versioned(&data, &default!()), Err(VersionError::Rejected(0)));
+        let policy = VersionPolicy::strict().with(0, VersionRule::Accept);
+        assert_eq!(DbcProof::from_versioned(&data, &policy), Ok(proof.clone()));
+        let policy = VersionPolicy::strict()
+            .with(0, VersionRule::Migrate(|_| Ok(DbcProof::Opret(OpretProof::default()))));
+        assert_eq!(DbcProof::from_versioned(&data, &policy), Ok(proof));
+    }
+}
@@ -0,0 +1,57 @@
+/*
+ * Bitcoin protocol core library C FFI.
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+ */
+
+#ifndef BP_FFI_H
+#define BP_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ * Status codes below 100 are specific to the FFI. The rest are error codes of
+ * the Rust library (`bp::Error::code`): ten thousands define the error
+ * category, hundreds - the error type and the last two digits - the variant.
+ */
+typedef uint16_t bp_status_t;
+
+#define BP_STATUS_OK 0
+#define BP_STATUS_NULL_POINTER 1
+#define BP_STATUS_PANIC 2
+
+#define BP_STATUS_CATEGORY(status) ((status) / 10000)
+#define BP_CATEGORY_PARSE 1
+#define BP_CATEGORY_ENCODING 2
+#define BP_CATEGORY_VERIFICATION 3
+#define BP_CATEGORY_RESOLVER 4
+#define BP_CATEGORY_CONSTRUCTION 5
+
+/* Error types returned by the FFI functions. */
+#define BP_STATUS_TYPE(status) ((status) / 100)
+#define BP_TYPE_INVALID_TX 201
+#define BP_TYPE_INVALID_DATA 205
+#define BP_TYPE_INVALID_COMMITMENT 301
+
+const char *bp_status_message(int code);
+
+bp_status_t bp_seal_outpoint(const uint8_t *seal_data, size_t seal_len,
+                             uint8_t txid_out[32], uint32_t *vout_out);
+
+bp_status_t bp_seal_conceal(const uint8_t *seal_data, size_t seal_len, uint8_t out[32]);
+
+bp_status_t bp_opret_verify(const uint8_t commitment_data[32],
+                            const uint8_t *tx_data, size_t tx_len);
+
+bp_status_t bp_tapret_verify(const uint8_t commitment_data[32],
+                             const uint8_t *proof_data, size_t proof_len,
+                             const uint8_t *tx_data, size_t tx_len);
+
+bp_status_t bp_dbc_verify(const uint8_t commitment_data[32],
+                          const uint8_t *proof_data, size_t proof_len,
+                          const uint8_t *tx_data, size_t tx_len);
+
+#endif /* BP_FFI_H */
@@ -0,0 +1,336 @@
+// Bitcoin protocol core library C FFI.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![deny(
+    non_upper_case_globals,
+    non_camel_case_types,
+    non_snake_case,
+    unused_mut,
+    unused_imports,
+    dead_code,
+    missing_docs
+)]
+
+//! C foreign function interface to the bitcoin protocol core library, allowing
+//! non-Rust software to parse and conceal seals and to verify deterministic
+//! bitcoin commitments.
+//!
+//! All functions return [`BpStatus`] code. Codes of the errors produced by the
+//! library are the same as the ones returned by [`bp::Error::code`]. Binary
+//! data are passed as pointer and length pairs; seals and proofs use strict
+//! encoding, transactions - bitcoin consensus encoding. Output buffers are
+//! allocated by the caller.
+
+use std::ffi::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use amplify::ByteArray;
+use bc::{ConsensusDecode, Tx};
+use bp::Error;
+use commit_verify::{mpc, CommitId};
+use dbc::opret::OpretProof;
+use dbc::tapret::TapretProof;
+use dbc::{decode_canonical, DbcError, DbcProof, Proof};
+use seals::TxoSealDef;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+/// Status code returned by the FFI functions.
+///
+/// Codes below 100 are specific to the FFI; the rest are the codes returned by
+/// [`bp::Error::code`], with ten thousands defining [`bp::ErrorCategory`].
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BpStatus(u16);
+
+impl BpStatus {
+    /// Operation has succeeded.
+    pub const OK: Self = BpStatus(0);
+    /// One of the required pointers is null.
+    pub const NULL_POINTER: Self = BpStatus(1);
+    /// Operation has panicked, which indicates a bug in the library.
+    pub const PANIC: Self = BpStatus(2);
+
+    /// Returns numeric value of the status code.
+    pub const fn code(self) -> u16 { self.0 }
+
+    /// Detects whether the status indicates success.
+    pub const fn is_ok(self) -> bool { self.0 == Self::OK.0 }
+}
+
+impl From<Error> for BpStatus {
+    fn from(err: Error) -> Self { BpStatus(err.code()) }
+}
+
+impl From<DbcError> for BpStatus {
+    fn from(err: DbcError) -> Self { Error::from(err).into() }
+}
+
+/// Runs the function, converting its result into a status code. Panics are
+/// caught and reported with [`BpStatus::PANIC`], since unwinding across the
+/// FFI boundary is undefined behaviour.
+fn status(f: impl FnOnce() -> Result<(), BpStatus>) -> BpStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => BpStatus::OK,
+        Ok(Err(status)) => status,
+        Err(_) => BpStatus::PANIC,
+    }
+}
+
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], BpStatus> {
+    if data.is_null() {
+        return Err(BpStatus::NULL_POINTER);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn commitment(data: *const u8) -> Result<mpc::Commitment, BpStatus> {
+    let data = input(data, 32)?;
+    Ok(mpc::Commitment::from(<[u8; 32]>::try_from(data).expect("fixed length")))
+}
+
+unsafe fn tx(data: *const u8, len: usize) -> Result<Tx, BpStatus> {
+    Tx::consensus_deserialize(input(data, len)?).map_err(|err| Error::from(err).into())
+}
+
+unsafe fn seal(data: *const u8, len: usize) -> Result<TxoSealDef, BpStatus> {
+    decode_canonical(input(data, len)?).map_err(|err| Error::from(err).into())
+}
+
+unsafe fn proof<P: StrictSerialize + StrictDeserialize>(
+    data: *const u8,
+    len: usize,
+) -> Result<P, BpStatus> {
+    decode_canonical(input(data, len)?).map_err(|err| Error::from(err).into())
+}
+
+/// Returns static null-terminated description of a status code. Codes of the
+/// library errors are described by their category.
+#[no_mangle]
+pub extern "C" fn bp_status_message(code: c_int) -> *const c_char {
+    let msg = match code {
+        0 => c"success",
+        1 => c"null pointer",
+        2 => c"internal error",
+        10000..=19999 => c"unable to parse data",
+        20000..=29999 => c"invalid encoded data",
+        30000..=39999 => c"verification has failed",
+        40000..=49999 => c"unable to resolve data",
+        50000..=59999 => c"unable to construct commitment",
+        _ => c"unknown status code",
+    };
+    msg.as_ptr()
+}
+
+/// Parses strict-encoded seal definition, writing 32-byte id of the
+/// transaction containing the sealed output to `txid_out` and its output
+/// number to `vout_out`.
+///
+/// # Safety
+///
+/// `seal_data` must point to `seal_len` readable bytes; `txid_out` must point
+/// to 32 writable bytes and `vout_out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn bp_seal_outpoint(
+    seal_data: *const u8,
+    seal_len: usize,
+    txid_out: *mut u8,
+    vout_out: *mut u32,
+) -> BpStatus {
+    status(|| {
+        let seal = seal(seal_data, seal_len)?;
+        if txid_out.is_null() || vout_out.is_null() {
+            return Err(BpStatus::NULL_POINTER);
+        }
+        let txid = seal.primary.txid.to_byte_array();
+        slice::from_raw_parts_mut(txid_out, 32).copy_from_slice(&txid);
+        *vout_out = seal.primary.vout.into_u32();
+        Ok(())
+    })
+}
+
+/// Conceals strict-encoded seal definition, writing 32-byte concealed seal
+/// (the commitment id of the seal definition) to `out`.
+///
+/// # Safety
+///
+/// `seal_data` must point to `seal_len` readable bytes; `out` must point to
+/// 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bp_seal_conceal(
+    seal_data: *const u8,
+    seal_len: usize,
+    out: *mut u8,
+) -> BpStatus {
+    status(|| {
+        let seal = seal(seal_data, seal_len)?;
+        if out.is_null() {
+            return Err(BpStatus::NULL_POINTER);
+        }
+        let concealed = seal.commit_id();
+        slice::from_raw_parts_mut(out, 32).copy_from_slice(concealed.as_slice());
+        Ok(())
+    })
+}
+
+/// Verifies that the consensus-encoded transaction contains opret commitment
+/// to the 32-byte multi-protocol commitment.
+///
+/// # Safety
+///
+/// `commitment_data` must point to 32 readable bytes and `tx_data` - to
+/// `tx_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bp_opret_verify(
+    commitment_data: *const u8,
+    tx_data: *const u8,
+    tx_len: usize,
+) -> BpStatus {
+    status(|| {
+        let commitment = commitment(commitment_data)?;
+        let tx = tx(tx_data, tx_len)?;
+        OpretProof::default().verify(&commitment, &tx).map_err(|err| DbcError::Opret(err).into())
+    })
+}
+
+/// Verifies that the consensus-encoded transaction contains tapret commitment
+/// to the 32-byte multi-protocol commitment, using strict-encoded tapret
+/// proof.
+///
+/// # Safety
+///
+/// `commitment_data` must point to 32 readable bytes, `proof_data` - to
+/// `proof_len` readable bytes and `tx_data` - to `tx_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bp_tapret_verify(
+    commitment_data: *const u8,
+    proof_data: *const u8,
+    proof_len: usize,
+    tx_data: *const u8,
+    tx_len: usize,
+) -> BpStatus {
+    status(|| {
+        let commitment = commitment(commitment_data)?;
+        let proof = proof::<TapretProof>(proof_data, proof_len)?;
+        let tx = tx(tx_data, tx_len)?;
+        proof.verify(&commitment, &tx).map_err(|err| DbcError::Tapret(err).into())
+    })
+}
+
+/// Verifies that the consensus-encoded transaction contains commitment to the
+/// 32-byte multi-protocol commitment, using strict-encoded DBC proof of any
+/// supported method.
+///
+/// # Safety
+///
+/// `commitment_data` must point to 32 readable bytes, `proof_data` - to
+/// `proof_len` readable bytes and `tx_data` - to `tx_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bp_dbc_verify(
+    commitment_data: *const u8,
+    proof_data: *const u8,
+    proof_len: usize,
+    tx_data: *const u8,
+    tx_len: usize,
+) -> BpStatus {
+    status(|| {
+        let commitment = commitment(commitment_data)?;
+        let proof = proof::<DbcProof>(proof_data, proof_len)?;
+        let tx = tx(tx_data, tx_len)?;
+        Ok(proof.verify(&commitment, &tx)?)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CStr;
+    use std::ptr;
+
+    use bc::{ConsensusEncode, LockTime, Sats, ScriptPubkey, TxOut, TxVer, VarIntArray};
+
+    use super::*;
+
+    fn opret_tx(payload: &[u8]) -> Vec<u8> {
+        Tx {
+            version: TxVer::V2,
+            inputs: Default::default(),
+            outputs: VarIntArray::from_checked(vec![TxOut::new(
+                ScriptPubkey::op_return(payload),
+                Sats::ZERO,
+            )]),
+            lock_time: LockTime::ZERO,
+        }
+        .consensus_serialize()
+    }
+
+    #[test]
+    fn codes() {
+        for err in [
+            Error::from(Tx::consensus_deserialize([0u8]).unwrap_err()),
+            Error::from(decode_canonical::<TxoSealDef>([0u8]).unwrap_err()),
+            Error::from(DbcError::Opret(dbc::opret::OpretVerifyError::NoOpretOutput)),
+        ] {
+            let code = err.code();
+            let status = BpStatus::from(err);
+            assert_eq!(status.code(), code);
+            assert!(!status.is_ok());
+        }
+    }
+
+    #[test]
+    fn panic() {
+        assert_eq!(status(|| panic!("bug")), BpStatus::PANIC);
+        let msg = unsafe { CStr::from_ptr(bp_status_message(BpStatus::PANIC.code() as c_int)) };
+        assert_eq!(msg.to_str(), Ok("internal error"));
+        let msg = unsafe { CStr::from_ptr(bp_status_message(30102)) };
+        assert_eq!(msg.to_str(), Ok("verification has failed"));
+    }
+
+    #[test]
+    fn opret_verify() {
+        let commitment = [7u8; 32];
+        let tx = opret_tx(&commitment);
+        unsafe {
+            assert_eq!(bp_opret_verify(commitment.as_ptr(), tx.as_ptr(), tx.len()), BpStatus::OK);
+            assert_eq!(
+                bp_opret_verify(commitment.as_ptr(), ptr::null(), tx.len()),
+                BpStatus::NULL_POINTER
+            );
+            let status = bp_opret_verify(commitment.as_ptr(), tx.as_ptr(), 3);
+            assert_eq!(status.code() / 100, 201, "consensus decoding error expected");
+            let other = opret_tx(&[8u8; 32]);
+            assert_eq!(
+                bp_opret_verify(commitment.as_ptr(), other.as_ptr(), other.len()).code(),
+                30102
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_seal() {
+        let mut txid = [0u8; 32];
+        let mut vout = 0u32;
+        unsafe {
+            let status = bp_seal_outpoint([1u8].as_ptr(), 1, txid.as_mut_ptr(), &mut vout);
+            assert_eq!(status.code() / 100, 205, "strict decoding error expected");
+        }
+    }
+}
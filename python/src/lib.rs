@@ -0,0 +1,160 @@
+// Bitcoin protocol core library Python bindings.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![deny(
+    non_upper_case_globals,
+    non_camel_case_types,
+    non_snake_case,
+    unused_mut,
+    unused_imports,
+    dead_code,
+    missing_docs
+)]
+
+//! Python bindings to the bitcoin protocol core library, allowing scripts to
+//! parse and conceal seals and to verify deterministic bitcoin commitments.
+//!
+//! Seals and proofs are passed as strict-encoded `bytes`, transactions - as
+//! consensus-encoded `bytes`. Malformed input raises `ValueError`, failed
+//! verification raises `bp_core.VerifyError`.
+
+use bc::{ConsensusDecode, Tx};
+use commit_verify::{mpc, CommitId};
+use dbc::opret::OpretProof;
+use dbc::tapret::TapretProof;
+use dbc::{decode_canonical, DbcProof, Proof};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use seals::TxoSealDef;
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+create_exception!(
+    bp_core,
+    VerifyError,
+    PyException,
+    "Transaction doesn't contain the deterministic bitcoin commitment."
+);
+
+fn decode<T: StrictSerialize + StrictDeserialize>(data: &[u8], name: &str) -> PyResult<T> {
+    decode_canonical(data).map_err(|err| PyValueError::new_err(format!("invalid {name}: {err}")))
+}
+
+fn commitment(data: &[u8]) -> PyResult<mpc::Commitment> {
+    let data = <[u8; 32]>::try_from(data)
+        .map_err(|_| PyValueError::new_err("commitment must be 32 bytes long"))?;
+    Ok(mpc::Commitment::from(data))
+}
+
+fn tx(data: &[u8]) -> PyResult<Tx> {
+    Tx::consensus_deserialize(data)
+        .map_err(|err| PyValueError::new_err(format!("invalid transaction: {err}")))
+}
+
+/// Returns transaction id and output number of the output defined by the
+/// strict-encoded seal definition.
+#[pyfunction]
+fn seal_outpoint(seal: &[u8]) -> PyResult<(String, u32)> {
+    let seal = decode::<TxoSealDef>(seal, "seal definition")?;
+    Ok((seal.primary.txid.to_string(), seal.primary.vout.into_u32()))
+}
+
+/// Returns 32-byte concealed form of the strict-encoded seal definition.
+#[pyfunction]
+fn seal_conceal<'py>(py: Python<'py>, seal: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+    let seal = decode::<TxoSealDef>(seal, "seal definition")?;
+    Ok(PyBytes::new_bound(py, seal.commit_id().as_slice()))
+}
+
+/// Verifies that the transaction contains opret commitment to the 32-byte
+/// multi-protocol commitment.
+#[pyfunction]
+fn opret_verify(commitment: &[u8], tx: &[u8]) -> PyResult<()> {
+    let commitment = self::commitment(commitment)?;
+    let tx = self::tx(tx)?;
+    OpretProof::default()
+        .verify(&commitment, &tx)
+        .map_err(|err| VerifyError::new_err(err.to_string()))
+}
+
+/// Verifies that the transaction contains tapret commitment to the 32-byte
+/// multi-protocol commitment, using strict-encoded tapret proof.
+#[pyfunction]
+fn tapret_verify(commitment: &[u8], proof: &[u8], tx: &[u8]) -> PyResult<()> {
+    let commitment = self::commitment(commitment)?;
+    let proof = decode::<TapretProof>(proof, "tapret proof")?;
+    let tx = self::tx(tx)?;
+    proof.verify(&commitment, &tx).map_err(|err| VerifyError::new_err(err.to_string()))
+}
+
+/// Verifies that the transaction contains commitment to the 32-byte
+/// multi-protocol commitment, using strict-encoded DBC proof of any supported
+/// method.
+#[pyfunction]
+fn dbc_verify(commitment: &[u8], proof: &[u8], tx: &[u8]) -> PyResult<()> {
+    let commitment = self::commitment(commitment)?;
+    let proof = decode::<DbcProof>(proof, "DBC proof")?;
+    let tx = self::tx(tx)?;
+    proof.verify(&commitment, &tx).map_err(|err| VerifyError::new_err(err.to_string()))
+}
+
+/// Bitcoin protocol core library.
+#[pymodule]
+fn bp_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("VerifyError", m.py().get_type_bound::<VerifyError>())?;
+    m.add_function(wrap_pyfunction!(seal_outpoint, m)?)?;
+    m.add_function(wrap_pyfunction!(seal_conceal, m)?)?;
+    m.add_function(wrap_pyfunction!(opret_verify, m)?)?;
+    m.add_function(wrap_pyfunction!(tapret_verify, m)?)?;
+    m.add_function(wrap_pyfunction!(dbc_verify, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{ConsensusEncode, LockTime, Sats, ScriptPubkey, TxOut, TxVer, VarIntArray};
+
+    use super::*;
+
+    #[test]
+    fn opret() {
+        let tx = Tx {
+            version: TxVer::V2,
+            inputs: Default::default(),
+            outputs: VarIntArray::from_checked(vec![TxOut::new(
+                ScriptPubkey::op_return(&[7u8; 32]),
+                Sats::ZERO,
+            )]),
+            lock_time: LockTime::ZERO,
+        }
+        .consensus_serialize();
+        assert!(opret_verify(&[7u8; 32], &tx).is_ok());
+        Python::with_gil(|py| {
+            let err = opret_verify(&[8u8; 32], &tx).unwrap_err();
+            assert!(err.is_instance_of::<VerifyError>(py));
+            let err = opret_verify(&[7u8; 31], &tx).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+            let err = seal_outpoint(&[1u8]).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
@@ -0,0 +1,275 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Batch verification of seal closings with deduplicated witness retrieval.
+
+use std::collections::HashMap;
+
+use bc::{Outpoint, Tx, Txid};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{mmb, Anchor, ResolveError, ResolveWitness, TxoSeal, TxoWitness, WitnessOrd};
+
+/// Cache of witness transactions and their statuses retrieved from a [`ResolveWitness`].
+///
+/// Transactions are cached by their ids: once a transaction spending some outpoint is retrieved,
+/// all other outpoints spent by the same transaction are resolved from the cache. Only successful
+/// lookups are cached: unspent outpoints and unknown transactions are requested again. Since
+/// witness statuses change over time, the cache should not outlive a single validation session.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct WitnessCache {
+    txs: HashMap<Txid, Tx>,
+    spends: HashMap<Outpoint, Txid>,
+    statuses: HashMap<Txid, WitnessOrd>,
+}
+
+impl WitnessCache {
+    /// Constructs empty cache.
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns number of cached transactions.
+    pub fn len(&self) -> usize { self.txs.len() }
+
+    /// Detects whether the cache is empty.
+    pub fn is_empty(&self) -> bool { self.txs.is_empty() }
+
+    /// Removes all cached data.
+    pub fn clear(&mut self) {
+        self.txs.clear();
+        self.spends.clear();
+        self.statuses.clear();
+    }
+
+    /// Returns cached transaction spending a given outpoint, if any.
+    pub fn spending_tx(&self, outpoint: Outpoint) -> Option<&Tx> {
+        self.spends.get(&outpoint).and_then(|txid| self.txs.get(txid))
+    }
+
+    fn resolve_spending<R: ResolveWitness>(
+        &mut self,
+        resolver: &R,
+        outpoint: Outpoint,
+    ) -> Result<Option<Txid>, R::Error> {
+        if let Some(txid) = self.spends.get(&outpoint) {
+            return Ok(Some(*txid));
+        }
+        let Some(tx) = resolver.resolve_spending_tx(outpoint)? else {
+            return Ok(None);
+        };
+        let txid = tx.txid();
+        self.spends.extend(tx.inputs().map(|txin| (txin.prev_output, txid)));
+        self.spends.insert(outpoint, txid);
+        self.txs.entry(txid).or_insert(tx);
+        Ok(Some(txid))
+    }
+
+    fn resolve_status<R: ResolveWitness>(
+        &mut self,
+        resolver: &R,
+        txid: Txid,
+    ) -> Result<WitnessOrd, R::Error> {
+        if let Some(status) = self.statuses.get(&txid) {
+            return Ok(*status);
+        }
+        let status = resolver.resolve_status(txid)?;
+        self.statuses.insert(txid, status);
+        Ok(status)
+    }
+}
+
+/// DBC proof type supported by [`verify_seals_batch`].
+///
+/// If the crate is compiled with `rayon` feature, the proofs are verified in
+/// parallel and must be thread-safe; otherwise any proof type is supported.
+#[cfg(feature = "rayon")]
+pub trait BatchProof: dbc::Proof<Error: Send> + Send + Sync {}
+#[cfg(feature = "rayon")]
+impl<D: dbc::Proof<Error: Send> + Send + Sync> BatchProof for D {}
+
+/// DBC proof type supported by [`verify_seals_batch`].
+///
+/// If the crate is compiled with `rayon` feature, the proofs are verified in
+/// parallel and must be thread-safe; otherwise any proof type is supported.
+#[cfg(not(feature = "rayon"))]
+pub trait BatchProof: dbc::Proof {}
+#[cfg(not(feature = "rayon"))]
+impl<D: dbc::Proof> BatchProof for D {}
+
+/// Verifies closings of many seals at once, returning per-item results in the order of the items.
+///
+/// Witness transaction spending the seals and its status are retrieved from the resolver only
+/// once, even if the same transaction closes many seals or the same seal is present in multiple
+/// items (see [`WitnessCache`]). If the crate is compiled with `rayon` feature, the verification
+/// runs in parallel.
+#[allow(clippy::type_complexity)]
+pub fn verify_seals_batch<D: BatchProof, R: ResolveWitness>(
+    resolver: &R,
+    cache: &mut WitnessCache,
+    items: impl IntoIterator<Item = (TxoSeal<D>, Anchor<D>, mmb::Message)>,
+) -> Vec<Result<(Txid, WitnessOrd), ResolveError<R::Error, D::Error>>> {
+    let mut results = Vec::new();
+    let mut pending = Vec::new();
+    for (pos, (seal, anchor, msg)) in items.into_iter().enumerate() {
+        let outpoint = seal.closing_outpoint(&anchor);
+        let txid = match cache.resolve_spending(resolver, outpoint) {
+            Ok(Some(txid)) => txid,
+            Ok(None) => {
+                results.push(Some(Err(ResolveError::Unspent(outpoint))));
+                continue;
+            }
+            Err(err) => {
+                results.push(Some(Err(ResolveError::Resolver(err))));
+                continue;
+            }
+        };
+        match cache.resolve_status(resolver, txid) {
+            Ok(ord) => {
+                results.push(None);
+                pending.push((pos, seal, anchor, msg, txid, ord));
+            }
+            Err(err) => results.push(Some(Err(ResolveError::Resolver(err)))),
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    let iter = pending.into_par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let iter = pending.into_iter();
+
+    let cache = &*cache;
+    let verified = iter
+        .map(|(pos, seal, anchor, msg, txid, ord)| {
+            let tx = cache.txs.get(&txid).expect("cached transaction").clone();
+            let res = TxoWitness::new(tx, anchor).verify_seals(&[seal], msg).map(|_| (txid, ord));
+            (pos, res)
+        })
+        .collect::<Vec<_>>();
+    for (pos, res) in verified {
+        results[pos] = Some(res.map_err(ResolveError::Seal));
+    }
+    results.into_iter().map(|res| res.expect("all items are processed")).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::test_helpers::{close, opret_output, outpoint, seal, tx};
+    use crate::SealVerifyError;
+
+    struct Resolver {
+        tx: Tx,
+        calls: Cell<usize>,
+    }
+
+    impl ResolveWitness for Resolver {
+        type Error = Infallible;
+
+        fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error> {
+            Ok(Some(self.tx.clone()).filter(|tx| tx.txid() == txid))
+        }
+
+        fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(Some(self.tx.clone())
+                .filter(|tx| tx.inputs().any(|txin| txin.prev_output == outpoint)))
+        }
+
+        fn resolve_status(&self, _txid: Txid) -> Result<WitnessOrd, Self::Error> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(WitnessOrd::Mempool)
+        }
+    }
+
+    #[test]
+    fn cache_by_txid() {
+        let tx = tx([outpoint(1), outpoint(2)], []);
+        let txid = tx.txid();
+        let resolver = Resolver {
+            tx,
+            calls: Cell::new(0),
+        };
+        let mut cache = WitnessCache::new();
+
+        assert_eq!(cache.resolve_spending(&resolver, outpoint(1)), Ok(Some(txid)));
+        assert_eq!(cache.resolve_spending(&resolver, outpoint(2)), Ok(Some(txid)));
+        assert_eq!(resolver.calls.get(), 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.spending_tx(outpoint(2)).map(Tx::txid), Some(txid));
+
+        assert_eq!(cache.resolve_spending(&resolver, outpoint(3)), Ok(None));
+        assert_eq!(cache.resolve_spending(&resolver, outpoint(3)), Ok(None));
+        assert_eq!(resolver.calls.get(), 3);
+
+        assert_eq!(cache.resolve_status(&resolver, txid), Ok(WitnessOrd::Mempool));
+        assert_eq!(cache.resolve_status(&resolver, txid), Ok(WitnessOrd::Mempool));
+        assert_eq!(resolver.calls.get(), 4);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    // Runs both with and without `rayon` feature, covering the sequential and
+    // the parallel verification.
+    #[test]
+    fn batch() {
+        let msg = mmb::Message::from([1u8; 32]);
+        let other = mmb::Message::from([2u8; 32]);
+        let (seal, unspent) = (seal(1), seal(2));
+        let (witness_tx, anchor) = close(&[seal], msg);
+        let txid = witness_tx.txid();
+        let resolver = Resolver {
+            tx: witness_tx,
+            calls: Cell::new(0),
+        };
+        let mut cache = WitnessCache::new();
+
+        let results = verify_seals_batch(&resolver, &mut cache, [
+            (seal, anchor.clone(), msg),
+            (seal, anchor.clone(), other),
+            (unspent, anchor.clone(), msg),
+            (seal, anchor, msg),
+        ]);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], Ok((txid, WitnessOrd::Mempool)));
+        assert!(matches!(results[1], Err(ResolveError::Seal(_))));
+        assert_eq!(results[2], Err(ResolveError::Unspent(unspent.primary)));
+        assert_eq!(results[3], results[0]);
+        // One request for the spending transaction of the unspent seal, and one for the
+        // spending transaction and status of the closed seal
+        assert_eq!(resolver.calls.get(), 3);
+
+        // Witness transaction which doesn't contain the commitment
+        let resolver = Resolver {
+            tx: tx([seal.primary], [opret_output()]),
+            calls: Cell::new(0),
+        };
+        let results = verify_seals_batch(&resolver, &mut WitnessCache::new(), [(
+            seal,
+            close(&[seal], msg).1,
+            msg,
+        )]);
+        assert!(matches!(results[0], Err(ResolveError::Seal(SealVerifyError::Dbc(_)))));
+    }
+}
@@ -0,0 +1,223 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Witness resolver using Bitcoin Core JSON-RPC interface.
+
+use core::str::FromStr;
+
+use amplify::hex::FromHex;
+use base64::Engine;
+use bc::{ConsensusDecode, ConsensusDecodeError, Outpoint, Tx, Txid};
+use serde_json::{json, Value};
+
+use crate::{BlockTime, ResolveWitness, WitnessOrd, WitnessPos};
+
+/// Default number of the most recent blocks scanned for a transaction spending an outpoint.
+pub const BITCOIND_SCAN_DEPTH: u32 = 144;
+
+/// RPC error code returned by Bitcoin Core for unknown transactions and blocks.
+const RPC_INVALID_ADDRESS_OR_KEY: i64 = -5;
+/// RPC error code returned by Bitcoin Core for unsupported methods.
+const RPC_METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum BitcoindError {
+    /// HTTP request to Bitcoin Core has failed. Details: {0}
+    #[from]
+    Http(minreq::Error),
+
+    /// Bitcoin Core has returned RPC error {code}: {message}.
+    Rpc { code: i64, message: String },
+
+    /// Bitcoin Core has returned unexpected response to `{0}` request.
+    InvalidResponse(&'static str),
+
+    /// Bitcoin Core has returned an invalid transaction. Details: {0}
+    #[from]
+    InvalidTx(ConsensusDecodeError),
+}
+
+/// Witness resolver using Bitcoin Core JSON-RPC interface.
+///
+/// Transactions are retrieved with `getrawtransaction`, which requires the node to run with
+/// `txindex=1` for the transactions outside of the mempool. Since Bitcoin Core doesn't index spent
+/// outputs, transactions spending an outpoint are looked up in the mempool and then in the last
+/// [`BitcoindResolver::with_scan_depth`] blocks.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BitcoindResolver {
+    url: String,
+    auth: Option<(String, String)>,
+    scan_depth: u32,
+}
+
+impl BitcoindResolver {
+    /// Constructs resolver for Bitcoin Core RPC server with a given URL, like
+    /// `http://127.0.0.1:8332`.
+    pub fn new(url: impl ToString) -> Self {
+        BitcoindResolver {
+            url: url.to_string(),
+            auth: None,
+            scan_depth: BITCOIND_SCAN_DEPTH,
+        }
+    }
+
+    /// Sets user name and password for the RPC authentication.
+    pub fn with_auth(mut self, user: impl ToString, password: impl ToString) -> Self {
+        self.auth = Some((user.to_string(), password.to_string()));
+        self
+    }
+
+    /// Sets number of the most recent blocks scanned for a transaction spending an outpoint.
+    pub fn with_scan_depth(mut self, scan_depth: u32) -> Self {
+        self.scan_depth = scan_depth;
+        self
+    }
+
+    /// Performs RPC call, returning `None` if the requested transaction or block is not known.
+    pub(crate) fn call(
+        &self,
+        method: &'static str,
+        params: Value,
+    ) -> Result<Option<Value>, BitcoindError> {
+        let body =
+            json!({ "jsonrpc": "1.0", "id": "bp-seals", "method": method, "params": params });
+        let mut req = minreq::post(&self.url).with_json(&body)?;
+        if let Some((user, password)) = &self.auth {
+            let creds =
+                base64::engine::general_purpose::STANDARD.encode(format!("{user}:{password}"));
+            req = req.with_header("Authorization", format!("Basic {creds}"));
+        }
+        let mut resp = req.send()?.json::<Value>()?;
+        match resp.get("error") {
+            None | Some(Value::Null) => Ok(Some(resp["result"].take())),
+            Some(err) => {
+                let code = err["code"].as_i64().unwrap_or_default();
+                if code == RPC_INVALID_ADDRESS_OR_KEY {
+                    return Ok(None);
+                }
+                let message = err["message"].as_str().unwrap_or_default().to_owned();
+                Err(BitcoindError::Rpc { code, message })
+            }
+        }
+    }
+
+    fn raw_tx(&self, txid: Txid, block_hash: Option<&str>) -> Result<Option<Tx>, BitcoindError> {
+        let params = match block_hash {
+            Some(block_hash) => json!([txid.to_string(), false, block_hash]),
+            None => json!([txid.to_string(), false]),
+        };
+        let Some(hex) = self.call("getrawtransaction", params)? else {
+            return Ok(None);
+        };
+        let data = hex
+            .as_str()
+            .and_then(|hex| Vec::<u8>::from_hex(hex).ok())
+            .ok_or(BitcoindError::InvalidResponse("getrawtransaction"))?;
+        Ok(Some(Tx::consensus_deserialize(data)?))
+    }
+
+    fn find_spending_txid(txs: &Value, outpoint: Outpoint) -> Option<Txid> {
+        txs.as_array()?.iter().find_map(|tx| {
+            let spends = tx["vin"].as_array()?.iter().any(|input| {
+                input["txid"].as_str() == Some(outpoint.txid.to_string().as_str())
+                    && input["vout"].as_u64() == Some(outpoint.vout.into_u32() as u64)
+            });
+            if spends {
+                Txid::from_str(tx["txid"].as_str()?).ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl ResolveWitness for BitcoindResolver {
+    type Error = BitcoindError;
+
+    fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error> { self.raw_tx(txid, None) }
+
+    fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error> {
+        let prevout =
+            json!([[{ "txid": outpoint.txid.to_string(), "vout": outpoint.vout.into_u32() }]]);
+        // `gettxspendingprevout` is not available before Bitcoin Core v24
+        let spending = match self.call("gettxspendingprevout", prevout) {
+            Err(BitcoindError::Rpc {
+                code: RPC_METHOD_NOT_FOUND,
+                ..
+            }) => None,
+            res => res?,
+        };
+        if let Some(txid) = spending.as_ref().and_then(|val| val[0]["spendingtxid"].as_str()) {
+            let txid = Txid::from_str(txid)
+                .map_err(|_| BitcoindError::InvalidResponse("gettxspendingprevout"))?;
+            return self.resolve_tx(txid);
+        }
+
+        let tip = self
+            .call("getblockcount", json!([]))?
+            .and_then(|count| count.as_u64())
+            .ok_or(BitcoindError::InvalidResponse("getblockcount"))?;
+        for height in (0..=tip).rev().take(self.scan_depth as usize) {
+            let Some(block_hash) = self.call("getblockhash", json!([height]))? else {
+                continue;
+            };
+            let Some(block) = self.call("getblock", json!([block_hash, 2]))? else {
+                continue;
+            };
+            if let Some(txid) = Self::find_spending_txid(&block["tx"], outpoint) {
+                return self.raw_tx(txid, block_hash.as_str());
+            }
+        }
+        Ok(None)
+    }
+
+    fn resolve_status(&self, txid: Txid) -> Result<WitnessOrd, Self::Error> {
+        let Some(tx) = self.call("getrawtransaction", json!([txid.to_string(), true]))? else {
+            return Ok(WitnessOrd::Unknown);
+        };
+        let Some(block_hash) = tx["blockhash"].as_str() else {
+            return Ok(WitnessOrd::Mempool);
+        };
+        // Block may get reorged out of the blockchain between the requests
+        let Some(block) = self.call("getblock", json!([block_hash, 1]))? else {
+            return Ok(WitnessOrd::Unknown);
+        };
+        if block["confirmations"].as_i64().unwrap_or_default() < 1 {
+            return Ok(WitnessOrd::Unknown);
+        }
+        let height = block["height"].as_u64().ok_or(BitcoindError::InvalidResponse("getblock"))?;
+        let txid = txid.to_string();
+        let pos = block["tx"]
+            .as_array()
+            .and_then(|txids| txids.iter().position(|id| id.as_str() == Some(txid.as_str())))
+            .ok_or(BitcoindError::InvalidResponse("getblock"))?;
+        let pos = match (block["time"].as_u64(), block["mediantime"].as_u64()) {
+            (Some(timestamp), Some(mtp)) => WitnessPos::with_time(
+                height as u32,
+                pos as u32,
+                BlockTime::new(timestamp as u32, mtp as u32),
+            ),
+            _ => WitnessPos::new(height as u32, pos as u32),
+        };
+        Ok(WitnessOrd::Mined(pos))
+    }
+}
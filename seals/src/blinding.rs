@@ -0,0 +1,140 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic seal noise derived from a BIP-32 extended public key.
+
+use bc::{CompressedPk, InvalidPubkey, Outpoint};
+use commit_verify::{DigestExt, Sha256};
+
+use crate::TxoSeal;
+
+/// Tag of the hash used to derive seal noise from an extended public key.
+pub const XPUB_BLINDING_TAG: &str = "urn:lnp-bp:seals:xpub-blinding#2024-12-02";
+
+/// Length of the BIP-32 serialized extended public key.
+pub const XPUB_DATA_LEN: usize = 78;
+
+/// Source of deterministic seal noise derived from a BIP-32 extended public
+/// key.
+///
+/// The noise for a seal is computed from a tagged SHA256 hash
+/// ([`XPUB_BLINDING_TAG`]) over the xpub chain code, the xpub public key and
+/// the keychain number; the index within the keychain is used as the seal
+/// nonce. Since only the public part of the key is used, a watch-only wallet
+/// instance holding the same xpub re-creates exactly the same seals as the
+/// signing instance, without sharing any database.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct XpubBlinding {
+    chain_code: [u8; 32],
+    pubkey: CompressedPk,
+}
+
+impl XpubBlinding {
+    /// Constructs blinding source from the public key and chain code of an
+    /// extended public key.
+    pub fn new(pubkey: CompressedPk, chain_code: [u8; 32]) -> Self {
+        XpubBlinding { chain_code, pubkey }
+    }
+
+    /// Constructs blinding source from a BIP-32 serialized extended public key
+    /// (without base58 check encoding).
+    pub fn from_xpub_data(data: [u8; XPUB_DATA_LEN]) -> Result<Self, InvalidPubkey<33>> {
+        let mut chain_code = [0u8; 32];
+        chain_code.copy_from_slice(&data[13..45]);
+        let pubkey = CompressedPk::from_bytes(&data[45..])?;
+        Ok(XpubBlinding { chain_code, pubkey })
+    }
+
+    /// Returns the public key of the extended public key.
+    pub fn pubkey(&self) -> CompressedPk { self.pubkey }
+
+    /// Returns the chain code of the extended public key.
+    pub fn chain_code(&self) -> [u8; 32] { self.chain_code }
+
+    /// Returns noise engine for the seals of a given keychain.
+    pub fn noise_engine(&self, keychain: u32) -> Sha256 {
+        let mut engine = Sha256::from_tag(XPUB_BLINDING_TAG);
+        engine.input_raw(&self.chain_code);
+        engine.input_raw(&self.pubkey.to_byte_array());
+        engine.input_raw(&keychain.to_be_bytes());
+        engine
+    }
+
+    /// Constructs seal over an outpoint with the noise derived for a given
+    /// keychain and index.
+    pub fn seal<D: dbc::Proof>(
+        &self,
+        outpoint: impl Into<Outpoint>,
+        keychain: u32,
+        index: u32,
+    ) -> TxoSeal<D> {
+        TxoSeal::no_fallback(outpoint, self.noise_engine(keychain), index as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::hex::ToHex;
+    use bc::{Txid, Vout};
+    use dbc::opret::OpretProof;
+
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        let pubkey = CompressedPk::from_str(
+            "02c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3",
+        )
+        .unwrap();
+        let mut data = [0u8; XPUB_DATA_LEN];
+        data[13..45].copy_from_slice(&[7u8; 32]);
+        data[45..].copy_from_slice(&pubkey.to_byte_array());
+
+        let signer = XpubBlinding::new(pubkey, [7u8; 32]);
+        let watch_only = XpubBlinding::from_xpub_data(data).unwrap();
+        assert_eq!(signer, watch_only);
+
+        let outpoint = Outpoint::new(Txid::from([1u8; 32]), Vout::from_u32(0));
+        let seal = signer.seal::<OpretProof>(outpoint, 0, 5);
+        assert_eq!(seal, watch_only.seal(outpoint, 0, 5));
+        assert_ne!(seal, watch_only.seal(outpoint, 1, 5));
+        assert_ne!(seal, watch_only.seal(outpoint, 0, 6));
+    }
+
+    #[test]
+    fn tagged_hash() {
+        let pubkey = CompressedPk::from_str(
+            "02c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3",
+        )
+        .unwrap();
+        let blinding = XpubBlinding::new(pubkey, [7u8; 32]);
+        assert_eq!(
+            blinding.noise_engine(0).finish().to_hex(),
+            "294cf6a48d3500b89fd062f5af721d0e67a9fe6f965b55314c6dc8d91549dac0"
+        );
+        assert_eq!(
+            blinding.noise_engine(1).finish().to_hex(),
+            "e4e4aa59765d8cfa1f60126289972195bd93dabcc3e720754fb5275f87eab112"
+        );
+    }
+}
@@ -0,0 +1,300 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Seals defined over outputs of a transaction closing another seal.
+
+use amplify::{ByteArray, Bytes};
+use bc::{Outpoint, Tx, Txid, Vout};
+use commit_verify::{CommitId, DigestExt, Sha256, StrictHash};
+
+use crate::{Noise, TxoSeal, TxoSealDef, TxoSealExt};
+
+/// Errors resolving [`ChainedSeal`] into a concrete seal.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ChainedSealError {
+    /// seal {0} is not the parent of the chained seal.
+    ParentMismatch(TxoSealDef),
+
+    /// transaction {txid} spends neither primary nor fallback outpoint of the
+    /// parent seal over {seal}.
+    NotClosing { txid: Txid, seal: Outpoint },
+
+    /// transaction {txid} closing the parent seal has no output #{vout}.
+    NoOutput { txid: Txid, vout: Vout },
+}
+
+/// Seal defined as "output `vout` of the transaction closing seal `parent`".
+///
+/// Unlike seals defined with [`TxoSeal::vout_no_fallback`], which refer to an
+/// output of *some* witness transaction, the chained seal identifies the
+/// parent seal whose witness is meant by the seal commitment id, and thus
+/// remains unambiguous in multi-hop protocol continuations, where several
+/// witness transactions may be involved. Once the witness closing the parent
+/// seal is known, the chained seal is resolved into a concrete [`TxoSeal`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{parent}:{vout}/{secondary}")]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ChainedSeal {
+    /// Commitment id of the parent seal definition.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub parent: StrictHash,
+    /// Output of the transaction closing the parent seal.
+    pub vout: Vout,
+    /// Secondary part of the seal definition.
+    pub secondary: TxoSealExt,
+}
+
+impl ChainedSeal {
+    /// Constructs chained seal with a noise derived from the parent seal id,
+    /// output number and a nonce.
+    ///
+    /// `nonce` is a deterministic incremental number, preventing from creating
+    /// the same seal if the same output is used.
+    pub fn with_noise(
+        parent: &TxoSealDef,
+        vout: impl Into<Vout>,
+        mut noise_engine: Sha256,
+        nonce: u64,
+    ) -> Self {
+        let parent = parent.commit_id();
+        let vout = vout.into();
+        noise_engine.input_raw(&nonce.to_be_bytes());
+        noise_engine.input_raw(parent.as_ref());
+        noise_engine.input_raw(&vout.to_u32().to_be_bytes());
+        let mut noise = [0xFFu8; 40];
+        noise[..32].copy_from_slice(&noise_engine.finish());
+        ChainedSeal {
+            parent,
+            vout,
+            secondary: TxoSealExt::Noise(Noise::from(Bytes::from_byte_array(noise))),
+        }
+    }
+
+    /// Constructs chained seal with a fallback outpoint.
+    pub fn with_fallback(parent: &TxoSealDef, vout: impl Into<Vout>, fallback: Outpoint) -> Self {
+        ChainedSeal {
+            parent: parent.commit_id(),
+            vout: vout.into(),
+            secondary: TxoSealExt::Fallback(fallback),
+        }
+    }
+
+    /// Detects whether the seal is chained to a given parent seal.
+    pub fn is_child_of(&self, parent: &TxoSealDef) -> bool { parent.commit_id() == self.parent }
+
+    /// Resolves chained seal into a concrete seal, given the parent seal and
+    /// the witness transaction closing it.
+    ///
+    /// The witness may close the parent seal by spending either its primary
+    /// or its fallback outpoint (see [`TxoSealExt::Fallback`]).
+    pub fn resolve<D: dbc::Proof>(
+        &self,
+        parent: &TxoSealDef,
+        witness: &Tx,
+    ) -> Result<TxoSeal<D>, ChainedSealError> {
+        if !self.is_child_of(parent) {
+            return Err(ChainedSealError::ParentMismatch(*parent));
+        }
+        let primary = witness_output(parent, witness, self.vout)?;
+        Ok(TxoSeal::from_definition(TxoSealDef {
+            primary,
+            secondary: self.secondary,
+        }))
+    }
+
+    /// Resolves all seals chained to a given parent seal, returning them in
+    /// the same order.
+    ///
+    /// Errors if any of the seals can't be resolved.
+    pub fn resolve_all<'seal, D: dbc::Proof>(
+        seals: impl IntoIterator<Item = &'seal ChainedSeal>,
+        parent: &TxoSealDef,
+        witness: &Tx,
+    ) -> Result<Vec<TxoSeal<D>>, ChainedSealError> {
+        seals.into_iter().map(|seal| seal.resolve(parent, witness)).collect()
+    }
+}
+
+/// Defines follow-up seals over the selected outputs of the witness
+/// transaction closing a seal (like change outputs), for protocol state
+/// transitions continuing onto the witness transaction.
+///
+/// The seals are defined with a noise derived from the engine, using `nonce`
+/// for the first of the seals and incrementing it for each next seal. Errors
+/// if the transaction doesn't close the seal or doesn't have some of the
+/// outputs.
+pub fn follow_up_seals<D: dbc::Proof>(
+    closed: &TxoSealDef,
+    witness: &Tx,
+    vouts: impl IntoIterator<Item = Vout>,
+    noise_engine: Sha256,
+    nonce: u64,
+) -> Result<Vec<TxoSeal<D>>, ChainedSealError> {
+    vouts
+        .into_iter()
+        .zip(nonce..)
+        .map(|(vout, nonce)| {
+            let outpoint = witness_output(closed, witness, vout)?;
+            Ok(TxoSeal::no_fallback(outpoint, noise_engine.clone(), nonce))
+        })
+        .collect()
+}
+
+fn witness_output(
+    closed: &TxoSealDef,
+    witness: &Tx,
+    vout: Vout,
+) -> Result<Outpoint, ChainedSealError> {
+    let txid = witness.txid();
+    let fallback = match closed.secondary {
+        TxoSealExt::Fallback(outpoint) => Some(outpoint),
+        TxoSealExt::Noise(_) => None,
+    };
+    if !witness
+        .inputs()
+        .any(|txin| txin.prev_output == closed.primary || Some(txin.prev_output) == fallback)
+    {
+        return Err(ChainedSealError::NotClosing {
+            txid,
+            seal: closed.primary,
+        });
+    }
+    if vout.into_usize() >= witness.outputs().count() {
+        return Err(ChainedSealError::NoOutput { txid, vout });
+    }
+    Ok(Outpoint::new(txid, vout))
+}
+
+#[cfg(test)]
+mod test {
+    use dbc::opret::OpretProof;
+
+    use super::*;
+    use crate::test_helpers::{p2sh_output, tx};
+
+    fn witness(prev_output: Outpoint) -> Tx { tx([prev_output], [p2sh_output(1), p2sh_output(2)]) }
+
+    #[test]
+    fn resolve() {
+        let parent = TxoSeal::<OpretProof>::no_fallback(
+            Outpoint::new(Txid::from([1u8; 32]), Vout::from_u32(0)),
+            Sha256::default(),
+            0,
+        )
+        .to_definition();
+        let other = TxoSeal::<OpretProof>::no_fallback(parent.primary, Sha256::default(), 1)
+            .to_definition();
+        let seal = ChainedSeal::with_noise(&parent, 1u32, Sha256::default(), 0);
+
+        let witness = witness(parent.primary);
+
+        let resolved = seal.resolve::<OpretProof>(&parent, &witness).unwrap();
+        assert_eq!(resolved.primary, Outpoint::new(witness.txid(), Vout::from_u32(1)));
+        assert_eq!(resolved.secondary, seal.secondary);
+        assert_eq!(
+            seal.resolve::<OpretProof>(&other, &witness),
+            Err(ChainedSealError::ParentMismatch(other))
+        );
+
+        let far = ChainedSeal::with_noise(&parent, 2u32, Sha256::default(), 0);
+        assert_eq!(
+            far.resolve::<OpretProof>(&parent, &witness),
+            Err(ChainedSealError::NoOutput {
+                txid: witness.txid(),
+                vout: Vout::from_u32(2)
+            })
+        );
+    }
+
+    #[test]
+    fn follow_up() {
+        let parent = TxoSeal::<OpretProof>::no_fallback(
+            Outpoint::new(Txid::from([1u8; 32]), Vout::from_u32(0)),
+            Sha256::default(),
+            0,
+        )
+        .to_definition();
+        let witness = witness(parent.primary);
+
+        let seals = follow_up_seals::<OpretProof>(
+            &parent,
+            &witness,
+            [Vout::from_u32(0), Vout::from_u32(1)],
+            Sha256::default(),
+            10,
+        )
+        .unwrap();
+        assert_eq!(seals[1].primary, Outpoint::new(witness.txid(), Vout::from_u32(1)));
+        assert_ne!(seals[0].secondary, seals[1].secondary);
+        let unrelated = TxoSeal::<OpretProof>::no_fallback(
+            Outpoint::new(Txid::from([9u8; 32]), Vout::from_u32(0)),
+            Sha256::default(),
+            0,
+        )
+        .to_definition();
+        assert_eq!(
+            follow_up_seals::<OpretProof>(
+                &unrelated,
+                &witness,
+                [Vout::from_u32(0)],
+                Sha256::default(),
+                0
+            ),
+            Err(ChainedSealError::NotClosing {
+                txid: witness.txid(),
+                seal: unrelated.primary
+            })
+        );
+    }
+
+    #[test]
+    fn resolve_fallback() {
+        let fallback = Outpoint::new(Txid::from([2u8; 32]), Vout::from_u32(1));
+        let parent = TxoSealDef {
+            primary: Outpoint::new(Txid::from([1u8; 32]), Vout::from_u32(0)),
+            secondary: TxoSealExt::Fallback(fallback),
+        };
+        let seal = ChainedSeal::with_noise(&parent, 0u32, Sha256::default(), 0);
+        let witness = witness(fallback);
+        let resolved = seal.resolve::<OpretProof>(&parent, &witness).unwrap();
+        assert_eq!(resolved.primary, Outpoint::new(witness.txid(), Vout::from_u32(0)));
+
+        let unrelated = TxoSealDef {
+            secondary: TxoSealExt::Fallback(Outpoint::new(
+                Txid::from([3u8; 32]),
+                Vout::from_u32(0),
+            )),
+            ..parent
+        };
+        let seal = ChainedSeal::with_noise(&unrelated, 0u32, Sha256::default(), 0);
+        assert_eq!(
+            seal.resolve::<OpretProof>(&unrelated, &witness),
+            Err(ChainedSealError::NotClosing {
+                txid: witness.txid(),
+                seal: unrelated.primary
+            })
+        );
+    }
+}
@@ -0,0 +1,297 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of seal definitions and anchors produced by bp-core v0.10 and
+//! their upgrade to the current types.
+//!
+//! In v0.10 seals were represented by `BlindSeal`, strict-encoded as the
+//! closing method (`u8`), the transaction pointer (an enum with tag `0` for
+//! the witness transaction and tag `1` followed by a txid), the output number
+//! (`u32`) and the blinding factor (`u64`), all integers in little-endian byte
+//! order.
+//!
+//! v0.10 anchors were strict-encoded as the witness txid, the multi-protocol
+//! commitment merkle proof (position `u32`, cofactor `u16` and the merkle path
+//! prefixed with `u8` length) and the DBC proof (an enum with tag `0` for the
+//! opret proof, which has no data, and tag `1` followed by the tapret proof).
+//! The only difference from the current [`dbc::DbcAnchor`] encoding is the
+//! absence of the merkle proof method, which was always `sha256t`.
+//!
+//! Concealed v0.10 seals (`SecretSeal`) are one-way hashes of the seal
+//! definitions and can't be upgraded; the same applies to the data
+//! committing to them.
+
+use amplify::{ByteArray, Bytes};
+use bc::{Outpoint, Txid, Vout};
+use commit_verify::mpc;
+use dbc::{decode_canonical, DbcProof, Method};
+
+use crate::{Noise, TxoSeal, TxoSealDef, TxoSealExt, WITNESS_TXID_PLACEHOLDER};
+
+/// Errors decoding and upgrading v0.10 seals and anchors.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CompatError {
+    /// legacy seal data have invalid length {0}.
+    InvalidLen(usize),
+
+    /// legacy seal uses unknown closing method {0}.
+    UnknownMethod(u8),
+
+    /// legacy seal uses unknown transaction pointer tag {0}.
+    UnknownTxPtr(u8),
+
+    /// legacy anchor data are invalid or non-canonical.
+    InvalidAnchor,
+
+    /// legacy seal uses {actual} closing method, while {expected} is required.
+    MethodMismatch {
+        /// Method required for the upgraded seal.
+        expected: Method,
+        /// Method used by the legacy seal.
+        actual: Method,
+    },
+}
+
+/// Transaction pointer of v0.10 seals.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum LegacyTxPtr {
+    /// Seal is defined over an output of the witness transaction.
+    WitnessTx,
+    /// Seal is defined over an output of a specific transaction.
+    Txid(Txid),
+}
+
+/// Seal definition of bp-core v0.10 (`BlindSeal`).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LegacyBlindSeal {
+    /// Method used to close the seal.
+    pub method: Method,
+    /// Transaction containing the sealed output.
+    pub txid: LegacyTxPtr,
+    /// Number of the sealed output.
+    pub vout: Vout,
+    /// Blinding factor hiding the seal.
+    pub blinding: u64,
+}
+
+impl LegacyBlindSeal {
+    /// Decodes v0.10 strict-encoded seal.
+    pub fn from_legacy_bytes(data: &[u8]) -> Result<Self, CompatError> {
+        let invalid_len = || CompatError::InvalidLen(data.len());
+        let (&method, rest) = data.split_first().ok_or_else(invalid_len)?;
+        let method = Method::try_from(method).map_err(|_| CompatError::UnknownMethod(method))?;
+        let (&tag, rest) = rest.split_first().ok_or_else(invalid_len)?;
+        let (txid, rest) = match tag {
+            0 => (LegacyTxPtr::WitnessTx, rest),
+            1 if rest.len() >= 32 => {
+                let (txid, rest) = rest.split_at(32);
+                let txid = Txid::from(<[u8; 32]>::try_from(txid).expect("fixed length"));
+                (LegacyTxPtr::Txid(txid), rest)
+            }
+            1 => return Err(invalid_len()),
+            tag => return Err(CompatError::UnknownTxPtr(tag)),
+        };
+        if rest.len() != 12 {
+            return Err(invalid_len());
+        }
+        let vout = u32::from_le_bytes(rest[..4].try_into().expect("fixed length"));
+        let blinding = u64::from_le_bytes(rest[4..].try_into().expect("fixed length"));
+        Ok(LegacyBlindSeal {
+            method,
+            txid,
+            vout: Vout::from_u32(vout),
+            blinding,
+        })
+    }
+
+    /// Upgrades legacy seal to the current seal definition.
+    ///
+    /// Seals pointing to the witness transaction use
+    /// [`WITNESS_TXID_PLACEHOLDER`] txid. The blinding factor is kept as
+    /// the first 8 bytes of the seal noise; the rest of the noise is filled
+    /// with `0xFF` bytes.
+    pub fn to_definition(&self) -> TxoSealDef {
+        let txid = match self.txid {
+            LegacyTxPtr::WitnessTx => Txid::from(WITNESS_TXID_PLACEHOLDER),
+            LegacyTxPtr::Txid(txid) => txid,
+        };
+        let mut noise = [0xFFu8; 40];
+        noise[..8].copy_from_slice(&self.blinding.to_le_bytes());
+        TxoSealDef {
+            primary: Outpoint::new(txid, self.vout),
+            secondary: TxoSealExt::Noise(Noise::from(Bytes::from_byte_array(noise))),
+        }
+    }
+
+    /// Upgrades legacy seal to the current seal type, checking that the seal
+    /// closing method matches the DBC proof type.
+    pub fn upgrade<D: dbc::Proof>(&self) -> Result<TxoSeal<D>, CompatError> {
+        if self.method != D::METHOD {
+            return Err(CompatError::MethodMismatch {
+                expected: D::METHOD,
+                actual: self.method,
+            });
+        }
+        Ok(TxoSeal::from_definition(self.to_definition()))
+    }
+}
+
+/// Anchor of bp-core v0.10.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LegacyAnchor {
+    /// Transaction containing deterministic bitcoin commitment.
+    pub txid: Txid,
+    /// Multi-protocol commitment merkle proof.
+    pub mpc_proof: mpc::MerkleProof,
+    /// Proof of the DBC commitment.
+    pub dbc_proof: DbcProof,
+}
+
+impl LegacyAnchor {
+    /// Decodes v0.10 strict-encoded anchor.
+    pub fn from_legacy_bytes(data: &[u8]) -> Result<Self, CompatError> {
+        if data.len() < 32 {
+            return Err(CompatError::InvalidLen(data.len()));
+        }
+        let (txid, rest) = data.split_at(32);
+        let mut current = Vec::with_capacity(data.len() + 1);
+        current.extend_from_slice(txid);
+        current.push(mpc::Method::Sha256t as u8);
+        current.extend_from_slice(rest);
+        let anchor = decode_canonical::<dbc::DbcAnchor<DbcProof>>(current)
+            .map_err(|_| CompatError::InvalidAnchor)?;
+        Ok(LegacyAnchor {
+            txid: anchor.txid,
+            mpc_proof: anchor.mpc_proof,
+            dbc_proof: anchor.dbc_proof,
+        })
+    }
+
+    /// Returns method used by the anchor commitment.
+    pub fn method(&self) -> Method { self.dbc_proof.method() }
+
+    /// Upgrades legacy anchor to the current anchor type.
+    pub fn upgrade(&self) -> dbc::DbcAnchor<DbcProof> {
+        dbc::DbcAnchor {
+            txid: self.txid,
+            mpc_proof: self.mpc_proof.clone(),
+            dbc_proof: self.dbc_proof.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::hex::FromHex;
+    use dbc::opret::OpretProof;
+    use dbc::tapret::TapretProof;
+    use strict_encoding::StrictSerialize;
+
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let mut data = vec![0x01, 0x01];
+        data.extend([0xAB; 32]);
+        data.extend(2u32.to_le_bytes());
+        data.extend(0x0102030405060708u64.to_le_bytes());
+        let seal = LegacyBlindSeal::from_legacy_bytes(&data).unwrap();
+        assert_eq!(seal, LegacyBlindSeal {
+            method: Method::TapretFirst,
+            txid: LegacyTxPtr::Txid(Txid::from([0xAB; 32])),
+            vout: Vout::from_u32(2),
+            blinding: 0x0102030405060708,
+        });
+        let upgraded = seal.upgrade::<TapretProof>().unwrap();
+        assert_eq!(upgraded.primary, Outpoint::new(Txid::from([0xAB; 32]), Vout::from_u32(2)));
+        assert_eq!(
+            seal.upgrade::<OpretProof>(),
+            Err(CompatError::MethodMismatch {
+                expected: Method::OpretFirst,
+                actual: Method::TapretFirst
+            })
+        );
+
+        let witness = [0x00, 0x00, 1, 0, 0, 0, 8, 7, 6, 5, 4, 3, 2, 1];
+        let seal = LegacyBlindSeal::from_legacy_bytes(&witness).unwrap();
+        assert_eq!(seal.txid, LegacyTxPtr::WitnessTx);
+        assert_eq!(seal.to_definition().primary.txid, Txid::from(WITNESS_TXID_PLACEHOLDER));
+
+        assert_eq!(
+            LegacyBlindSeal::from_legacy_bytes(&witness[..10]),
+            Err(CompatError::InvalidLen(10))
+        );
+        assert_eq!(LegacyBlindSeal::from_legacy_bytes(&[0x02]), Err(CompatError::UnknownMethod(2)));
+    }
+
+    // Anchors assembled by hand following the v0.10 layout described in the
+    // module docs: txid, mpc proof without the method and the DBC proof. They
+    // are not produced by the bp-dbc 0.10 crate and must be replaced with the
+    // output of `Anchor::<MerkleProof, DbcProof>::to_strict_serialized` from
+    // bp-dbc 0.10.x once such vectors are generated.
+    const OPRET_ANCHOR: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b\
+                                03000000040001\
+                                5a1b0c2d3e4f5061728394a5b6c7d8e9fa0b1c2d3e4f5061728394a5b6c7d8e9\
+                                00";
+    const TAPRET_ANCHOR: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b\
+                                 0000000001000001\
+                                 0003\
+                                 79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn legacy_anchor(hex: &str) -> Result<LegacyAnchor, CompatError> {
+        let data = Vec::<u8>::from_hex(hex).unwrap();
+        LegacyAnchor::from_legacy_bytes(&data)
+    }
+
+    #[test]
+    fn decode_anchor() {
+        let anchor = legacy_anchor(OPRET_ANCHOR).unwrap();
+        assert_eq!(anchor.method(), Method::OpretFirst);
+        assert_eq!(anchor.dbc_proof, DbcProof::Opret(OpretProof::default()));
+        let upgraded = anchor.upgrade();
+        assert_eq!(upgraded.txid, anchor.txid);
+        let current = upgraded.to_strict_serialized::<{ dbc::PROOF_MAX_LEN }>().unwrap();
+        let legacy = Vec::<u8>::from_hex(OPRET_ANCHOR).unwrap();
+        assert_eq!(current[32], mpc::Method::Sha256t as u8);
+        assert_eq!(current[..32], legacy[..32]);
+        assert_eq!(current[33..], legacy[32..]);
+
+        let anchor = legacy_anchor(TAPRET_ANCHOR).unwrap();
+        assert_eq!(anchor.method(), Method::TapretFirst);
+        let DbcProof::Tapret(ref proof) = anchor.dbc_proof else {
+            panic!("tapret proof expected")
+        };
+        assert_eq!(proof.path_proof.nonce(), 3);
+    }
+
+    #[test]
+    fn invalid_anchor() {
+        assert_eq!(legacy_anchor("4a5e1e4b"), Err(CompatError::InvalidLen(4)));
+        assert_eq!(
+            legacy_anchor(&OPRET_ANCHOR[..OPRET_ANCHOR.len() - 2]),
+            Err(CompatError::InvalidAnchor)
+        );
+        assert_eq!(legacy_anchor(&format!("{OPRET_ANCHOR}00")), Err(CompatError::InvalidAnchor));
+        let unknown = format!("{}02", &OPRET_ANCHOR[..OPRET_ANCHOR.len() - 2]);
+        assert_eq!(legacy_anchor(&unknown), Err(CompatError::InvalidAnchor));
+    }
+}
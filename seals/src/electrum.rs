@@ -0,0 +1,213 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Witness resolver using Electrum protocol.
+
+use amplify::ByteArray;
+use bc::{ConsensusDecode, ConsensusDecodeError, Outpoint, ScriptPubkey, Tx, Txid};
+use electrum_client::bitcoin::hashes::Hash;
+use electrum_client::bitcoin::{self, Script};
+use electrum_client::{Client, ElectrumApi};
+
+use crate::{BlockTime, ResolveWitness, WitnessOrd, WitnessPos, MEDIAN_TIME_SPAN};
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ElectrumError {
+    /// Electrum request has failed. Details: {0}
+    #[from]
+    Client(electrum_client::Error),
+
+    /// Electrum server has returned an invalid transaction. Details: {0}
+    #[from]
+    InvalidTx(ConsensusDecodeError),
+
+    /// history of the script locked by {outpoint} contains {len} transactions, exceeding the
+    /// limit of {max} transactions.
+    HistoryTooLong {
+        /// Outpoint which spending is resolved.
+        outpoint: Outpoint,
+        /// Number of transactions in the script history.
+        len: usize,
+        /// Maximal number of transactions which may be retrieved.
+        max: usize,
+    },
+}
+
+/// Default maximal number of transactions in a script history which are retrieved to find a
+/// transaction spending an outpoint.
+pub const ELECTRUM_MAX_HISTORY: usize = 1000;
+
+/// Number of transactions retrieved with a single batched request.
+const BATCH_SIZE: usize = 50;
+
+/// Fragments of the error messages used by Electrum servers and bitcoin nodes behind them to report
+/// unknown transactions.
+const NOT_FOUND_MESSAGES: [&str; 4] = [
+    "no such mempool or blockchain transaction",
+    "no transaction matching",
+    "missing transaction",
+    "not found",
+];
+
+/// Detects whether the error reported by the server means that the transaction is not known.
+fn is_not_found(err: &electrum_client::Error) -> bool {
+    let electrum_client::Error::Protocol(value) = err else {
+        return false;
+    };
+    let message = value.to_string().to_lowercase();
+    NOT_FOUND_MESSAGES.iter().any(|fragment| message.contains(fragment))
+}
+
+fn to_bitcoin(txid: Txid) -> bitcoin::Txid { bitcoin::Txid::from_byte_array(txid.to_byte_array()) }
+
+fn from_bitcoin(txid: bitcoin::Txid) -> Txid { Txid::from(txid.to_byte_array()) }
+
+/// Witness resolver using Electrum protocol.
+///
+/// Since Electrum servers index transactions by their scripts, the resolver detects spending of
+/// an outpoint by scanning the history of the script locked by the outpoint, retrieving the
+/// transactions from the history with batched requests until the spending transaction is found.
+/// Scripts with the history longer than the limit (by default [`ELECTRUM_MAX_HISTORY`]) are not
+/// scanned.
+pub struct ElectrumResolver {
+    client: Client,
+    max_history: usize,
+}
+
+impl ElectrumResolver {
+    /// Connects to an Electrum server with a given URL, like
+    /// `ssl://electrum.blockstream.info:50002`.
+    pub fn new(url: &str) -> Result<Self, ElectrumError> { Ok(Self::with(Client::new(url)?)) }
+
+    /// Constructs resolver from an already connected client.
+    pub fn with(client: Client) -> Self {
+        ElectrumResolver {
+            client,
+            max_history: ELECTRUM_MAX_HISTORY,
+        }
+    }
+
+    /// Sets maximal number of transactions in a script history which may be retrieved to find a
+    /// transaction spending an outpoint.
+    pub fn with_max_history(mut self, max_history: usize) -> Self {
+        self.max_history = max_history;
+        self
+    }
+
+    /// Returns ids and heights of transactions touching a given script.
+    fn history(&self, script_pubkey: &ScriptPubkey) -> Result<Vec<(Txid, i32)>, ElectrumError> {
+        let script = Script::from_bytes(&script_pubkey[..]);
+        Ok(self
+            .client
+            .script_get_history(script)?
+            .into_iter()
+            .map(|item| (from_bitcoin(item.tx_hash), item.height))
+            .collect())
+    }
+
+    /// Finds a script indexed by the server which history contains the transaction.
+    ///
+    /// OP_RETURN outputs are not indexed, so the first spendable output is used; if the
+    /// transaction has none, the script of the output spent by its first input is used.
+    fn indexed_script(&self, tx: &Tx) -> Result<Option<ScriptPubkey>, ElectrumError> {
+        if let Some(txout) = tx.outputs().find(|txout| !txout.script_pubkey.is_op_return()) {
+            return Ok(Some(txout.script_pubkey.clone()));
+        }
+        let Some(prevout) = tx.inputs().next().map(|txin| txin.prev_output) else {
+            return Ok(None);
+        };
+        let Some(prev_tx) = self.resolve_tx(prevout.txid)? else {
+            return Ok(None);
+        };
+        Ok(prev_tx.outputs().nth(prevout.vout.to_usize()).map(|txout| txout.script_pubkey.clone()))
+    }
+}
+
+impl ResolveWitness for ElectrumResolver {
+    type Error = ElectrumError;
+
+    fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error> {
+        match self.client.transaction_get_raw(&to_bitcoin(txid)) {
+            Ok(data) => Ok(Some(Tx::consensus_deserialize(data)?)),
+            // Electrum servers report unknown transactions as a protocol-level error
+            Err(err) if is_not_found(&err) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error> {
+        let Some(prev_tx) = self.resolve_tx(outpoint.txid)? else {
+            return Ok(None);
+        };
+        let Some(txout) = prev_tx.outputs().nth(outpoint.vout.to_usize()) else {
+            return Ok(None);
+        };
+        let txids = self
+            .history(&txout.script_pubkey)?
+            .into_iter()
+            .filter(|(txid, _)| *txid != outpoint.txid)
+            .map(|(txid, _)| to_bitcoin(txid))
+            .collect::<Vec<_>>();
+        if txids.len() > self.max_history {
+            return Err(ElectrumError::HistoryTooLong {
+                outpoint,
+                len: txids.len(),
+                max: self.max_history,
+            });
+        }
+        for chunk in txids.chunks(BATCH_SIZE) {
+            for data in self.client.batch_transaction_get_raw(chunk)? {
+                let tx = Tx::consensus_deserialize(data)?;
+                if tx.inputs().any(|input| input.prev_output == outpoint) {
+                    return Ok(Some(tx));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn resolve_status(&self, txid: Txid) -> Result<WitnessOrd, Self::Error> {
+        let Some(tx) = self.resolve_tx(txid)? else {
+            return Ok(WitnessOrd::Unknown);
+        };
+        let Some(script_pubkey) = self.indexed_script(&tx)? else {
+            return Ok(WitnessOrd::Unknown);
+        };
+        let history = self.history(&script_pubkey)?;
+        let Some((_, height)) = history.into_iter().find(|(id, _)| *id == txid) else {
+            return Ok(WitnessOrd::Unknown);
+        };
+        if height <= 0 {
+            return Ok(WitnessOrd::Mempool);
+        }
+        let merkle = self.client.transaction_get_merkle(&to_bitcoin(txid), height as usize)?;
+        let height = merkle.block_height;
+        let start = height.saturating_sub(MEDIAN_TIME_SPAN - 1);
+        let headers = self.client.block_headers(start, height - start + 1)?.headers;
+        let times = headers.iter().map(|header| header.time).collect::<Vec<_>>();
+        let pos = match BlockTime::from_timestamps(&times) {
+            Some(time) => WitnessPos::with_time(height as u32, merkle.pos as u32, time),
+            None => WitnessPos::new(height as u32, merkle.pos as u32),
+        };
+        Ok(WitnessOrd::Mined(pos))
+    }
+}
@@ -0,0 +1,157 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Witness resolver using Esplora HTTP API.
+
+use bc::{BlockHash, ConsensusDecode, ConsensusDecodeError, Outpoint, Tx, Txid};
+
+use crate::{BlockTime, ResolveWitness, WitnessOrd, WitnessPos};
+
+/// Default timeout for Esplora requests, in seconds.
+pub const ESPLORA_TIMEOUT: u64 = 30;
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum EsploraError {
+    /// HTTP request to Esplora server has failed. Details: {0}
+    #[from]
+    Http(minreq::Error),
+
+    /// Esplora server has responded with HTTP status {0}: {1}.
+    Status(i32, String),
+
+    /// Esplora server has returned an invalid transaction. Details: {0}
+    #[from]
+    InvalidTx(ConsensusDecodeError),
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct OutspendResponse {
+    spent: bool,
+    txid: Option<Txid>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct StatusResponse {
+    confirmed: bool,
+    block_hash: Option<BlockHash>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct BlockResponse {
+    timestamp: u32,
+    mediantime: u32,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct MerkleProofResponse {
+    block_height: u32,
+    pos: u32,
+}
+
+/// Witness resolver using blocking requests to an Esplora server.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct EsploraResolver {
+    url: String,
+    timeout: u64,
+}
+
+impl EsploraResolver {
+    /// Constructs resolver for the Esplora server with a given base URL, like
+    /// `https://blockstream.info/api`.
+    pub fn new(url: impl ToString) -> Self {
+        EsploraResolver {
+            url: url.to_string().trim_end_matches('/').to_owned(),
+            timeout: ESPLORA_TIMEOUT,
+        }
+    }
+
+    /// Sets timeout for the requests, in seconds.
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Performs GET request for a given path, returning `None` if the server responds with 404
+    /// status.
+    fn get(&self, path: &str) -> Result<Option<minreq::Response>, EsploraError> {
+        let resp = minreq::get(format!("{}{path}", self.url)).with_timeout(self.timeout).send()?;
+        match resp.status_code {
+            200..=299 => Ok(Some(resp)),
+            404 => Ok(None),
+            code => Err(EsploraError::Status(code, resp.as_str().unwrap_or_default().to_owned())),
+        }
+    }
+}
+
+impl ResolveWitness for EsploraResolver {
+    type Error = EsploraError;
+
+    fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error> {
+        let Some(resp) = self.get(&format!("/tx/{txid}/raw"))? else {
+            return Ok(None);
+        };
+        let tx = Tx::consensus_deserialize(resp.as_bytes())?;
+        Ok(Some(tx))
+    }
+
+    fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error> {
+        let Some(resp) = self.get(&format!("/tx/{}/outspend/{}", outpoint.txid, outpoint.vout))?
+        else {
+            return Ok(None);
+        };
+        match resp.json::<OutspendResponse>()? {
+            OutspendResponse {
+                spent: true,
+                txid: Some(txid),
+            } => self.resolve_tx(txid),
+            _ => Ok(None),
+        }
+    }
+
+    fn resolve_status(&self, txid: Txid) -> Result<WitnessOrd, Self::Error> {
+        let Some(resp) = self.get(&format!("/tx/{txid}/status"))? else {
+            return Ok(WitnessOrd::Unknown);
+        };
+        let status = resp.json::<StatusResponse>()?;
+        if !status.confirmed {
+            return Ok(WitnessOrd::Mempool);
+        }
+        // Transaction may get reorged out of the blockchain between the requests
+        let Some(resp) = self.get(&format!("/tx/{txid}/merkle-proof"))? else {
+            return Ok(WitnessOrd::Unknown);
+        };
+        let proof = resp.json::<MerkleProofResponse>()?;
+        let block = match status.block_hash {
+            Some(block_hash) => self.get(&format!("/block/{block_hash}"))?,
+            None => None,
+        };
+        let pos = match block {
+            Some(resp) => {
+                let block = resp.json::<BlockResponse>()?;
+                let time = BlockTime::new(block.timestamp, block.mediantime);
+                WitnessPos::with_time(proof.block_height, proof.pos, time)
+            }
+            None => WitnessPos::new(proof.block_height, proof.pos),
+        };
+        Ok(WitnessOrd::Mined(pos))
+    }
+}
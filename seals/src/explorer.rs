@@ -0,0 +1,157 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Block explorer links to the on-chain evidence of seal closing.
+
+use bc::{Outpoint, Txid, Vout};
+
+use crate::TxoSeal;
+
+/// Base URL of mempool.space explorer for bitcoin mainnet.
+pub const MEMPOOL_SPACE_URL: &str = "https://mempool.space";
+/// Base URL of blockstream.info explorer for bitcoin mainnet.
+pub const BLOCKSTREAM_URL: &str = "https://blockstream.info";
+
+/// Type of block explorer software, defining the format of the links to
+/// transaction outputs.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Default)]
+#[display(lowercase)]
+pub enum ExplorerKind {
+    /// mempool.space explorer and its self-hosted instances.
+    #[default]
+    Mempool,
+
+    /// Esplora explorer, used by blockstream.info.
+    Esplora,
+}
+
+/// Block explorer producing URLs for witness transactions, sealed outpoints
+/// and commitment host outputs.
+///
+/// Explorers for testnets and signets are constructed with [`Explorer::new`]
+/// using a network-specific base URL, like `https://mempool.space/testnet`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{base}")]
+pub struct Explorer {
+    kind: ExplorerKind,
+    base: String,
+}
+
+impl Explorer {
+    /// Constructs explorer of a given kind with a base URL.
+    pub fn new(kind: ExplorerKind, base: impl ToString) -> Self {
+        Explorer {
+            kind,
+            base: base.to_string().trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// Constructs mempool.space explorer for bitcoin mainnet.
+    pub fn mempool_space() -> Self { Self::new(ExplorerKind::Mempool, MEMPOOL_SPACE_URL) }
+
+    /// Constructs blockstream.info explorer for bitcoin mainnet.
+    pub fn blockstream() -> Self { Self::new(ExplorerKind::Esplora, BLOCKSTREAM_URL) }
+
+    /// Returns kind of the explorer.
+    pub fn kind(&self) -> ExplorerKind { self.kind }
+
+    /// Returns base URL of the explorer.
+    pub fn base(&self) -> &str { &self.base }
+
+    /// Returns URL of a transaction, like a witness transaction closing seals.
+    pub fn tx_url(&self, txid: Txid) -> String { format!("{}/tx/{txid}", self.base) }
+
+    /// Returns URL of a transaction output, like a sealed outpoint.
+    pub fn outpoint_url(&self, outpoint: Outpoint) -> String {
+        let vout = outpoint.vout.to_u32();
+        match self.kind {
+            ExplorerKind::Mempool => format!("{}#vout={vout}", self.tx_url(outpoint.txid)),
+            ExplorerKind::Esplora => format!("{}?output:{vout}", self.tx_url(outpoint.txid)),
+        }
+    }
+
+    /// Returns URL of an output of a witness transaction hosting deterministic
+    /// bitcoin commitment.
+    pub fn host_url(&self, witness_txid: Txid, vout: Vout) -> String {
+        self.outpoint_url(Outpoint::new(witness_txid, vout))
+    }
+
+    /// Returns URL of the outpoint defined by the primary seal definition.
+    ///
+    /// Returns `None` for seals defined over an output of a witness
+    /// transaction (see [`TxoSeal::is_witness_relative`]), which outpoint is
+    /// not known until the witness is; use [`Self::witness_seal_url`] for
+    /// them.
+    pub fn seal_url<D: dbc::Proof>(&self, seal: &TxoSeal<D>) -> Option<String> {
+        if seal.is_witness_relative() {
+            return None;
+        }
+        Some(self.outpoint_url(seal.primary))
+    }
+
+    /// Returns URL of the outpoint defined by the primary seal definition,
+    /// resolving seals defined over an output of a witness transaction with
+    /// the provided witness txid.
+    pub fn witness_seal_url<D: dbc::Proof>(&self, seal: &TxoSeal<D>, witness_txid: Txid) -> String {
+        match seal.is_witness_relative() {
+            true => self.host_url(witness_txid, seal.primary.vout),
+            false => self.outpoint_url(seal.primary),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use commit_verify::Sha256;
+    use dbc::opret::OpretProof;
+
+    use super::*;
+
+    #[test]
+    fn urls() {
+        let txid = Txid::from([0x11u8; 32]);
+        let outpoint = Outpoint::new(txid, Vout::from_u32(2));
+        let mempool = Explorer::mempool_space();
+        assert_eq!(mempool.tx_url(txid), format!("https://mempool.space/tx/{txid}"));
+        assert_eq!(
+            mempool.outpoint_url(outpoint),
+            format!("https://mempool.space/tx/{txid}#vout=2")
+        );
+
+        let esplora = Explorer::new(ExplorerKind::Esplora, "https://blockstream.info/testnet/");
+        assert_eq!(
+            esplora.host_url(txid, Vout::from_u32(1)),
+            format!("https://blockstream.info/testnet/tx/{txid}?output:1")
+        );
+
+        let seal = TxoSeal::<OpretProof>::no_fallback(outpoint, Sha256::default(), 0);
+        assert_eq!(mempool.seal_url(&seal), Some(mempool.outpoint_url(outpoint)));
+        assert_eq!(mempool.witness_seal_url(&seal, txid), mempool.outpoint_url(outpoint));
+
+        let witness = Txid::from([0x22u8; 32]);
+        let seal = TxoSeal::<OpretProof>::vout_no_fallback(Vout::from_u32(3), Sha256::default(), 0);
+        assert_eq!(mempool.seal_url(&seal), None);
+        assert_eq!(
+            mempool.witness_seal_url(&seal, witness),
+            format!("https://mempool.space/tx/{witness}#vout=3")
+        );
+    }
+}
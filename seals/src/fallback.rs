@@ -0,0 +1,312 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Seals defined over an ordered list of alternative outpoints.
+
+use core::error::Error;
+
+use amplify::confinement::TinyVec;
+use amplify::{ByteArray, Bytes};
+use bc::{Outpoint, Tx, Txid};
+use commit_verify::{CommitId, DigestExt, Sha256, StrictHash};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{Noise, ResolveWitness, TxoSeal, TxoSealDef, TxoSealExt};
+
+/// Errors defining [`FallbackSeal`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum FallbackDefError {
+    /// fallback seal must have at least one alternative.
+    Empty,
+
+    /// fallback seal can't have more than 255 alternatives.
+    TooMany,
+
+    /// outpoint {0} is used as an alternative more than once.
+    Duplicate(Outpoint),
+}
+
+/// Errors selecting active alternative of [`FallbackSeal`].
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum FallbackSealError<E: Error> {
+    /// all alternatives of the fallback seal are spent by other transactions.
+    AllSpent,
+
+    /// witness {witness} doesn't close the active seal alternative {active}.
+    NotClosed { witness: Txid, active: Outpoint },
+
+    /// seal alternative #{index} is spent by transaction {txid} closing the
+    /// seal, thus the seal can't be closed once more by another witness.
+    DoubleClose { index: usize, txid: Txid },
+
+    /// unable to resolve spending transaction. Details: {0}
+    Resolver(E),
+}
+
+/// Seal defined over an ordered list of alternative outpoints.
+///
+/// The first alternative which is not spent by another transaction is the
+/// effective seal, allowing protocols to remain functional when a counterparty
+/// spends some of the candidate outputs. Unlike [`TxoSealExt::Fallback`],
+/// which provides a single fallback outpoint used by a specific protocol
+/// construction, the order of the alternatives here defines which of them is
+/// active.
+///
+/// The seal commitment covers the whole list of the alternatives and the
+/// noise; [`Self::to_seal`] derives concrete seals for the alternatives with
+/// a noise specific to the fallback seal and the alternative position.
+///
+/// Transactions spending the skipped alternatives must not close the seal:
+/// otherwise the same seal may be closed twice, by a transaction spending an
+/// alternative and by a witness spending a later one. The selection requires a
+/// function detecting such closings and rejects the witness if any of the
+/// skipped alternatives is closed.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = StrictHash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FallbackSeal {
+    /// Alternative outpoints in the order of their priority.
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<Outpoint>"))]
+    pub alternatives: TinyVec<Outpoint>,
+    /// Noise hiding the seal definition.
+    pub noise: Noise,
+}
+
+impl StrictSerialize for FallbackSeal {}
+impl StrictDeserialize for FallbackSeal {}
+
+impl FallbackSeal {
+    /// Constructs fallback seal with a noise derived from the alternatives and
+    /// a nonce.
+    ///
+    /// `nonce` is a deterministic incremental number, preventing from creating
+    /// the same seal if the same outputs are used.
+    pub fn with_noise(
+        alternatives: impl IntoIterator<Item = Outpoint>,
+        mut noise_engine: Sha256,
+        nonce: u64,
+    ) -> Result<Self, FallbackDefError> {
+        let mut list = Vec::new();
+        for outpoint in alternatives {
+            if list.contains(&outpoint) {
+                return Err(FallbackDefError::Duplicate(outpoint));
+            }
+            list.push(outpoint);
+        }
+        if list.is_empty() {
+            return Err(FallbackDefError::Empty);
+        }
+        let alternatives = TinyVec::try_from(list).map_err(|_| FallbackDefError::TooMany)?;
+        noise_engine.input_raw(&nonce.to_be_bytes());
+        for outpoint in &alternatives {
+            noise_engine.input_raw(outpoint.txid.as_ref());
+            noise_engine.input_raw(&outpoint.vout.to_u32().to_be_bytes());
+        }
+        let mut noise = [0xFFu8; 40];
+        noise[..32].copy_from_slice(&noise_engine.finish());
+        Ok(FallbackSeal {
+            alternatives,
+            noise: Noise::from(Bytes::from_byte_array(noise)),
+        })
+    }
+
+    /// Returns concealed seal commitment, covering all the alternatives.
+    pub fn seal_id(&self) -> StrictHash { self.commit_id() }
+
+    /// Derives concrete seal for the alternative at the given position.
+    pub fn to_seal<D: dbc::Proof>(&self, index: usize) -> Option<TxoSeal<D>> {
+        let primary = *self.alternatives.get(index)?;
+        let mut engine = Sha256::default();
+        engine.input_raw(self.seal_id().as_ref());
+        engine.input_raw(&(index as u8).to_be_bytes());
+        let mut noise = [0xFFu8; 40];
+        noise[..32].copy_from_slice(&engine.finish());
+        Some(TxoSeal::from_definition(TxoSealDef {
+            primary,
+            secondary: TxoSealExt::Noise(Noise::from(Bytes::from_byte_array(noise))),
+        }))
+    }
+
+    /// Selects the alternative closed by the witness transaction, given a
+    /// function returning id of a transaction spending an outpoint and a
+    /// function detecting whether a transaction spending an alternative
+    /// carries a commitment closing the seal of that alternative (see
+    /// [`Self::to_seal`]).
+    ///
+    /// Alternatives spent by transactions other than the witness are
+    /// skipped; the first alternative which is unspent or spent by the
+    /// witness is the active one. Returns the position of the active
+    /// alternative, which must be closed by the witness. Errors if any of
+    /// the skipped alternatives is spent by a transaction closing the seal.
+    pub fn select<E: Error>(
+        &self,
+        witness: Txid,
+        mut spent_by: impl FnMut(Outpoint) -> Result<Option<Txid>, E>,
+        mut closes: impl FnMut(usize, Txid) -> Result<bool, E>,
+    ) -> Result<usize, FallbackSealError<E>> {
+        for (index, outpoint) in self.alternatives.iter().enumerate() {
+            match spent_by(*outpoint).map_err(FallbackSealError::Resolver)? {
+                Some(txid) if txid == witness => return Ok(index),
+                Some(txid) => {
+                    if closes(index, txid).map_err(FallbackSealError::Resolver)? {
+                        return Err(FallbackSealError::DoubleClose { index, txid });
+                    }
+                }
+                None => {
+                    return Err(FallbackSealError::NotClosed {
+                        witness,
+                        active: *outpoint,
+                    })
+                }
+            }
+        }
+        Err(FallbackSealError::AllSpent)
+    }
+
+    /// Selects the alternative closed by the witness transaction, using the
+    /// resolver to find transactions spending the alternatives and `closes`
+    /// function to detect whether a transaction spending an alternative closes
+    /// its seal.
+    ///
+    /// See [`Self::select`] for the details.
+    pub fn resolve_active<R: ResolveWitness>(
+        &self,
+        witness: Txid,
+        resolver: &R,
+        mut closes: impl FnMut(usize, &Tx) -> bool,
+    ) -> Result<usize, FallbackSealError<R::Error>> {
+        for (index, outpoint) in self.alternatives.iter().enumerate() {
+            match resolver.resolve_spending_tx(*outpoint).map_err(FallbackSealError::Resolver)? {
+                Some(tx) if tx.txid() == witness => return Ok(index),
+                Some(tx) if closes(index, &tx) => {
+                    return Err(FallbackSealError::DoubleClose {
+                        index,
+                        txid: tx.txid(),
+                    })
+                }
+                Some(_) => continue,
+                None => {
+                    return Err(FallbackSealError::NotClosed {
+                        witness,
+                        active: *outpoint,
+                    })
+                }
+            }
+        }
+        Err(FallbackSealError::AllSpent)
+    }
+
+    /// Selects the first alternative which is not spent yet, if any.
+    pub fn find_unspent<R: ResolveWitness>(
+        &self,
+        resolver: &R,
+    ) -> Result<Option<usize>, FallbackSealError<R::Error>> {
+        for (index, outpoint) in self.alternatives.iter().enumerate() {
+            let spent =
+                resolver.resolve_spending_tx(*outpoint).map_err(FallbackSealError::Resolver)?;
+            if spent.is_none() {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::convert::Infallible;
+
+    use bc::Vout;
+    use dbc::opret::OpretProof;
+
+    use super::*;
+
+    #[test]
+    fn select() {
+        let alt = |no: u8| Outpoint::new(Txid::from([no; 32]), Vout::from_u32(0));
+        let seal =
+            FallbackSeal::with_noise([alt(1), alt(2), alt(3)], Sha256::default(), 0).unwrap();
+        assert_eq!(
+            FallbackSeal::with_noise([alt(1), alt(1)], Sha256::default(), 0),
+            Err(FallbackDefError::Duplicate(alt(1)))
+        );
+
+        let witness = Txid::from([0xAA; 32]);
+        let other = Txid::from([0xBB; 32]);
+        let spends = bmap! { alt(1) => other, alt(2) => witness };
+        let spent_by = |spends: BTreeMap<Outpoint, Txid>| {
+            move |outpoint: Outpoint| Ok::<_, Infallible>(spends.get(&outpoint).copied())
+        };
+        let no_closing = |_: usize, _: Txid| Ok::<_, Infallible>(false);
+        assert_eq!(seal.select(witness, spent_by(spends), no_closing), Ok(1));
+
+        let spends = bmap! { alt(1) => witness, alt(2) => other };
+        assert_eq!(seal.select(witness, spent_by(spends), no_closing), Ok(0));
+
+        let spends = bmap! { alt(1) => other };
+        assert_eq!(
+            seal.select(witness, spent_by(spends), no_closing),
+            Err(FallbackSealError::NotClosed {
+                witness,
+                active: alt(2)
+            })
+        );
+
+        let first = seal.to_seal::<OpretProof>(0).unwrap();
+        let second = seal.to_seal::<OpretProof>(1).unwrap();
+        assert_eq!(second.primary, alt(2));
+        assert_ne!(first.secondary, second.secondary);
+        assert!(seal.to_seal::<OpretProof>(3).is_none());
+    }
+
+    #[test]
+    fn double_close() {
+        let alt = |no: u8| Outpoint::new(Txid::from([no; 32]), Vout::from_u32(0));
+        let seal = FallbackSeal::with_noise([alt(1), alt(2)], Sha256::default(), 0).unwrap();
+        let first = Txid::from([0xAA; 32]);
+        let second = Txid::from([0xBB; 32]);
+        let spends = bmap! { alt(1) => first, alt(2) => second };
+        let spent_by = |outpoint: Outpoint| Ok::<_, Infallible>(spends.get(&outpoint).copied());
+
+        // The holder closes the seal with the first alternative and then
+        // tries to close it once more with the second one
+        let closes = |index: usize, txid: Txid| Ok::<_, Infallible>(index == 0 && txid == first);
+        assert_eq!(seal.select(first, spent_by, closes), Ok(0));
+        assert_eq!(
+            seal.select(second, spent_by, closes),
+            Err(FallbackSealError::DoubleClose {
+                index: 0,
+                txid: first
+            })
+        );
+
+        // Counterparty spending of the first alternative without commitment
+        // activates the second one
+        assert_eq!(seal.select(second, spent_by, |_, _| Ok::<_, Infallible>(false)), Ok(1));
+    }
+}
@@ -0,0 +1,215 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal validation of block header chains backing SPV proofs.
+//!
+//! The validator checks proof of work of each header against the target encoded in the header
+//! itself, linkage of the headers via previous block hashes, and computes cumulative work of the
+//! chain. It doesn't check difficulty adjustments and timestamps, thus the caller must compare
+//! the cumulative work against a trusted checkpoint or a competing chain.
+
+use amplify::num::u256;
+use amplify::ByteArray;
+use bc::{BlockHash, BlockHeader};
+
+use crate::TxProof;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum HeaderChainError {
+    /// header chain contains no headers.
+    Empty,
+
+    /// block {0} has invalid compact target value {1}.
+    InvalidTarget(BlockHash, u32),
+
+    /// block {0} hash is above the target defined by the block header.
+    InsufficientWork(BlockHash),
+
+    /// block {block} doesn't follow block {expected} but references {actual} as its parent.
+    Disconnected {
+        block: BlockHash,
+        expected: BlockHash,
+        actual: BlockHash,
+    },
+}
+
+/// Converts compact target representation from a block header into a 256-bit target.
+///
+/// Returns `None` for negative, zero or overflowing targets.
+pub fn target_from_bits(bits: u32) -> Option<u256> {
+    let exp = (bits >> 24) as usize;
+    if bits & 0x0080_0000 != 0 {
+        return None;
+    }
+    let (mantissa, shift) = match bits & 0x007F_FFFF {
+        mantissa if exp <= 3 => (mantissa >> (8 * (3 - exp)), 0),
+        mantissa => (mantissa, exp - 3),
+    };
+    if mantissa == 0 {
+        return None;
+    }
+    let mut le = [0u8; 32];
+    for (pos, byte) in mantissa.to_le_bytes()[..3].iter().enumerate() {
+        if *byte == 0 {
+            continue;
+        }
+        *le.get_mut(shift + pos)? = *byte;
+    }
+    Some(u256::from_le_bytes(le))
+}
+
+/// Computes amount of work represented by a block with a given target, i.e. expected number of
+/// hashes required to find a block hash not exceeding the target.
+pub fn target_work(target: u256) -> u256 { (!target / (target + u256::ONE)) + u256::ONE }
+
+/// Chain of block headers with validated proof of work and linkage.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct HeaderChain {
+    headers: Vec<(BlockHash, BlockHeader)>,
+    work: u256,
+}
+
+impl HeaderChain {
+    /// Validates a sequence of block headers, starting from the oldest one.
+    pub fn validate(
+        headers: impl IntoIterator<Item = BlockHeader>,
+    ) -> Result<Self, HeaderChainError> {
+        let mut chain = HeaderChain {
+            headers: vec![],
+            work: u256::ZERO,
+        };
+        for header in headers {
+            chain.push(header)?;
+        }
+        if chain.headers.is_empty() {
+            return Err(HeaderChainError::Empty);
+        }
+        Ok(chain)
+    }
+
+    /// Validates a header and appends it to the tip of the chain.
+    pub fn push(&mut self, header: BlockHeader) -> Result<(), HeaderChainError> {
+        let block_hash = header.block_hash();
+        if let Some(tip) = self.tip() {
+            if header.prev_block_hash != tip {
+                return Err(HeaderChainError::Disconnected {
+                    block: block_hash,
+                    expected: tip,
+                    actual: header.prev_block_hash,
+                });
+            }
+        }
+        let target = target_from_bits(header.bits)
+            .ok_or(HeaderChainError::InvalidTarget(block_hash, header.bits))?;
+        if u256::from_le_bytes(block_hash.to_byte_array()) > target {
+            return Err(HeaderChainError::InsufficientWork(block_hash));
+        }
+        self.work = self.work + target_work(target);
+        self.headers.push((block_hash, header));
+        Ok(())
+    }
+
+    /// Returns hash of the last block in the chain.
+    pub fn tip(&self) -> Option<BlockHash> { self.headers.last().map(|(hash, _)| *hash) }
+
+    /// Returns cumulative work of all headers in the chain.
+    pub fn work(&self) -> u256 { self.work }
+
+    /// Detects whether the chain has more cumulative work than the other chain.
+    pub fn has_more_work(&self, other: &HeaderChain) -> bool { self.work > other.work }
+
+    /// Returns number of headers in the chain.
+    pub fn len(&self) -> usize { self.headers.len() }
+
+    /// Detects whether the chain has no headers.
+    pub fn is_empty(&self) -> bool { self.headers.is_empty() }
+
+    /// Iterates over headers in the chain, starting from the oldest one.
+    pub fn headers(&self) -> impl Iterator<Item = &BlockHeader> {
+        self.headers.iter().map(|(_, header)| header)
+    }
+
+    /// Returns number of headers on top of the block with a given hash, including the block
+    /// itself, or `None` if the block is not part of the chain.
+    pub fn confirmations(&self, block_hash: BlockHash) -> Option<u32> {
+        let pos = self.headers.iter().position(|(hash, _)| *hash == block_hash)?;
+        Some((self.headers.len() - pos) as u32)
+    }
+
+    /// Returns number of confirmations of the block containing a transaction proven by the SPV
+    /// proof, or `None` if the block is not part of the chain.
+    pub fn tx_proof_confirmations(&self, tx_proof: &TxProof) -> Option<u32> {
+        self.confirmations(tx_proof.header.block_hash())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn target() {
+        let target = target_from_bits(0x1d00ffff).unwrap();
+        let mut le = [0u8; 32];
+        le[26] = 0xFF;
+        le[27] = 0xFF;
+        assert_eq!(target, u256::from_le_bytes(le));
+        let mut le = [0u8; 32];
+        le[..5].copy_from_slice(&[0x01, 0x00, 0x01, 0x00, 0x01]);
+        assert_eq!(target_work(target), u256::from_le_bytes(le));
+
+        assert_eq!(target_from_bits(0x1d80ffff), None);
+        assert_eq!(target_from_bits(0x1d000000), None);
+        assert_eq!(target_from_bits(0x2300ffff), None);
+    }
+
+    #[test]
+    fn chain() {
+        // block height 835056
+        let header = BlockHeader::from_str(
+            "00006020333eaffe61bc29a9a387aa56bd424b3c73ebb536cc4a03000000000000000000af225b062c\
+             7acf90aac833cc4e0789f17b13ef53564cdd3b748e7897d7df20ff25bcf665595a03170bcd54ad",
+        )
+        .unwrap();
+        let chain = HeaderChain::validate([header]).unwrap();
+        assert_eq!(chain.tip(), Some(header.block_hash()));
+        assert_eq!(chain.confirmations(header.block_hash()), Some(1));
+
+        assert_eq!(
+            HeaderChain::validate([header, header]),
+            Err(HeaderChainError::Disconnected {
+                block: header.block_hash(),
+                expected: header.block_hash(),
+                actual: header.prev_block_hash,
+            })
+        );
+
+        let forged = BlockHeader { nonce: 0, ..header };
+        assert_eq!(
+            HeaderChain::validate([forged]),
+            Err(HeaderChainError::InsufficientWork(forged.block_hash()))
+        );
+        assert_eq!(HeaderChain::validate([]), Err(HeaderChainError::Empty));
+    }
+}
@@ -0,0 +1,221 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compact storage for large sets of seals sharing the same transactions.
+
+use amplify::confinement::LargeVec;
+use bc::{Outpoint, Txid, Vout};
+use strict_encoding::{
+    DecodeError, ReadStruct, StrictDecode, StrictDeserialize, StrictDumb, StrictSerialize,
+    TypedRead,
+};
+
+use crate::{TxoSealDef, TxoSealExt};
+
+/// Seal definition referencing its transaction id by the index in the
+/// [`InternedSeals`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+struct InternedSeal {
+    txid_no: u32,
+    vout: Vout,
+    secondary: TxoSealExt,
+}
+
+/// Collection of seal definitions storing each of the seal transaction ids
+/// only once.
+///
+/// Useful for wallets tracking large number of seals defined over outputs of
+/// the same transactions (like in batch issuance), where memory is otherwise
+/// dominated by duplicated transaction ids. The same compact layout is used by
+/// the strict encoding of the collection.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[derive(StrictType, StrictEncode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+pub struct InternedSeals {
+    txids: LargeVec<Txid>,
+    seals: LargeVec<InternedSeal>,
+    /// Numbers of the transaction ids sorted by the id value, used for lookups.
+    #[strict_type(skip)]
+    order: Vec<u32>,
+}
+
+impl StrictDumb for InternedSeals {
+    fn strict_dumb() -> Self { default!() }
+}
+
+impl StrictDecode for InternedSeals {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let txids: LargeVec<Txid> = r.read_field(fname!("txids"))?;
+            let seals: LargeVec<InternedSeal> = r.read_field(fname!("seals"))?;
+            let mut order = (0..txids.len() as u32).collect::<Vec<_>>();
+            order.sort_unstable_by_key(|no| txids[*no as usize]);
+            if order.windows(2).any(|w| txids[w[0] as usize] == txids[w[1] as usize]) {
+                return Err(DecodeError::DataIntegrityError(s!(
+                    "repeated transaction id in interned seals"
+                )));
+            }
+            if seals.iter().any(|seal| seal.txid_no as usize >= txids.len()) {
+                return Err(DecodeError::DataIntegrityError(s!(
+                    "interned seal references unknown transaction id"
+                )));
+            }
+            Ok(InternedSeals {
+                txids,
+                seals,
+                order,
+            })
+        })
+    }
+}
+
+impl StrictSerialize for InternedSeals {}
+impl StrictDeserialize for InternedSeals {}
+
+impl InternedSeals {
+    /// Constructs empty collection.
+    pub fn new() -> Self { default!() }
+
+    /// Returns number of seals in the collection.
+    pub fn len(&self) -> usize { self.seals.len() }
+
+    /// Detects whether the collection has no seals.
+    pub fn is_empty(&self) -> bool { self.seals.is_empty() }
+
+    /// Returns number of distinct transaction ids used by the seals.
+    pub fn txid_count(&self) -> usize { self.txids.len() }
+
+    /// Adds a seal to the collection, returning its index.
+    ///
+    /// # Panics
+    ///
+    /// If the collection already contains 2^32 seals.
+    pub fn push(&mut self, seal: TxoSealDef) -> usize {
+        let txid = seal.primary.txid;
+        let txid_no = match self.lookup(txid) {
+            Ok(pos) => self.order[pos],
+            Err(pos) => {
+                let no = self.txids.len() as u32;
+                self.txids.push(txid).expect("too many seals");
+                self.order.insert(pos, no);
+                no
+            }
+        };
+        self.seals
+            .push(InternedSeal {
+                txid_no,
+                vout: seal.primary.vout,
+                secondary: seal.secondary,
+            })
+            .expect("too many seals");
+        self.seals.len() - 1
+    }
+
+    /// Returns seal with a given index.
+    pub fn get(&self, index: usize) -> Option<TxoSealDef> {
+        self.seals.get(index).map(|seal| self.resolve(seal))
+    }
+
+    /// Iterates over all seals in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = TxoSealDef> + '_ {
+        self.seals.iter().map(|seal| self.resolve(seal))
+    }
+
+    /// Iterates over seals defined over outputs of a given transaction.
+    pub fn by_txid(&self, txid: Txid) -> impl Iterator<Item = TxoSealDef> + '_ {
+        let no = self.lookup(txid).ok().map(|pos| self.order[pos]);
+        self.seals
+            .iter()
+            .filter(move |seal| Some(seal.txid_no) == no)
+            .map(|seal| self.resolve(seal))
+    }
+
+    /// Converts the collection into the plain list of seals.
+    pub fn to_plain(&self) -> Vec<TxoSealDef> { self.iter().collect() }
+
+    fn lookup(&self, txid: Txid) -> Result<usize, usize> {
+        self.order.binary_search_by(|no| self.txids[*no as usize].cmp(&txid))
+    }
+
+    fn resolve(&self, seal: &InternedSeal) -> TxoSealDef {
+        TxoSealDef {
+            primary: Outpoint::new(self.txids[seal.txid_no as usize], seal.vout),
+            secondary: seal.secondary,
+        }
+    }
+}
+
+impl FromIterator<TxoSealDef> for InternedSeals {
+    fn from_iter<T: IntoIterator<Item = TxoSealDef>>(iter: T) -> Self {
+        let mut seals = InternedSeals::new();
+        seals.extend(iter);
+        seals
+    }
+}
+
+impl Extend<TxoSealDef> for InternedSeals {
+    fn extend<T: IntoIterator<Item = TxoSealDef>>(&mut self, iter: T) {
+        for seal in iter {
+            self.push(seal);
+        }
+    }
+}
+
+impl From<Vec<TxoSealDef>> for InternedSeals {
+    fn from(seals: Vec<TxoSealDef>) -> Self { seals.into_iter().collect() }
+}
+
+impl From<&InternedSeals> for Vec<TxoSealDef> {
+    fn from(seals: &InternedSeals) -> Self { seals.to_plain() }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use commit_verify::Sha256;
+    use dbc::opret::OpretProof;
+
+    use super::*;
+    use crate::TxoSeal;
+
+    #[test]
+    fn roundtrip() {
+        let plain = (0..10u32)
+            .map(|no| {
+                let txid = Txid::from([(no % 3) as u8; 32]);
+                let outpoint = Outpoint::new(txid, Vout::from_u32(no));
+                TxoSeal::<OpretProof>::no_fallback(outpoint, Sha256::default(), no as u64)
+                    .to_definition()
+            })
+            .collect::<Vec<_>>();
+        let seals = InternedSeals::from(plain.clone());
+        assert_eq!(seals.len(), 10);
+        assert_eq!(seals.txid_count(), 3);
+        assert_eq!(seals.get(4), Some(plain[4]));
+        assert_eq!(seals.by_txid(Txid::from([1u8; 32])).count(), 3);
+        assert_eq!(seals.to_plain(), plain);
+
+        let data = seals.to_strict_serialized::<U32>().unwrap();
+        assert_eq!(InternedSeals::from_strict_serialized::<U32>(data).unwrap(), seals);
+    }
+}
@@ -44,7 +44,65 @@ extern crate commit_verify;
 extern crate serde;
 
 mod txout;
+mod witness;
+mod spv;
+mod resolver;
+mod batch;
+mod chained;
+#[cfg(feature = "compat")]
+mod compat;
+mod reorg;
+mod watch;
+mod monitor;
+mod explorer;
+mod fallback;
+mod interned;
+mod template;
+mod blinding;
+mod status;
+#[cfg(feature = "headers")]
+mod headers;
+#[cfg(feature = "esplora")]
+mod esplora;
+#[cfg(feature = "electrum")]
+mod electrum;
+#[cfg(feature = "bitcoind")]
+mod bitcoind;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+#[cfg(test)]
+mod test_helpers;
 
+pub use batch::{verify_seals_batch, BatchProof, WitnessCache};
+#[cfg(feature = "bitcoind")]
+pub use bitcoind::{BitcoindError, BitcoindResolver, BITCOIND_SCAN_DEPTH};
+pub use blinding::{XpubBlinding, XPUB_BLINDING_TAG, XPUB_DATA_LEN};
+pub use chained::{follow_up_seals, ChainedSeal, ChainedSealError};
+#[cfg(feature = "compat")]
+pub use compat::{CompatError, LegacyAnchor, LegacyBlindSeal, LegacyTxPtr};
+#[cfg(feature = "electrum")]
+pub use electrum::{ElectrumError, ElectrumResolver, ELECTRUM_MAX_HISTORY};
+#[cfg(feature = "esplora")]
+pub use esplora::{EsploraError, EsploraResolver, ESPLORA_TIMEOUT};
+pub use explorer::{Explorer, ExplorerKind, BLOCKSTREAM_URL, MEMPOOL_SPACE_URL};
+pub use fallback::{FallbackDefError, FallbackSeal, FallbackSealError};
+#[cfg(feature = "headers")]
+pub use headers::{target_from_bits, target_work, HeaderChain, HeaderChainError};
+pub use interned::InternedSeals;
+pub use monitor::{PollingWatcher, SealEvent, SealWatcher};
+pub use reorg::{revalidate, ChainView, MinedWitness, Revalidation};
+#[cfg(feature = "async")]
+pub use resolver::AsyncResolveWitness;
+pub use resolver::{ResolveError, ResolveWitness};
+pub use spv::{TxProof, TxProofError, TX_PROOF_MAX_DEPTH};
+pub use status::{SealStatus, SealTracker, SEAL_BURIED_DEPTH};
+pub use template::{TemplateError, TemplateSeal};
 pub use txout::{
-    mmb, mpc, Anchor, AnchorError, AnchorMergeError, Noise, TxoSeal, TxoSealDef, TxoSealExt,
+    mmb, mpc, Anchor, AnchorError, AnchorMergeError, Noise, SealVerifyError, TxoSeal, TxoSealDef,
+    TxoSealExt, TxoWitness, WITNESS_TXID_PLACEHOLDER,
+};
+pub use watch::{WatchError, WatchSet};
+pub use witness::{
+    signals_rbf, witness_statuses, BlockTime, WitnessOrd, WitnessPos, WitnessStatus,
+    BIP125_MAX_SEQ_NO, MEDIAN_TIME_SPAN,
 };
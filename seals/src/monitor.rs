@@ -0,0 +1,212 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Monitoring of seal closings.
+
+use core::error::Error;
+use std::collections::BTreeMap;
+
+use bc::{Outpoint, Txid};
+
+use crate::{ResolveWitness, WitnessOrd, WitnessPos};
+
+/// Event happening to a monitored seal.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum SealEvent {
+    /// Seal is spent by a witness transaction, which is not necessarily mined.
+    #[display("seal {seal} is spent by {witness_txid}")]
+    Spent { seal: Outpoint, witness_txid: Txid },
+
+    /// Witness transaction spending the seal is mined.
+    #[display("witness {witness_txid} closing seal {seal} is mined at {pos}")]
+    Mined {
+        seal: Outpoint,
+        witness_txid: Txid,
+        pos: WitnessPos,
+    },
+
+    /// Block containing witness transaction spending the seal was reorged out of the blockchain.
+    #[display("witness {witness_txid} closing seal {seal} is reorged out")]
+    Reorged { seal: Outpoint, witness_txid: Txid },
+}
+
+/// Service monitoring a registered set of seals and reporting events happening to them.
+pub trait SealWatcher {
+    /// Errors of the underlying backend.
+    type Error: Error;
+
+    /// Registers seal defined by the outpoint for monitoring.
+    fn watch(&mut self, seal: Outpoint);
+
+    /// Stops monitoring the seal, returning whether it was monitored.
+    fn unwatch(&mut self, seal: Outpoint) -> bool;
+
+    /// Returns events which happened since the previous call.
+    ///
+    /// If the call fails, the monitoring state is left unchanged, and the events are reported by
+    /// the next successful call.
+    fn poll(&mut self) -> Result<Vec<SealEvent>, Self::Error>;
+}
+
+/// Reference [`SealWatcher`] implementation, polling a [`ResolveWitness`] for each of the seals.
+#[derive(Clone, Debug)]
+pub struct PollingWatcher<R: ResolveWitness> {
+    resolver: R,
+    seals: BTreeMap<Outpoint, Option<(Txid, WitnessOrd)>>,
+}
+
+impl<R: ResolveWitness> PollingWatcher<R> {
+    /// Constructs watcher using a given resolver.
+    pub fn new(resolver: R) -> Self {
+        PollingWatcher {
+            resolver,
+            seals: empty!(),
+        }
+    }
+
+    /// Returns witness transaction spending the seal and its last known status, if the seal is
+    /// monitored and known to be spent.
+    pub fn witness(&self, seal: Outpoint) -> Option<(Txid, WitnessOrd)> {
+        self.seals.get(&seal).copied().flatten()
+    }
+
+    fn poll_seal(
+        &self,
+        seal: Outpoint,
+        known: Option<(Txid, WitnessOrd)>,
+        events: &mut Vec<SealEvent>,
+    ) -> Result<Option<(Txid, WitnessOrd)>, R::Error> {
+        let (witness_txid, prev_ord) = match known {
+            Some(known) => known,
+            None => {
+                let Some(tx) = self.resolver.resolve_spending_tx(seal)? else {
+                    return Ok(None);
+                };
+                let witness_txid = tx.txid();
+                events.push(SealEvent::Spent { seal, witness_txid });
+                (witness_txid, WitnessOrd::Unknown)
+            }
+        };
+        let ord = self.resolver.resolve_status(witness_txid)?;
+        if prev_ord.is_mined() && ord != prev_ord {
+            events.push(SealEvent::Reorged { seal, witness_txid });
+        }
+        if let Some(pos) = ord.pos() {
+            if ord != prev_ord {
+                events.push(SealEvent::Mined {
+                    seal,
+                    witness_txid,
+                    pos,
+                });
+            }
+        }
+        if ord == WitnessOrd::Unknown {
+            // Witness has left the mempool, so the seal may get spent by another transaction
+            return Ok(None);
+        }
+        Ok(Some((witness_txid, ord)))
+    }
+}
+
+impl<R: ResolveWitness> SealWatcher for PollingWatcher<R> {
+    type Error = R::Error;
+
+    fn watch(&mut self, seal: Outpoint) { self.seals.entry(seal).or_insert(None); }
+
+    fn unwatch(&mut self, seal: Outpoint) -> bool { self.seals.remove(&seal).is_some() }
+
+    fn poll(&mut self) -> Result<Vec<SealEvent>, Self::Error> {
+        let mut events = vec![];
+        let mut states = Vec::with_capacity(self.seals.len());
+        for (seal, known) in &self.seals {
+            states.push((*seal, self.poll_seal(*seal, *known, &mut events)?));
+        }
+        self.seals.extend(states);
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use bc::Tx;
+
+    use super::*;
+    use crate::test_helpers::{outpoint, tx};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+    #[display("backend is offline")]
+    struct Offline;
+
+    struct Resolver {
+        witness: Tx,
+        offline: Cell<Option<Outpoint>>,
+    }
+
+    impl ResolveWitness for Resolver {
+        type Error = Offline;
+
+        fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error> {
+            Ok(Some(self.witness.clone()).filter(|tx| tx.txid() == txid))
+        }
+
+        fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error> {
+            if self.offline.get() == Some(outpoint) {
+                return Err(Offline);
+            }
+            Ok(Some(self.witness.clone())
+                .filter(|tx| tx.inputs().any(|txin| txin.prev_output == outpoint)))
+        }
+
+        fn resolve_status(&self, _txid: Txid) -> Result<WitnessOrd, Self::Error> {
+            Ok(WitnessOrd::Mempool)
+        }
+    }
+
+    #[test]
+    fn failed_poll() {
+        let (spent, unspent) = (outpoint(1), outpoint(2));
+        let witness = tx([spent], []);
+        let witness_txid = witness.txid();
+        let resolver = Resolver {
+            witness,
+            offline: Cell::new(Some(unspent)),
+        };
+        let mut watcher = PollingWatcher::new(&resolver);
+        watcher.watch(spent);
+        watcher.watch(unspent);
+
+        assert_eq!(watcher.poll(), Err(Offline));
+        assert_eq!(watcher.witness(spent), None);
+
+        resolver.offline.set(None);
+        assert_eq!(
+            watcher.poll(),
+            Ok(vec![SealEvent::Spent {
+                seal: spent,
+                witness_txid
+            }])
+        );
+        assert_eq!(watcher.witness(spent), Some((witness_txid, WitnessOrd::Mempool)));
+        assert_eq!(watcher.poll(), Ok(vec![]));
+    }
+}
@@ -0,0 +1,161 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Revalidation of mined witness positions after blockchain reorganizations.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bc::{BlockHash, Txid};
+
+use crate::{ResolveWitness, WitnessOrd, WitnessPos};
+
+/// Witness which was previously accepted as mined in a specific block.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MinedWitness {
+    /// Witness transaction id.
+    pub txid: Txid,
+    /// Position of the witness in the blockchain.
+    pub pos: WitnessPos,
+    /// Hash of the block containing the witness.
+    pub block_hash: BlockHash,
+}
+
+/// View of the blockchain after a possible reorganization.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ChainView {
+    /// Height of the current chain tip.
+    pub tip_height: u32,
+    /// Hashes of the blocks known to be part of the current chain, by their height.
+    pub blocks: BTreeMap<u32, BlockHash>,
+    /// Hashes of the blocks known to be no longer part of the chain.
+    pub invalidated: BTreeSet<BlockHash>,
+}
+
+impl ChainView {
+    /// Constructs chain view with a given tip height and no known blocks.
+    pub fn with_tip(tip_height: u32) -> Self {
+        ChainView {
+            tip_height,
+            ..default!()
+        }
+    }
+
+    /// Detects whether the witness is still part of the chain.
+    ///
+    /// The witness is considered reorged out if its block is invalidated, is above the chain tip,
+    /// or the chain contains a different block at the same height.
+    pub fn is_active(&self, witness: &MinedWitness) -> bool {
+        !self.invalidated.contains(&witness.block_hash)
+            && witness.pos.height <= self.tip_height
+            && self
+                .blocks
+                .get(&witness.pos.height)
+                .map(|hash| *hash == witness.block_hash)
+                .unwrap_or(true)
+    }
+}
+
+/// Result of witness revalidation against an updated chain view.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Revalidation {
+    /// Witnesses which remain in the chain, in their blockchain order.
+    pub active: Vec<MinedWitness>,
+    /// Witnesses whose blocks were reorged out, with their current status.
+    pub reorged: BTreeMap<Txid, WitnessOrd>,
+}
+
+impl Revalidation {
+    /// Detects whether any of the witnesses were reorged out.
+    #[inline]
+    pub fn has_reorgs(&self) -> bool { !self.reorged.is_empty() }
+
+    /// Returns ordering of all the witnesses, placing the reorged witnesses according to their
+    /// current status.
+    pub fn ordering(&self) -> Vec<(Txid, WitnessOrd)> {
+        let mut ordering = self
+            .active
+            .iter()
+            .map(|witness| (witness.txid, WitnessOrd::Mined(witness.pos)))
+            .chain(self.reorged.iter().map(|(txid, ord)| (*txid, *ord)))
+            .collect::<Vec<_>>();
+        ordering.sort_by_key(|(txid, ord)| (*ord, *txid));
+        ordering
+    }
+
+    /// Updates status of the reorged witnesses using the resolver, since they may be re-mined in
+    /// another block or returned to the mempool.
+    pub fn resolve<R: ResolveWitness>(&mut self, resolver: R) -> Result<(), R::Error> {
+        for (txid, ord) in &mut self.reorged {
+            *ord = resolver.resolve_status(*txid)?;
+        }
+        Ok(())
+    }
+}
+
+/// Revalidates previously accepted mined witnesses against the updated chain view.
+///
+/// Witnesses which were reorged out get [`WitnessOrd::Unknown`] status, which may be updated with
+/// [`Revalidation::resolve`].
+pub fn revalidate(
+    witnesses: impl IntoIterator<Item = MinedWitness>,
+    view: &ChainView,
+) -> Revalidation {
+    let mut revalidation = Revalidation::default();
+    for witness in witnesses {
+        if view.is_active(&witness) {
+            revalidation.active.push(witness);
+        } else {
+            revalidation.reorged.insert(witness.txid, WitnessOrd::Unknown);
+        }
+    }
+    revalidation.active.sort_by_key(|witness| (witness.pos, witness.txid));
+    revalidation
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reorg() {
+        let witness = |n: u8, height: u32| MinedWitness {
+            txid: Txid::from([n; 32]),
+            pos: WitnessPos::new(height, 1),
+            block_hash: BlockHash::from([height as u8; 32]),
+        };
+        let mut view = ChainView::with_tip(102);
+        view.blocks.insert(101, BlockHash::from([0xFF; 32]));
+        view.invalidated.insert(BlockHash::from([100; 32]));
+
+        let revalidation =
+            revalidate([witness(1, 99), witness(2, 100), witness(3, 101), witness(4, 103)], &view);
+        assert_eq!(revalidation.active, vec![witness(1, 99)]);
+        assert_eq!(revalidation.reorged.len(), 3);
+        assert_eq!(
+            revalidation.ordering()[0],
+            (Txid::from([1; 32]), WitnessOrd::Mined(WitnessPos::new(99, 1)))
+        );
+    }
+}
@@ -0,0 +1,396 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Abstraction over backends providing witness transactions and their status.
+
+use core::error::Error;
+#[cfg(feature = "async")]
+use core::future::Future;
+
+use bc::{Outpoint, Tx, Txid};
+
+use crate::{mmb, Anchor, SealVerifyError, TxoSeal, TxoWitness, WitnessOrd};
+
+/// Provider of witness transactions and their mining status, which may be an indexer, a node or
+/// a local wallet database.
+pub trait ResolveWitness {
+    /// Errors reported by the backend.
+    type Error: Error;
+
+    /// Retrieves transaction with a given id, returning `None` if the transaction is not known.
+    fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error>;
+
+    /// Retrieves transaction spending a given outpoint, returning `None` if the outpoint is not
+    /// spent by any known transaction.
+    fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error>;
+
+    /// Retrieves mining status of the transaction with a given id.
+    fn resolve_status(&self, txid: Txid) -> Result<WitnessOrd, Self::Error>;
+}
+
+impl<R: ResolveWitness> ResolveWitness for &R {
+    type Error = R::Error;
+
+    #[inline]
+    fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error> { (*self).resolve_tx(txid) }
+
+    #[inline]
+    fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error> {
+        (*self).resolve_spending_tx(outpoint)
+    }
+
+    #[inline]
+    fn resolve_status(&self, txid: Txid) -> Result<WitnessOrd, Self::Error> {
+        (*self).resolve_status(txid)
+    }
+}
+
+/// Asynchronous version of [`ResolveWitness`].
+#[cfg(feature = "async")]
+pub trait AsyncResolveWitness {
+    /// Errors reported by the backend.
+    type Error: Error;
+
+    /// Retrieves transaction with a given id, returning `None` if the transaction is not known.
+    fn resolve_tx(
+        &self,
+        txid: Txid,
+    ) -> impl Future<Output = Result<Option<Tx>, Self::Error>> + Send;
+
+    /// Retrieves transaction spending a given outpoint, returning `None` if the outpoint is not
+    /// spent by any known transaction.
+    fn resolve_spending_tx(
+        &self,
+        outpoint: Outpoint,
+    ) -> impl Future<Output = Result<Option<Tx>, Self::Error>> + Send;
+
+    /// Retrieves mining status of the transaction with a given id.
+    fn resolve_status(
+        &self,
+        txid: Txid,
+    ) -> impl Future<Output = Result<WitnessOrd, Self::Error>> + Send;
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncResolveWitness + Sync> AsyncResolveWitness for &R {
+    type Error = R::Error;
+
+    #[inline]
+    fn resolve_tx(
+        &self,
+        txid: Txid,
+    ) -> impl Future<Output = Result<Option<Tx>, Self::Error>> + Send {
+        (*self).resolve_tx(txid)
+    }
+
+    #[inline]
+    fn resolve_spending_tx(
+        &self,
+        outpoint: Outpoint,
+    ) -> impl Future<Output = Result<Option<Tx>, Self::Error>> + Send {
+        (*self).resolve_spending_tx(outpoint)
+    }
+
+    #[inline]
+    fn resolve_status(
+        &self,
+        txid: Txid,
+    ) -> impl Future<Output = Result<WitnessOrd, Self::Error>> + Send {
+        (*self).resolve_status(txid)
+    }
+}
+
+/// Errors retrieving witness transactions from a [`ResolveWitness`] backend
+/// and verifying that they close the seals.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ResolveError<E: Error, P: Error> {
+    /// unable to resolve witness transaction. Details: {0}
+    Resolver(E),
+
+    /// no seals are provided for the verification.
+    NoSeals,
+
+    /// witness transaction {0} is not known.
+    UnknownTx(Txid),
+
+    /// seal {0} is not spent by any known transaction.
+    Unspent(Outpoint),
+
+    /// witness doesn't close the seals. Details: {0}
+    #[from]
+    Seal(SealVerifyError<P>),
+}
+
+impl<D: dbc::Proof> TxoWitness<D> {
+    /// Retrieves witness transaction with a given id and verifies that it closes all the seals
+    /// over the message, returning the witness and its mining status.
+    pub fn resolve<R: ResolveWitness>(
+        resolver: R,
+        txid: Txid,
+        anchor: Anchor<D>,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(Self, WitnessOrd), ResolveError<R::Error, D::Error>> {
+        let tx = resolver
+            .resolve_tx(txid)
+            .map_err(ResolveError::Resolver)?
+            .ok_or(ResolveError::UnknownTx(txid))?;
+        Self::new(tx, anchor).verify_resolved(resolver, seals, msg)
+    }
+
+    /// Retrieves transaction spending the seals and verifies that it closes all of them over the
+    /// message, returning the witness and its mining status.
+    pub fn resolve_spending<R: ResolveWitness>(
+        resolver: R,
+        anchor: Anchor<D>,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(Self, WitnessOrd), ResolveError<R::Error, D::Error>> {
+        let seal = seals.first().ok_or(ResolveError::NoSeals)?;
+        let outpoint = seal.closing_outpoint(&anchor);
+        let tx = resolver
+            .resolve_spending_tx(outpoint)
+            .map_err(ResolveError::Resolver)?
+            .ok_or(ResolveError::Unspent(outpoint))?;
+        Self::new(tx, anchor).verify_resolved(resolver, seals, msg)
+    }
+
+    fn verify_resolved<R: ResolveWitness>(
+        self,
+        resolver: R,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(Self, WitnessOrd), ResolveError<R::Error, D::Error>> {
+        self.verify_seals(seals, msg)?;
+        let status = resolver.resolve_status(self.tx.txid()).map_err(ResolveError::Resolver)?;
+        Ok((self, status))
+    }
+
+    /// Asynchronous version of [`TxoWitness::resolve`].
+    #[cfg(feature = "async")]
+    pub async fn resolve_async<R: AsyncResolveWitness>(
+        resolver: R,
+        txid: Txid,
+        anchor: Anchor<D>,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(Self, WitnessOrd), ResolveError<R::Error, D::Error>> {
+        let tx = resolver
+            .resolve_tx(txid)
+            .await
+            .map_err(ResolveError::Resolver)?
+            .ok_or(ResolveError::UnknownTx(txid))?;
+        Self::new(tx, anchor).verify_resolved_async(resolver, seals, msg).await
+    }
+
+    /// Asynchronous version of [`TxoWitness::resolve_spending`].
+    #[cfg(feature = "async")]
+    pub async fn resolve_spending_async<R: AsyncResolveWitness>(
+        resolver: R,
+        anchor: Anchor<D>,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(Self, WitnessOrd), ResolveError<R::Error, D::Error>> {
+        let seal = seals.first().ok_or(ResolveError::NoSeals)?;
+        let outpoint = seal.closing_outpoint(&anchor);
+        let tx = resolver
+            .resolve_spending_tx(outpoint)
+            .await
+            .map_err(ResolveError::Resolver)?
+            .ok_or(ResolveError::Unspent(outpoint))?;
+        Self::new(tx, anchor).verify_resolved_async(resolver, seals, msg).await
+    }
+
+    #[cfg(feature = "async")]
+    async fn verify_resolved_async<R: AsyncResolveWitness>(
+        self,
+        resolver: R,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(Self, WitnessOrd), ResolveError<R::Error, D::Error>> {
+        self.verify_seals(seals, msg)?;
+        let status =
+            resolver.resolve_status(self.tx.txid()).await.map_err(ResolveError::Resolver)?;
+        Ok((self, status))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::test_helpers::{close, opret_output, seal, tx};
+    use crate::WitnessPos;
+
+    /// Resolver keeping transactions in memory.
+    struct MemResolver(Vec<Tx>);
+
+    impl ResolveWitness for MemResolver {
+        type Error = Infallible;
+
+        fn resolve_tx(&self, txid: Txid) -> Result<Option<Tx>, Self::Error> {
+            Ok(self.0.iter().find(|tx| tx.txid() == txid).cloned())
+        }
+
+        fn resolve_spending_tx(&self, outpoint: Outpoint) -> Result<Option<Tx>, Self::Error> {
+            Ok(self
+                .0
+                .iter()
+                .find(|tx| tx.inputs().any(|txin| txin.prev_output == outpoint))
+                .cloned())
+        }
+
+        fn resolve_status(&self, _txid: Txid) -> Result<WitnessOrd, Self::Error> {
+            Ok(WitnessOrd::Mined(WitnessPos::new(100, 1)))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncResolveWitness for MemResolver {
+        type Error = Infallible;
+
+        fn resolve_tx(
+            &self,
+            txid: Txid,
+        ) -> impl Future<Output = Result<Option<Tx>, Self::Error>> + Send {
+            core::future::ready(ResolveWitness::resolve_tx(self, txid))
+        }
+
+        fn resolve_spending_tx(
+            &self,
+            outpoint: Outpoint,
+        ) -> impl Future<Output = Result<Option<Tx>, Self::Error>> + Send {
+            core::future::ready(ResolveWitness::resolve_spending_tx(self, outpoint))
+        }
+
+        fn resolve_status(
+            &self,
+            txid: Txid,
+        ) -> impl Future<Output = Result<WitnessOrd, Self::Error>> + Send {
+            core::future::ready(ResolveWitness::resolve_status(self, txid))
+        }
+    }
+
+    /// Polls the future on the current thread until it completes.
+    #[cfg(feature = "async")]
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Noop;
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = core::pin::pin!(fut);
+        loop {
+            if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn resolve() {
+        let msg = mmb::Message::from([1u8; 32]);
+        let (seal, unspent) = (seal(1), seal(2));
+        let (tx, anchor) = close(&[seal], msg);
+        let txid = tx.txid();
+        let resolver = MemResolver(vec![tx.clone()]);
+
+        let (witness, ord) =
+            TxoWitness::resolve(&resolver, txid, anchor.clone(), &[seal], msg).unwrap();
+        assert_eq!(witness, TxoWitness::new(tx, anchor.clone()));
+        assert_eq!(ord, WitnessOrd::Mined(WitnessPos::new(100, 1)));
+
+        let other = Txid::from([0xFF; 32]);
+        assert_eq!(
+            TxoWitness::resolve(&resolver, other, anchor.clone(), &[seal], msg),
+            Err(ResolveError::UnknownTx(other))
+        );
+        assert_eq!(
+            TxoWitness::resolve(&resolver, txid, anchor, &[unspent], msg),
+            Err(ResolveError::Seal(SealVerifyError::NotSpent(unspent.primary)))
+        );
+    }
+
+    #[test]
+    fn resolve_spending() {
+        let msg = mmb::Message::from([1u8; 32]);
+        let (seal, unspent) = (seal(1), seal(2));
+        let (witness_tx, anchor) = close(&[seal], msg);
+        let resolver = MemResolver(vec![witness_tx.clone()]);
+
+        let (witness, _) =
+            TxoWitness::resolve_spending(&resolver, anchor.clone(), &[seal], msg).unwrap();
+        assert_eq!(witness.tx, witness_tx);
+
+        assert_eq!(
+            TxoWitness::resolve_spending(&resolver, anchor.clone(), &[], msg),
+            Err(ResolveError::NoSeals)
+        );
+        assert_eq!(
+            TxoWitness::resolve_spending(&resolver, anchor.clone(), &[unspent], msg),
+            Err(ResolveError::Unspent(unspent.primary))
+        );
+
+        // The seal is spent by a transaction which doesn't commit to the anchor
+        let resolver = MemResolver(vec![tx([seal.primary], [opret_output()])]);
+        assert!(matches!(
+            TxoWitness::resolve_spending(&resolver, anchor, &[seal], msg),
+            Err(ResolveError::Seal(SealVerifyError::Dbc(_)))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn resolve_async() {
+        let msg = mmb::Message::from([1u8; 32]);
+        let (seal, unspent) = (seal(1), seal(2));
+        let (tx, anchor) = close(&[seal], msg);
+        let txid = tx.txid();
+        let resolver = MemResolver(vec![tx.clone()]);
+
+        let (witness, ord) =
+            block_on(TxoWitness::resolve_async(&resolver, txid, anchor.clone(), &[seal], msg))
+                .unwrap();
+        assert_eq!(witness.tx, tx);
+        assert_eq!(ord, WitnessOrd::Mined(WitnessPos::new(100, 1)));
+        let (witness, _) =
+            block_on(TxoWitness::resolve_spending_async(&resolver, anchor.clone(), &[seal], msg))
+                .unwrap();
+        assert_eq!(witness.tx, tx);
+
+        let other = Txid::from([0xFF; 32]);
+        assert_eq!(
+            block_on(TxoWitness::resolve_async(&resolver, other, anchor.clone(), &[seal], msg)),
+            Err(ResolveError::UnknownTx(other))
+        );
+        assert_eq!(
+            block_on(TxoWitness::resolve_spending_async(&resolver, anchor, &[unspent], msg)),
+            Err(ResolveError::Unspent(unspent.primary))
+        );
+    }
+}
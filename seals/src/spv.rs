@@ -0,0 +1,303 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SPV proofs of transaction inclusion into a block.
+
+use amplify::confinement::Confined;
+use amplify::{ByteArray, Bytes32};
+use bc::{BlockHeader, BlockMerkleRoot, LenVarInt, Outpoint, Tx, Txid, VarInt, Vout};
+use commit_verify::{DigestExt, Sha256};
+
+/// Maximal depth of a block merkle tree.
+pub const TX_PROOF_MAX_DEPTH: usize = 32;
+
+/// Length of the transaction serialization without witness data, which can't be distinguished
+/// from an inner node of a block merkle tree (see CVE-2017-12842).
+pub const FORGEABLE_TX_LEN: usize = 64;
+
+/// Errors verifying [`TxProof`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TxProofError {
+    /// transaction index {0} doesn't fit the merkle path of the proof.
+    IndexOutOfRange(u32),
+
+    /// transaction {txid} is not included into block with merkle root {expected}; the proof
+    /// produces merkle root {actual}.
+    MerkleRootMismatch {
+        /// Id of the transaction which inclusion is verified.
+        txid: Txid,
+        /// Merkle root of the block header.
+        expected: BlockMerkleRoot,
+        /// Merkle root computed from the transaction id and the merkle path.
+        actual: BlockMerkleRoot,
+    },
+
+    /// transaction {0} has non-witness serialization of 64 bytes, which can be confused with an
+    /// inner node of the block merkle tree.
+    ForgeableTx(Txid),
+
+    /// transaction {0} is not a coinbase transaction.
+    NotCoinbase(Txid),
+
+    /// coinbase proof is made for a different block than the transaction proof.
+    BlockMismatch,
+
+    /// merkle path of the transaction proof has depth {actual}, while the block merkle tree has
+    /// depth {expected}.
+    DepthMismatch {
+        /// Depth of the block merkle tree, proven by the coinbase transaction proof.
+        expected: u8,
+        /// Depth of the merkle path of the transaction proof.
+        actual: u8,
+    },
+}
+
+/// Proof of a transaction inclusion into a block, consisting of the block header and merkle
+/// branch connecting the transaction id with the merkle root of the block.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TxProof {
+    /// Header of the block containing the transaction.
+    pub header: BlockHeader,
+    /// Index of the transaction within the block.
+    pub tx_index: u32,
+    /// Hashes of the sibling nodes on the path from the transaction to the merkle root, starting
+    /// from the leaf level.
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<String>"))]
+    pub merkle_path: Confined<Vec<Bytes32>, 0, TX_PROOF_MAX_DEPTH>,
+}
+
+/// Computes length of the transaction serialization without witness data.
+fn base_len(tx: &Tx) -> usize {
+    let inputs = tx
+        .inputs()
+        .map(|txin| {
+            let script_len = txin.sig_script.len();
+            32 + 4 + VarInt::with(script_len).len() + script_len + 4
+        })
+        .sum::<usize>();
+    let outputs = tx
+        .outputs()
+        .map(|txout| {
+            let script_len = txout.script_pubkey.len();
+            8 + VarInt::with(script_len).len() + script_len
+        })
+        .sum::<usize>();
+    4 + tx.inputs.len_var_int().len() + inputs + tx.outputs.len_var_int().len() + outputs + 4
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut engine = Sha256::default();
+    engine.input_raw(left);
+    engine.input_raw(right);
+    let mut double = Sha256::default();
+    double.input_raw(&engine.finish());
+    double.finish()
+}
+
+impl TxProof {
+    /// Computes merkle root of the block for the transaction with a given id using the merkle path
+    /// of the proof.
+    pub fn merkle_root(&self, txid: Txid) -> Result<BlockMerkleRoot, TxProofError> {
+        let depth = self.merkle_path.len() as u32;
+        if depth < u32::BITS && self.tx_index >> depth != 0 {
+            return Err(TxProofError::IndexOutOfRange(self.tx_index));
+        }
+        let mut node = txid.to_byte_array();
+        for (level, sibling) in self.merkle_path.iter().enumerate() {
+            node = if (self.tx_index >> level) & 1 == 0 {
+                hash_pair(&node, &sibling.to_byte_array())
+            } else {
+                hash_pair(&sibling.to_byte_array(), &node)
+            };
+        }
+        Ok(BlockMerkleRoot::from(node))
+    }
+
+    /// Verifies that the transaction with a given id is included into the block.
+    pub fn verify(&self, txid: Txid) -> Result<(), TxProofError> {
+        let actual = self.merkle_root(txid)?;
+        if actual != self.header.merkle_root {
+            return Err(TxProofError::MerkleRootMismatch {
+                txid,
+                expected: self.header.merkle_root,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Verifies that the transaction is included into the block.
+    ///
+    /// Transactions with the non-witness serialization of 64 bytes are rejected, since they can be
+    /// used to forge a proof for a transaction which is not included into the block
+    /// (CVE-2017-12842). To additionally ensure that the merkle path goes down to the leaf level of
+    /// the block merkle tree, use [`TxProof::verify_depth`].
+    pub fn verify_tx(&self, tx: &Tx) -> Result<(), TxProofError> {
+        let txid = tx.txid();
+        if base_len(tx) == FORGEABLE_TX_LEN {
+            return Err(TxProofError::ForgeableTx(txid));
+        }
+        self.verify(txid)
+    }
+
+    /// Verifies that the merkle path of the proof has the depth of the block merkle tree, using a
+    /// proof of the coinbase transaction inclusion into the same block.
+    ///
+    /// Since the coinbase transaction can't be 64 bytes long and is always the first transaction
+    /// in the block, its merkle path defines the depth of the tree, preventing the use of an inner
+    /// node of the tree in place of a transaction.
+    pub fn verify_depth(&self, coinbase: &TxProof, coinbase_tx: &Tx) -> Result<(), TxProofError> {
+        let txid = coinbase_tx.txid();
+        let coinbase_input = Outpoint::new(Txid::from([0u8; 32]), Vout::from_u32(u32::MAX));
+        if coinbase.tx_index != 0
+            || coinbase_tx.inputs.len() != 1
+            || coinbase_tx.inputs().all(|txin| txin.prev_output != coinbase_input)
+        {
+            return Err(TxProofError::NotCoinbase(txid));
+        }
+        if coinbase.header != self.header {
+            return Err(TxProofError::BlockMismatch);
+        }
+        coinbase.verify(txid)?;
+        if coinbase.merkle_path.len() != self.merkle_path.len() {
+            return Err(TxProofError::DepthMismatch {
+                expected: coinbase.merkle_path.len() as u8,
+                actual: self.merkle_path.len() as u8,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{BlockHash, Sats, ScriptPubkey, TxOut};
+
+    use super::*;
+    use crate::test_helpers::{outpoint, tx};
+
+    fn header(merkle_root: BlockMerkleRoot) -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            prev_block_hash: BlockHash::from([0u8; 32]),
+            merkle_root,
+            time: 0,
+            bits: 0,
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn inclusion() {
+        let leaves = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32]];
+        let left = hash_pair(&leaves[0], &leaves[1]);
+        let right = hash_pair(&leaves[2], &leaves[3]);
+        let root = hash_pair(&left, &right);
+
+        let proof = TxProof {
+            header: header(BlockMerkleRoot::from(root)),
+            tx_index: 2,
+            merkle_path: Confined::from_iter_checked([
+                Bytes32::from(leaves[3]),
+                Bytes32::from(left),
+            ]),
+        };
+        assert_eq!(proof.verify(Txid::from(leaves[2])), Ok(()));
+        assert!(proof.verify(Txid::from(leaves[3])).is_err());
+
+        let proof = TxProof {
+            tx_index: 4,
+            ..proof
+        };
+        assert_eq!(proof.verify(Txid::from(leaves[2])), Err(TxProofError::IndexOutOfRange(4)));
+    }
+
+    #[test]
+    fn forgeable_tx() {
+        let tx = tx([outpoint(1)], [TxOut::new(
+            ScriptPubkey::from_unsafe(vec![0x6a, 0x02, 0x01, 0x02]),
+            Sats::ZERO,
+        )]);
+        assert_eq!(base_len(&tx), FORGEABLE_TX_LEN);
+        let proof = TxProof {
+            header: header(BlockMerkleRoot::from(tx.txid().to_byte_array())),
+            tx_index: 0,
+            merkle_path: none!(),
+        };
+        assert_eq!(proof.verify(tx.txid()), Ok(()));
+        assert_eq!(proof.verify_tx(&tx), Err(TxProofError::ForgeableTx(tx.txid())));
+    }
+
+    #[test]
+    fn depth() {
+        let coinbase_tx =
+            tx([Outpoint::new(Txid::from([0u8; 32]), Vout::from_u32(u32::MAX))], [TxOut::new(
+                ScriptPubkey::p2sh([1u8; 20]),
+                50u64,
+            )]);
+        let leaves = [coinbase_tx.txid().to_byte_array(), [2u8; 32], [3u8; 32], [4u8; 32]];
+        let left = hash_pair(&leaves[0], &leaves[1]);
+        let right = hash_pair(&leaves[2], &leaves[3]);
+        let header = header(BlockMerkleRoot::from(hash_pair(&left, &right)));
+
+        let coinbase = TxProof {
+            header,
+            tx_index: 0,
+            merkle_path: Confined::from_iter_checked([
+                Bytes32::from(leaves[1]),
+                Bytes32::from(right),
+            ]),
+        };
+        let proof = TxProof {
+            header,
+            tx_index: 2,
+            merkle_path: Confined::from_iter_checked([
+                Bytes32::from(leaves[3]),
+                Bytes32::from(left),
+            ]),
+        };
+        assert_eq!(proof.verify_depth(&coinbase, &coinbase_tx), Ok(()));
+
+        // inner node of the tree presented as a transaction
+        let forged = TxProof {
+            header,
+            tx_index: 1,
+            merkle_path: Confined::from_iter_checked([Bytes32::from(left)]),
+        };
+        assert_eq!(forged.verify(Txid::from(right)), Ok(()));
+        assert_eq!(
+            forged.verify_depth(&coinbase, &coinbase_tx),
+            Err(TxProofError::DepthMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            coinbase.verify_depth(&proof, &coinbase_tx),
+            Err(TxProofError::NotCoinbase(coinbase_tx.txid()))
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lifecycle of seals, as reported by wallets to the users.
+
+use amplify::confinement::MediumOrdMap;
+use bc::{Outpoint, Txid};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{SealEvent, WitnessOrd};
+
+/// Default number of confirmations after which a closed seal is considered
+/// buried.
+pub const SEAL_BURIED_DEPTH: u32 = 6;
+
+/// Lifecycle status of a single-use-seal.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE, tags = custom, dumb = Self::Defined)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum SealStatus {
+    /// Seal is defined, but not closed yet.
+    #[default]
+    #[display("defined")]
+    #[strict_type(tag = 0x00)]
+    Defined,
+
+    /// Seal is closed by a witness transaction, which is not mined yet.
+    #[display("closed by {0}")]
+    #[strict_type(tag = 0x01)]
+    Closed(Txid),
+
+    /// Witness transaction closing the seal is mined, but has less than the
+    /// required number of confirmations.
+    #[display("closed by {witness} mined at {height}")]
+    #[strict_type(tag = 0x02)]
+    Mined { witness: Txid, height: u32 },
+
+    /// Witness transaction closing the seal is buried under enough blocks to
+    /// be considered final.
+    #[display("closed by {witness} mined at {height} with depth {depth}")]
+    #[strict_type(tag = 0x03)]
+    Buried {
+        witness: Txid,
+        height: u32,
+        depth: u32,
+    },
+
+    /// Seal was closed by a witness which does not commit to a valid message,
+    /// or can't be closed anymore.
+    #[display("invalidated")]
+    #[strict_type(tag = 0xFF)]
+    Invalidated,
+}
+
+impl SealStatus {
+    /// Detects whether the seal is closed, irrespectively of the mining
+    /// status of the witness transaction.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, SealStatus::Closed(_) | SealStatus::Mined { .. } | SealStatus::Buried { .. })
+    }
+
+    /// Detects whether the seal closing is final.
+    pub fn is_final(&self) -> bool { matches!(self, SealStatus::Buried { .. }) }
+
+    /// Returns id of the witness transaction closing the seal, if any.
+    pub fn witness(&self) -> Option<Txid> {
+        match self {
+            SealStatus::Closed(witness)
+            | SealStatus::Mined { witness, .. }
+            | SealStatus::Buried { witness, .. } => Some(*witness),
+            SealStatus::Defined | SealStatus::Invalidated => None,
+        }
+    }
+
+    /// Returns height of the block containing the witness transaction, if it
+    /// is mined.
+    pub fn height(&self) -> Option<u32> {
+        match self {
+            SealStatus::Mined { height, .. } | SealStatus::Buried { height, .. } => Some(*height),
+            _ => None,
+        }
+    }
+
+    fn mined(witness: Txid, height: u32, tip: u32, buried_depth: u32) -> Self {
+        let depth = tip.saturating_add(1).saturating_sub(height);
+        if depth >= buried_depth {
+            SealStatus::Buried {
+                witness,
+                height,
+                depth,
+            }
+        } else {
+            SealStatus::Mined { witness, height }
+        }
+    }
+}
+
+/// Tracker of the lifecycle statuses for a set of seals.
+///
+/// The statuses are updated from [`SealEvent`]s reported by a
+/// [`crate::SealWatcher`], from witness statuses returned by a
+/// [`crate::ResolveWitness`] and from the new chain tips. Invalidated seals
+/// remain invalidated and are not updated anymore.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SealTracker {
+    tip: u32,
+    buried_depth: u32,
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<Outpoint, SealStatus>")
+    )]
+    seals: MediumOrdMap<Outpoint, SealStatus>,
+}
+
+impl StrictSerialize for SealTracker {}
+impl StrictDeserialize for SealTracker {}
+
+impl Default for SealTracker {
+    fn default() -> Self { SealTracker::new(SEAL_BURIED_DEPTH) }
+}
+
+impl SealTracker {
+    /// Constructs empty tracker considering seals buried after a given number
+    /// of confirmations.
+    pub fn new(buried_depth: u32) -> Self {
+        SealTracker {
+            tip: 0,
+            buried_depth,
+            seals: empty!(),
+        }
+    }
+
+    /// Returns height of the last known chain tip.
+    pub fn tip(&self) -> u32 { self.tip }
+
+    /// Returns number of confirmations after which seals are considered
+    /// buried.
+    pub fn buried_depth(&self) -> u32 { self.buried_depth }
+
+    /// Registers seal in the [`SealStatus::Defined`] status. Does nothing if
+    /// the seal is already tracked.
+    ///
+    /// # Panics
+    ///
+    /// If the number of tracked seals exceeds 2^24.
+    pub fn define(&mut self, seal: Outpoint) {
+        if !self.seals.contains_key(&seal) {
+            self.seals.insert(seal, SealStatus::Defined).expect("too many seals");
+        }
+    }
+
+    /// Returns status of the seal, if it is tracked.
+    pub fn status(&self, seal: Outpoint) -> Option<SealStatus> { self.seals.get(&seal).copied() }
+
+    /// Iterates over all tracked seals with their statuses.
+    pub fn iter(&self) -> impl Iterator<Item = (Outpoint, SealStatus)> + '_ {
+        self.seals.iter().map(|(seal, status)| (*seal, *status))
+    }
+
+    /// Marks seal as invalidated, returning whether the seal is tracked.
+    pub fn invalidate(&mut self, seal: Outpoint) -> bool { self.set(seal, SealStatus::Invalidated) }
+
+    /// Updates status of the seal from an event reported by a seal watcher.
+    ///
+    /// Returns the new status, or `None` if the seal is not tracked.
+    pub fn apply(&mut self, event: SealEvent) -> Option<SealStatus> {
+        let (tip, buried_depth) = (self.tip, self.buried_depth);
+        let (seal, status) = match event {
+            SealEvent::Spent { seal, witness_txid } | SealEvent::Reorged { seal, witness_txid } => {
+                (seal, SealStatus::Closed(witness_txid))
+            }
+            SealEvent::Mined {
+                seal,
+                witness_txid,
+                pos,
+            } => (seal, SealStatus::mined(witness_txid, pos.height, tip, buried_depth)),
+        };
+        self.set(seal, status);
+        self.status(seal)
+    }
+
+    /// Updates status of the seal from the status of the witness transaction
+    /// closing it, as returned by a witness resolver.
+    ///
+    /// Returns the new status, or `None` if the seal is not tracked.
+    pub fn update_witness(
+        &mut self,
+        seal: Outpoint,
+        witness: Txid,
+        ord: WitnessOrd,
+    ) -> Option<SealStatus> {
+        let (tip, buried_depth) = (self.tip, self.buried_depth);
+        let status = match ord {
+            WitnessOrd::Mined(pos) => SealStatus::mined(witness, pos.height, tip, buried_depth),
+            WitnessOrd::Mempool | WitnessOrd::Unknown => SealStatus::Closed(witness),
+        };
+        self.set(seal, status);
+        self.status(seal)
+    }
+
+    /// Updates chain tip, moving seals with witnesses mined deep enough into
+    /// the [`SealStatus::Buried`] status.
+    ///
+    /// Witnesses mined above the new tip (i.e. reorged out of the chain) are
+    /// moved back to the [`SealStatus::Closed`] status.
+    pub fn update_tip(&mut self, height: u32) {
+        self.tip = height;
+        for status in self.seals.values_mut() {
+            match *status {
+                SealStatus::Mined { witness, height }
+                | SealStatus::Buried {
+                    witness, height, ..
+                } if height > self.tip => *status = SealStatus::Closed(witness),
+                SealStatus::Mined { witness, height }
+                | SealStatus::Buried {
+                    witness, height, ..
+                } => *status = SealStatus::mined(witness, height, self.tip, self.buried_depth),
+                _ => {}
+            }
+        }
+    }
+
+    fn set(&mut self, seal: Outpoint, status: SealStatus) -> bool {
+        match self.seals.get_mut(&seal) {
+            Some(SealStatus::Invalidated) => true,
+            Some(current) => {
+                *current = status;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U16;
+    use bc::Vout;
+
+    use super::*;
+    use crate::WitnessPos;
+
+    #[test]
+    fn lifecycle() {
+        let seal = Outpoint::new(Txid::from([1u8; 32]), Vout::from_u32(0));
+        let witness = Txid::from([2u8; 32]);
+        let mut tracker = SealTracker::default();
+        tracker.update_tip(100);
+        tracker.define(seal);
+        assert_eq!(tracker.status(seal), Some(SealStatus::Defined));
+
+        let spent = SealEvent::Spent {
+            seal,
+            witness_txid: witness,
+        };
+        assert_eq!(tracker.apply(spent), Some(SealStatus::Closed(witness)));
+
+        let mined = SealEvent::Mined {
+            seal,
+            witness_txid: witness,
+            pos: WitnessPos::new(101, 1),
+        };
+        assert_eq!(
+            tracker.apply(mined),
+            Some(SealStatus::Mined {
+                witness,
+                height: 101
+            })
+        );
+
+        tracker.update_tip(106);
+        assert_eq!(
+            tracker.status(seal),
+            Some(SealStatus::Buried {
+                witness,
+                height: 101,
+                depth: 6
+            })
+        );
+
+        tracker.update_tip(100);
+        assert_eq!(tracker.status(seal), Some(SealStatus::Closed(witness)));
+
+        assert!(tracker.invalidate(seal));
+        assert_eq!(tracker.apply(spent), Some(SealStatus::Invalidated));
+
+        let data = tracker.to_strict_serialized::<U16>().unwrap();
+        assert_eq!(SealTracker::from_strict_serialized(data).unwrap(), tracker);
+    }
+}
@@ -0,0 +1,159 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provisional seals defined over a script and amount before the funding
+//! transaction exists.
+
+use amplify::{ByteArray, Bytes};
+use bc::{Outpoint, Sats, ScriptPubkey, Tx, Txid, Vout};
+use commit_verify::{DigestExt, Sha256};
+
+use crate::{Noise, TxoSeal, TxoSealDef, TxoSealExt};
+
+/// Errors binding [`TemplateSeal`] to a funding transaction.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum TemplateError {
+    /// transaction {0} doesn't contain an output matching the seal template.
+    NoMatch(Txid),
+
+    /// transaction {0} contains multiple outputs matching the seal template.
+    Ambiguous(Txid),
+}
+
+/// Provisional seal defined over a scriptPubkey and an expected amount rather
+/// than an outpoint.
+///
+/// Used in invoice flows where the payee has to publish a seal before the
+/// payer's funding transaction exists. Once a transaction paying the script
+/// appears, the template is bound to a concrete [`TxoSeal`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TemplateSeal {
+    /// Script which must be paid by the funding transaction output.
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub script_pubkey: ScriptPubkey,
+    /// Amount which must be paid to the script.
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
+    pub amount: Sats,
+    /// Secondary part of the seal definition, used after the binding.
+    pub secondary: TxoSealExt,
+}
+
+impl TemplateSeal {
+    /// Constructs seal template with a noise derived from the script, amount
+    /// and a nonce.
+    ///
+    /// `nonce` is a deterministic incremental number, preventing from creating
+    /// the same seal if the same script is used.
+    pub fn with_noise(
+        script_pubkey: ScriptPubkey,
+        amount: Sats,
+        mut noise_engine: Sha256,
+        nonce: u64,
+    ) -> Self {
+        noise_engine.input_raw(&nonce.to_be_bytes());
+        noise_engine.input_raw(&script_pubkey[..]);
+        noise_engine.input_raw(&amount.sats().to_be_bytes());
+        let mut noise = [0xFFu8; 40];
+        noise[..32].copy_from_slice(&noise_engine.finish());
+        TemplateSeal {
+            script_pubkey,
+            amount,
+            secondary: TxoSealExt::Noise(Noise::from(Bytes::from_byte_array(noise))),
+        }
+    }
+
+    /// Constructs seal template with a fallback outpoint.
+    pub fn with_fallback(script_pubkey: ScriptPubkey, amount: Sats, fallback: Outpoint) -> Self {
+        TemplateSeal {
+            script_pubkey,
+            amount,
+            secondary: TxoSealExt::Fallback(fallback),
+        }
+    }
+
+    /// Finds an output of the transaction matching the template.
+    ///
+    /// Errors if there is no matching output or if there are several of them,
+    /// in which case the seal can't be bound unambiguously.
+    pub fn find_output(&self, tx: &Tx) -> Result<Vout, TemplateError> {
+        let txid = tx.txid();
+        let mut outputs = tx.outputs().enumerate().filter(|(_, txout)| {
+            txout.script_pubkey == self.script_pubkey && txout.value == self.amount
+        });
+        let (vout, _) = outputs.next().ok_or(TemplateError::NoMatch(txid))?;
+        if outputs.next().is_some() {
+            return Err(TemplateError::Ambiguous(txid));
+        }
+        Ok(Vout::from_u32(vout as u32))
+    }
+
+    /// Binds the template to the output of the funding transaction, returning
+    /// a concrete seal.
+    pub fn bind<D: dbc::Proof>(&self, tx: &Tx) -> Result<TxoSeal<D>, TemplateError> {
+        let vout = self.find_output(tx)?;
+        Ok(TxoSeal::from_definition(TxoSealDef {
+            primary: Outpoint::new(tx.txid(), vout),
+            secondary: self.secondary,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::TxOut;
+    use dbc::opret::OpretProof;
+
+    use super::*;
+    use crate::test_helpers::tx;
+
+    fn funding_tx(outputs: impl IntoIterator<Item = (ScriptPubkey, u64)>) -> Tx {
+        tx([], outputs.into_iter().map(|(spk, value)| TxOut::new(spk, value)))
+    }
+
+    #[test]
+    fn bind() {
+        let script = ScriptPubkey::p2sh([1u8; 20]);
+        let template =
+            TemplateSeal::with_noise(script.clone(), Sats::from(1000u32), Sha256::default(), 0);
+
+        let funding = funding_tx([(ScriptPubkey::p2sh([2u8; 20]), 1000), (script.clone(), 1000)]);
+        let seal = template.bind::<OpretProof>(&funding).unwrap();
+        assert_eq!(seal.primary, Outpoint::new(funding.txid(), Vout::from_u32(1)));
+        assert_eq!(seal.secondary, template.secondary);
+
+        let wrong_amount = funding_tx([(script.clone(), 999)]);
+        assert_eq!(
+            template.bind::<OpretProof>(&wrong_amount),
+            Err(TemplateError::NoMatch(wrong_amount.txid()))
+        );
+
+        let ambiguous = funding_tx([(script.clone(), 1000), (script, 1000)]);
+        assert_eq!(
+            template.bind::<OpretProof>(&ambiguous),
+            Err(TemplateError::Ambiguous(ambiguous.txid()))
+        );
+    }
+}
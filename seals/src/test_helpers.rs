@@ -0,0 +1,106 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fixtures shared by the unit tests of the crate.
+
+use amplify::confinement::SmallOrdMap;
+use amplify::ByteArray;
+use bc::opcodes::OP_RETURN;
+use bc::{
+    LockTime, Outpoint, Sats, ScriptPubkey, SeqNo, Tx, TxIn, TxOut, TxVer, Txid, VarIntArray, Vout,
+};
+use commit_verify::{mpc, CommitId, Sha256};
+use dbc::opret::OpretProof;
+use dbc::{commit_to_tx, CommitMethod, DbcProof};
+
+use crate::{mmb, Anchor, TxoSeal};
+
+/// Output number 0 of the transaction with id filled with the given byte.
+pub fn outpoint(no: u8) -> Outpoint { Outpoint::new(Txid::from([no; 32]), Vout::from_u32(0)) }
+
+/// Opret seal over [`outpoint`] with the given number.
+pub fn seal(no: u8) -> TxoSeal<OpretProof> {
+    TxoSeal::no_fallback(outpoint(no), Sha256::default(), no as u64)
+}
+
+/// Transaction spending the outpoints with zero sequence numbers and having
+/// the given outputs.
+pub fn tx(
+    inputs: impl IntoIterator<Item = Outpoint>,
+    outputs: impl IntoIterator<Item = TxOut>,
+) -> Tx {
+    tx_with_seq(inputs.into_iter().map(|prev_output| (prev_output, SeqNo::ZERO)), outputs)
+}
+
+/// Transaction spending the outpoints with the given sequence numbers and
+/// having the given outputs.
+pub fn tx_with_seq(
+    inputs: impl IntoIterator<Item = (Outpoint, SeqNo)>,
+    outputs: impl IntoIterator<Item = TxOut>,
+) -> Tx {
+    Tx {
+        version: TxVer::V2,
+        inputs: VarIntArray::from_iter_checked(inputs.into_iter().map(
+            |(prev_output, sequence)| TxIn {
+                prev_output,
+                sig_script: none!(),
+                sequence,
+                witness: none!(),
+            },
+        )),
+        outputs: VarIntArray::from_iter_checked(outputs),
+        lock_time: LockTime::ZERO,
+    }
+}
+
+/// P2SH output with the script hash filled with the given byte and a value of
+/// 1000 sats.
+pub fn p2sh_output(no: u8) -> TxOut { TxOut::new(ScriptPubkey::p2sh([no; 20]), 1000u64) }
+
+/// Zero-value output with an empty `OP_RETURN` script, which can host opret
+/// commitment.
+pub fn opret_output() -> TxOut {
+    TxOut::new(ScriptPubkey::from_unsafe(vec![OP_RETURN]), Sats::ZERO)
+}
+
+/// Constructs witness transaction closing the seals over the message with an
+/// opret commitment.
+pub fn close(seals: &[TxoSeal<OpretProof>], msg: mmb::Message) -> (Tx, Anchor<OpretProof>) {
+    let protocol_id = mpc::ProtocolId::from([0xAA; 32]);
+    let mmb_proof = mmb::BundleProof {
+        map: SmallOrdMap::from_iter_checked((0..seals.len() as u32).map(|no| (no, msg))),
+    };
+    let mpc_msg = mpc::Message::from_byte_array(mmb_proof.commit_id().to_byte_array());
+    let tx = tx(seals.iter().map(|seal| seal.primary), [opret_output()]);
+    let (tx, anchor) =
+        commit_to_tx(bmap! { protocol_id => mpc_msg }, tx, CommitMethod::Opret).unwrap();
+    let DbcProof::Opret(dbc_proof) = anchor.dbc_proof else {
+        panic!("opret proof expected")
+    };
+    let anchor = Anchor {
+        mmb_proof,
+        mpc_protocol: protocol_id,
+        mpc_proof: anchor.mpc_proof.to_merkle_proof(protocol_id).unwrap(),
+        dbc_proof,
+        fallback_proof: default!(),
+    };
+    (tx, anchor)
+}
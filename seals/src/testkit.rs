@@ -0,0 +1,350 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! End-to-end test harness running real transactions through a local regtest
+//! Bitcoin Core node.
+//!
+//! The node must run with `-regtest -txindex=1` and a loaded wallet with
+//! spendable coins (see [`Regtest::mine`]). If the node has several wallets,
+//! the RPC URL must point to the wallet endpoint, like
+//! `http://127.0.0.1:18443/wallet/test`.
+
+use amplify::confinement::SmallOrdMap;
+use amplify::hex::{FromHex, ToHex};
+use amplify::ByteArray;
+use bc::opcodes::OP_RETURN;
+use bc::{
+    ConsensusDecode, ConsensusEncode, InternalPk, LockTime, Outpoint, Sats, ScriptPubkey, SeqNo,
+    SigScript, Tx, TxIn, TxOut, TxVer, Txid, VarIntArray, Vout, Witness,
+};
+use commit_verify::{mpc, CommitId, Sha256};
+use dbc::opret::OpretProof;
+use dbc::tapret::{TapretPathProof, TapretProof};
+use dbc::{commit_to_tx, CommitError, CommitMethod, DbcProof};
+use serde_json::{json, Value};
+
+use crate::{mmb, Anchor, BitcoindError, BitcoindResolver, ResolveWitness, TxoSeal, TxoWitness};
+
+/// Value (in satoshis) of the outputs created by the harness for the seals and
+/// the tapret commitments.
+pub const TESTKIT_OUTPUT_VALUE: u64 = 10_000;
+
+/// Fee rate (in satoshis per vbyte) used for funding transactions, leaving
+/// room for the commitments embedded after the funding.
+pub const TESTKIT_FEE_RATE: u64 = 10;
+
+/// Errors happening in the regtest harness.
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum TestkitError {
+    /// request to Bitcoin Core has failed. Details: {0}
+    #[from]
+    Bitcoind(BitcoindError),
+
+    /// Bitcoin Core has returned unexpected response to `{0}` request.
+    InvalidResponse(&'static str),
+
+    /// Bitcoin Core wallet is unable to sign transaction {0}.
+    Unsigned(Txid),
+
+    /// transaction {0} is not known to the node.
+    UnknownTx(Txid),
+
+    /// transaction {0} known to the node differs from the witness transaction.
+    TxMismatch(Txid),
+
+    /// unable to commit to the witness transaction. Details: {0}
+    #[from]
+    Commit(CommitError),
+
+    /// multi-protocol commitment doesn't contain the protocol. Details: {0}
+    #[from]
+    Mpc(mpc::LeafNotKnown),
+
+    /// seals closing verification has failed. Details: {0}
+    Verify(String),
+}
+
+/// Harness for end-to-end tests of seals and anchors against a regtest
+/// Bitcoin Core node.
+///
+/// All transactions are funded and signed by the node wallet; the harness
+/// takes care of placing seals, embedding deterministic bitcoin commitments
+/// and constructing anchors.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Regtest {
+    rpc: BitcoindResolver,
+}
+
+impl Regtest {
+    /// Constructs harness for Bitcoin Core RPC server with a given URL and
+    /// credentials.
+    pub fn new(url: impl ToString, user: impl ToString, password: impl ToString) -> Self {
+        Regtest {
+            rpc: BitcoindResolver::new(url).with_auth(user, password),
+        }
+    }
+
+    /// Returns witness resolver connected to the node.
+    pub fn resolver(&self) -> &BitcoindResolver { &self.rpc }
+
+    fn call(&self, method: &'static str, params: Value) -> Result<Value, TestkitError> {
+        self.rpc.call(method, params)?.ok_or(TestkitError::InvalidResponse(method))
+    }
+
+    fn call_tx(&self, method: &'static str, params: Value) -> Result<(Tx, Value), TestkitError> {
+        let resp = self.call(method, params)?;
+        let data = resp["hex"]
+            .as_str()
+            .and_then(|hex| Vec::<u8>::from_hex(hex).ok())
+            .ok_or(TestkitError::InvalidResponse(method))?;
+        let tx =
+            Tx::consensus_deserialize(data).map_err(|_| TestkitError::InvalidResponse(method))?;
+        Ok((tx, resp))
+    }
+
+    /// Generates new segwit v0 address of the node wallet.
+    pub fn new_address(&self) -> Result<String, TestkitError> {
+        let addr = self.call("getnewaddress", json!(["", "bech32"]))?;
+        addr.as_str().map(str::to_owned).ok_or(TestkitError::InvalidResponse("getnewaddress"))
+    }
+
+    /// Generates new segwit v0 scriptPubkey controlled by the node wallet.
+    pub fn new_script(&self) -> Result<ScriptPubkey, TestkitError> {
+        let info = self.call("getaddressinfo", json!([self.new_address()?]))?;
+        info["scriptPubKey"]
+            .as_str()
+            .and_then(|hex| Vec::<u8>::from_hex(hex).ok())
+            .map(ScriptPubkey::from_unsafe)
+            .ok_or(TestkitError::InvalidResponse("getaddressinfo"))
+    }
+
+    /// Mines a number of blocks paying the coinbase to the node wallet.
+    ///
+    /// Coinbase outputs become spendable after 100 confirmations, so fresh
+    /// regtest chains need at least 101 blocks mined before any funding.
+    pub fn mine(&self, blocks: u32) -> Result<(), TestkitError> {
+        self.call("generatetoaddress", json!([blocks, self.new_address()?]))?;
+        Ok(())
+    }
+
+    /// Adds wallet inputs and a change output to the transaction.
+    ///
+    /// The inputs and outputs of the original transaction keep their
+    /// positions, and the change always uses segwit v0, such that it never
+    /// becomes the first taproot output of the transaction.
+    pub fn fund(&self, tx: &Tx) -> Result<Tx, TestkitError> {
+        let options = json!({
+            "add_inputs": true,
+            "changePosition": tx.outputs.len(),
+            "change_type": "bech32",
+            "fee_rate": TESTKIT_FEE_RATE,
+        });
+        let params = json!([tx.consensus_serialize().to_hex(), options, false]);
+        self.call_tx("fundrawtransaction", params).map(|(tx, _)| tx)
+    }
+
+    /// Signs transaction with the node wallet and broadcasts it.
+    pub fn sign_and_send(&self, tx: &Tx) -> Result<Tx, TestkitError> {
+        let params = json!([tx.consensus_serialize().to_hex()]);
+        let (signed, resp) = self.call_tx("signrawtransactionwithwallet", params)?;
+        if resp["complete"].as_bool() != Some(true) {
+            return Err(TestkitError::Unsigned(tx.txid()));
+        }
+        self.call("sendrawtransaction", json!([signed.consensus_serialize().to_hex()]))?;
+        Ok(signed)
+    }
+
+    /// Creates a transaction with an output for each of the seals, mines it
+    /// and returns the seals defined over these outputs.
+    pub fn define_seals<D: dbc::Proof>(
+        &self,
+        count: u16,
+        noise_engine: Sha256,
+    ) -> Result<Vec<TxoSeal<D>>, TestkitError> {
+        let outputs = (0..count)
+            .map(|_| self.new_script().map(|spk| TxOut::new(spk, TESTKIT_OUTPUT_VALUE)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tx = self.sign_and_send(&self.fund(&tx(vec![], outputs))?)?;
+        self.mine(1)?;
+        let txid = tx.txid();
+        Ok((0..count as u32)
+            .map(|no| {
+                let outpoint = Outpoint::new(txid, Vout::from_u32(no));
+                TxoSeal::no_fallback(outpoint, noise_engine.clone(), no as u64)
+            })
+            .collect())
+    }
+
+    fn close<D: dbc::Proof>(
+        &self,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+        protocol_id: mpc::ProtocolId,
+        outputs: Vec<TxOut>,
+        method: CommitMethod,
+        dbc_proof: impl FnOnce(DbcProof) -> Option<D>,
+    ) -> Result<TxoWitness<D>, TestkitError> {
+        let inputs = seals.iter().map(|seal| seal.primary).collect();
+        let tx = self.fund(&tx(inputs, outputs))?;
+
+        let map = (0..seals.len() as u32).map(|no| (no, msg));
+        let mmb_proof = mmb::BundleProof {
+            map: SmallOrdMap::from_iter_checked(map),
+        };
+        let mpc_msg = mpc::Message::from_byte_array(mmb_proof.commit_id().to_byte_array());
+        let (tx, anchor) = commit_to_tx(bmap! { protocol_id => mpc_msg }, tx, method)?;
+        let mpc_proof = anchor.mpc_proof.to_merkle_proof(protocol_id)?;
+        let dbc_proof =
+            dbc_proof(anchor.dbc_proof).expect("commitment method matches the proof type");
+
+        let tx = self.sign_and_send(&tx)?;
+        self.mine(1)?;
+        let anchor = Anchor {
+            mmb_proof,
+            mpc_protocol: protocol_id,
+            mpc_proof,
+            dbc_proof,
+            fallback_proof: default!(),
+        };
+        Ok(TxoWitness::new(tx, anchor))
+    }
+
+    /// Closes seals over a message with a transaction committing to it with
+    /// opret commitment, mines the transaction and returns the witness.
+    pub fn close_opret(
+        &self,
+        seals: &[TxoSeal<OpretProof>],
+        msg: mmb::Message,
+        protocol_id: mpc::ProtocolId,
+    ) -> Result<TxoWitness<OpretProof>, TestkitError> {
+        let outputs = vec![TxOut::new(ScriptPubkey::from_unsafe(vec![OP_RETURN]), Sats::ZERO)];
+        self.close(seals, msg, protocol_id, outputs, CommitMethod::Opret, |proof| match proof {
+            DbcProof::Opret(proof) => Some(proof),
+            DbcProof::Tapret(_) => None,
+        })
+    }
+
+    /// Closes seals over a message with a transaction committing to it with
+    /// tapret commitment in a taproot output with a given internal key, mines
+    /// the transaction and returns the witness.
+    ///
+    /// The commitment output is not controlled by the node wallet.
+    pub fn close_tapret(
+        &self,
+        seals: &[TxoSeal<TapretProof>],
+        msg: mmb::Message,
+        protocol_id: mpc::ProtocolId,
+        internal_pk: InternalPk,
+    ) -> Result<TxoWitness<TapretProof>, TestkitError> {
+        let outputs =
+            vec![TxOut::new(ScriptPubkey::p2tr_key_only(internal_pk), TESTKIT_OUTPUT_VALUE)];
+        let method = CommitMethod::Tapret {
+            internal_pk,
+            path_proof: TapretPathProof::root(0),
+        };
+        self.close(seals, msg, protocol_id, outputs, method, |proof| match proof {
+            DbcProof::Tapret(proof) => Some(proof),
+            DbcProof::Opret(_) => None,
+        })
+    }
+
+    /// Verifies that the witness transaction is known to the node and that
+    /// it closes the seals over the message.
+    pub fn verify<D: dbc::Proof>(
+        &self,
+        witness: &TxoWitness<D>,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(), TestkitError> {
+        let txid = witness.tx.txid();
+        let tx = self.rpc.resolve_tx(txid)?.ok_or(TestkitError::UnknownTx(txid))?;
+        if tx != witness.tx {
+            return Err(TestkitError::TxMismatch(txid));
+        }
+        witness.verify_seals(seals, msg).map_err(|err| TestkitError::Verify(err.to_string()))
+    }
+}
+
+fn tx(inputs: Vec<Outpoint>, outputs: Vec<TxOut>) -> Tx {
+    let inputs = inputs.into_iter().map(|prev_output| TxIn {
+        prev_output,
+        sig_script: SigScript::empty(),
+        sequence: SeqNo::ZERO,
+        witness: Witness::new(),
+    });
+    Tx {
+        version: TxVer::V2,
+        inputs: VarIntArray::from_iter_checked(inputs),
+        outputs: VarIntArray::from_iter_checked(outputs),
+        lock_time: LockTime::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::str::FromStr;
+
+    use super::*;
+
+    const INTERNAL_PK: &str = "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3";
+
+    /// Connects to the regtest node given by `BP_REGTEST_URL`,
+    /// `BP_REGTEST_USER` and `BP_REGTEST_PASSWORD` environment variables,
+    /// mining blocks to make the wallet coins spendable.
+    fn regtest() -> Regtest {
+        let var = |name: &str, default: &str| env::var(name).unwrap_or_else(|_| default.to_owned());
+        let regtest = Regtest::new(
+            var("BP_REGTEST_URL", "http://127.0.0.1:18443"),
+            var("BP_REGTEST_USER", "bitcoin"),
+            var("BP_REGTEST_PASSWORD", "bitcoin"),
+        );
+        regtest.mine(101).unwrap();
+        regtest
+    }
+
+    #[test]
+    #[ignore = "requires regtest bitcoind"]
+    fn opret() {
+        let regtest = regtest();
+        let seals = regtest.define_seals::<OpretProof>(2, Sha256::default()).unwrap();
+        let msg = mmb::Message::from([1u8; 32]);
+        let protocol_id = mpc::ProtocolId::from([2u8; 32]);
+        let witness = regtest.close_opret(&seals, msg, protocol_id).unwrap();
+        regtest.verify(&witness, &seals, msg).unwrap();
+        let other = mmb::Message::from([3u8; 32]);
+        assert!(matches!(regtest.verify(&witness, &seals, other), Err(TestkitError::Verify(_))));
+    }
+
+    #[test]
+    #[ignore = "requires regtest bitcoind"]
+    fn tapret() {
+        let regtest = regtest();
+        let seals = regtest.define_seals::<TapretProof>(1, Sha256::default()).unwrap();
+        let msg = mmb::Message::from([1u8; 32]);
+        let protocol_id = mpc::ProtocolId::from([2u8; 32]);
+        let internal_pk = InternalPk::from_str(INTERNAL_PK).unwrap();
+        let witness = regtest.close_tapret(&seals, msg, protocol_id, internal_pk).unwrap();
+        regtest.verify(&witness, &seals, msg).unwrap();
+        assert_eq!(witness.anchor.dbc_proof.internal_pk, internal_pk);
+    }
+}
@@ -30,14 +30,26 @@ use core::marker::PhantomData;
 use amplify::{ByteArray, Bytes, Bytes32};
 use bc::{Outpoint, Tx, Txid, Vout};
 use commit_verify::{CommitId, DigestExt, ReservedBytes, Sha256, StrictHash};
-use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
-use strict_encoding::StrictDumb;
+use dbc::armor::{strict_id, StrictArmor};
+use dbc::container::Containerized;
+use dbc::{NetworkBound, PolicyViolation, VerifyOpts};
+use single_use_seals::{
+    ClientSideWitness, PublishedWitness, SealError, SealWitness, SingleUseSeal,
+};
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+
+use crate::{TxProof, TxProofError};
+
+/// Placeholder txid of seals defined over an output of a witness transaction which is not known
+/// yet, like the ones constructed with [`TxoSeal::vout_no_fallback`].
+pub const WITNESS_TXID_PLACEHOLDER: [u8; 32] = [0xFF; 32];
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = dbc::LIB_NAME_BPCORE)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
-pub struct Noise(Bytes<40>);
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Noise(#[cfg_attr(feature = "schemars", schemars(with = "String"))] Bytes<40>);
 
 pub mod mmb {
     use amplify::confinement::SmallOrdMap;
@@ -50,9 +62,11 @@ pub mod mmb {
     #[derive(StrictType, StrictEncode, StrictDecode)]
     #[strict_type(lib = dbc::LIB_NAME_BPCORE)]
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct Message(
         #[from]
         #[from([u8; 32])]
+        #[cfg_attr(feature = "schemars", schemars(with = "String"))]
         Bytes32,
     );
 
@@ -61,9 +75,11 @@ pub mod mmb {
     #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
     #[strict_type(lib = dbc::LIB_NAME_BPCORE)]
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct Commitment(
         #[from]
         #[from([u8; 32])]
+        #[cfg_attr(feature = "schemars", schemars(with = "String"))]
         Bytes32,
     );
     impl CommitmentId for Commitment {
@@ -83,10 +99,29 @@ pub mod mmb {
     #[derive(CommitEncode)]
     #[commit_encode(strategy = strict, id = Commitment)]
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct BundleProof {
+        #[cfg_attr(
+            feature = "schemars",
+            schemars(with = "std::collections::BTreeMap<u32, Message>")
+        )]
         pub map: SmallOrdMap<u32, Message>,
     }
 
+    impl StrictSerialize for BundleProof {}
+    impl StrictDeserialize for BundleProof {}
+
+    impl Containerized for BundleProof {
+        const CONTAINER_TAG: u16 = 0x0200;
+    }
+
+    impl StrictArmor for BundleProof {
+        type Id = StrictHash;
+        const PLATE_TITLE: &'static str = "SEAL BUNDLE PROOF";
+
+        fn armor_id(&self) -> Self::Id { strict_id(self) }
+    }
+
     impl BundleProof {
         pub fn verify(&self, seal: Outpoint, msg: Message, tx: &Tx) -> bool {
             let Some(input_index) = tx.inputs().position(|input| input.prev_output == seal) else {
@@ -185,17 +220,71 @@ pub mod mpc {
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = dbc::LIB_NAME_BPCORE)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
 pub struct Anchor<D: dbc::Proof> {
     pub mmb_proof: mmb::BundleProof,
     pub mpc_protocol: mpc::ProtocolId,
     pub mpc_proof: mpc::MerkleProof,
     pub dbc_proof: D,
-    #[cfg_attr(feature = "serde", serde(skip))]
     // TODO: This should become an option once fallback proofs are ready
     pub fallback_proof: ReservedBytes<1>,
 }
 
+impl<D: dbc::Proof> StrictSerialize for Anchor<D> {}
+impl<D: dbc::Proof> StrictDeserialize for Anchor<D> {}
+
+#[cfg(feature = "serde")]
+mod _serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    impl<D: dbc::Proof> Serialize for Anchor<D> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            dbc::strict_serde::serialize(self, serializer)
+        }
+    }
+
+    impl<'de, D: dbc::Proof> Deserialize<'de> for Anchor<D> {
+        fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+            dbc::strict_serde::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<D: dbc::Proof> schemars::JsonSchema for Anchor<D> {
+    fn schema_name() -> String { s!("Anchor") }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        <String as schemars::JsonSchema>::json_schema(gen)
+    }
+}
+
+impl<D: dbc::Proof> Containerized for Anchor<D> {
+    const CONTAINER_TAG: u16 = 0x0210 | D::METHOD as u16;
+}
+
+impl<D: dbc::Proof> StrictArmor for Anchor<D> {
+    type Id = StrictHash;
+    const PLATE_TITLE: &'static str = "SEAL ANCHOR";
+
+    fn armor_id(&self) -> Self::Id { strict_id(self) }
+}
+
+#[cfg(feature = "cbor")]
+mod _cbor {
+    use dbc::cbor::Cbor;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+
+    use super::*;
+
+    impl Cbor for mmb::BundleProof {}
+    impl<D: dbc::Proof + Serialize + DeserializeOwned> Cbor for Anchor<D> {}
+    impl Cbor for TxoSealDef {}
+    impl<D: dbc::Proof> Cbor for TxoSeal<D> {}
+}
+
 impl<D: dbc::Proof> Anchor<D> {
     // TODO: Change when the fallback proofs are ready
     pub fn is_fallback(&self) -> bool { false }
@@ -213,6 +302,7 @@ pub struct Proof<D: dbc::Proof> {
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = dbc::LIB_NAME_BPCORE, tags = custom)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum TxoSealExt {
     #[display("~")]
     #[strict_type(tag = 0)]
@@ -238,11 +328,22 @@ impl StrictDumb for TxoSealExt {
 #[derive(CommitEncode)]
 #[commit_encode(strategy = strict, id = StrictHash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TxoSealDef {
     pub primary: Outpoint,
     pub secondary: TxoSealExt,
 }
 
+impl StrictSerialize for TxoSealDef {}
+impl StrictDeserialize for TxoSealDef {}
+
+impl StrictArmor for TxoSealDef {
+    type Id = StrictHash;
+    const PLATE_TITLE: &'static str = "SEAL DEFINITION";
+
+    fn armor_id(&self) -> Self::Id { self.commit_id() }
+}
+
 impl<D: dbc::Proof> From<TxoSeal<D>> for TxoSealDef {
     fn from(seal: TxoSeal<D>) -> Self {
         TxoSealDef {
@@ -257,6 +358,7 @@ impl<D: dbc::Proof> From<TxoSeal<D>> for TxoSealDef {
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = dbc::LIB_NAME_BPCORE)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TxoSeal<D: dbc::Proof> {
     pub primary: Outpoint,
     pub secondary: TxoSealExt,
@@ -280,12 +382,34 @@ impl<D: dbc::Proof> TxoSeal<D> {
     /// `nonce` is a deterministic incremental number, preventing from creating the same seal if the
     /// same output is used.
     pub fn vout_no_fallback(vout: Vout, noise_engine: Sha256, nonce: u64) -> Self {
-        Self::no_fallback(Outpoint::new(Txid::from([0xFFu8; 32]), vout), noise_engine, nonce)
+        Self::no_fallback(
+            Outpoint::new(Txid::from(WITNESS_TXID_PLACEHOLDER), vout),
+            noise_engine,
+            nonce,
+        )
+    }
+
+    /// Detects whether the seal is defined over an output of a witness transaction which is not
+    /// known yet, i.e. uses [`WITNESS_TXID_PLACEHOLDER`] as the txid of its primary outpoint.
+    pub fn is_witness_relative(&self) -> bool {
+        self.primary.txid == Txid::from(WITNESS_TXID_PLACEHOLDER)
     }
 
     /// `nonce` is a deterministic incremental number, preventing from creating the same seal if the
     /// same output is used.
-    pub fn no_fallback(outpoint: Outpoint, mut noise_engine: Sha256, nonce: u64) -> Self {
+    ///
+    /// Accepts outpoints of any type convertible into [`Outpoint`], including `bitcoin::OutPoint`
+    /// when `rust-bitcoin` feature is enabled.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(nonce = nonce))
+    )]
+    pub fn no_fallback(
+        outpoint: impl Into<Outpoint>,
+        mut noise_engine: Sha256,
+        nonce: u64,
+    ) -> Self {
+        let outpoint = outpoint.into();
         noise_engine.input_raw(&nonce.to_be_bytes());
         noise_engine.input_raw(outpoint.txid.as_ref());
         noise_engine.input_raw(&outpoint.vout.to_u32().to_be_bytes());
@@ -307,6 +431,14 @@ impl<D: dbc::Proof> TxoSeal<D> {
     }
 
     pub fn to_definition(&self) -> TxoSealDef { TxoSealDef::from(*self) }
+
+    /// Returns outpoint which must be spent by the witness closing the seal with the anchor.
+    pub fn closing_outpoint(&self, anchor: &Anchor<D>) -> Outpoint {
+        match self.secondary {
+            TxoSealExt::Fallback(fallback) if anchor.is_fallback() => fallback,
+            _ => self.primary,
+        }
+    }
 }
 
 impl<D: dbc::Proof> SingleUseSeal for TxoSeal<D> {
@@ -393,3 +525,125 @@ pub enum AnchorError {
     #[display("message {0} is not part of the anchor")]
     Mmb(mmb::Message),
 }
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum SealVerifyError<E: Error> {
+    /// no seals are provided for the verification.
+    NoSeals,
+
+    /// seal {0} is not spent by the witness transaction.
+    NotSpent(Outpoint),
+
+    /// anchor uses fallback proof, but seal {0} doesn't define a fallback outpoint.
+    NoFallback(Outpoint),
+
+    /// message is not committed to by the witness transaction input spending {0}.
+    NotIncluded(Outpoint),
+
+    /// invalid anchor. Details: {0}
+    #[from]
+    Anchor(AnchorError),
+
+    /// invalid deterministic bitcoin commitment. Details: {0}
+    Dbc(E),
+
+    /// invalid proof of witness transaction inclusion into a block. Details: {0}
+    #[from]
+    Inclusion(TxProofError),
+
+    /// verification policy is violated. Details: {0}
+    #[from]
+    Policy(PolicyViolation),
+}
+
+/// Witness closing a set of txout seals, consisting of the witness transaction and the anchor
+/// proving commitment to the message.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TxoWitness<D: dbc::Proof> {
+    #[cfg_attr(feature = "schemars", schemars(with = "dbc::schema::Opaque"))]
+    pub tx: Tx,
+    pub anchor: Anchor<D>,
+    /// Optional SPV proof of the witness transaction inclusion into a block.
+    pub tx_proof: Option<TxProof>,
+}
+
+impl<D: dbc::Proof> TxoWitness<D> {
+    pub fn new(tx: Tx, anchor: Anchor<D>) -> Self {
+        Self {
+            tx,
+            anchor,
+            tx_proof: None,
+        }
+    }
+
+    /// Attaches SPV proof of the witness transaction inclusion into a block.
+    pub fn with_tx_proof(mut self, tx_proof: TxProof) -> Self {
+        self.tx_proof = Some(tx_proof);
+        self
+    }
+
+    /// Verifies that all the seals are closed by the witness transaction over the message, i.e.
+    /// that the transaction spends each of the seals, commits to the message in the inputs spending
+    /// them and contains the deterministic bitcoin commitment to the anchored data. If the witness
+    /// has SPV proof attached, also verifies the transaction inclusion into the block.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(txid = %self.tx.txid(), method = %D::METHOD, seals = seals.len()),
+        err(level = "debug")
+    ))]
+    pub fn verify_seals(
+        &self,
+        seals: &[TxoSeal<D>],
+        msg: mmb::Message,
+    ) -> Result<(), SealVerifyError<D::Error>> {
+        if seals.is_empty() {
+            return Err(SealVerifyError::NoSeals);
+        }
+        if let Some(tx_proof) = &self.tx_proof {
+            tx_proof.verify_tx(&self.tx)?;
+        }
+        for seal in seals {
+            if self.anchor.is_fallback() && matches!(seal.secondary, TxoSealExt::Noise(_)) {
+                return Err(SealVerifyError::NoFallback(seal.primary));
+            }
+            let outpoint = seal.closing_outpoint(&self.anchor);
+            #[cfg(feature = "tracing")]
+            tracing::trace!(%outpoint, "verifying seal closing");
+            if !self.tx.inputs().any(|input| input.prev_output == outpoint) {
+                return Err(SealVerifyError::NotSpent(outpoint));
+            }
+        }
+        SealWitness::<TxoSeal<D>>::new(self.tx.clone(), self.anchor.clone())
+            .verify_seals_closing(seals, msg)
+            .map_err(|err| match err {
+                SealError::NotIncluded(seal, _) => {
+                    SealVerifyError::NotIncluded(seal.closing_outpoint(&self.anchor))
+                }
+                SealError::Published(err) => SealVerifyError::Dbc(err),
+                SealError::Client(err) => SealVerifyError::Anchor(err),
+            })
+    }
+
+    /// Verifies closing of the seals marked with a network, checking that each of the seals is
+    /// created for the network required by the options.
+    ///
+    /// See [`TxoWitness::verify_seals`] for the details of the verification.
+    pub fn verify_network_seals(
+        &self,
+        seals: &[NetworkBound<TxoSeal<D>>],
+        msg: mmb::Message,
+        opts: &VerifyOpts,
+    ) -> Result<(), SealVerifyError<D::Error>> {
+        for seal in seals {
+            seal.check_network(opts)?;
+        }
+        let seals = seals.iter().map(|seal| seal.data).collect::<Vec<_>>();
+        self.verify_seals(&seals, msg)
+    }
+}
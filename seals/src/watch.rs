@@ -0,0 +1,156 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Derivation of data for detecting seal closings with BIP-157/158 compact block filters.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bc::{Outpoint, ScriptPubkey, Tx, Txid};
+
+use crate::{TxoSeal, TxoSealExt, WITNESS_TXID_PLACEHOLDER};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum WatchError {
+    /// transaction defining sealed outpoint {0} is not provided.
+    UnknownPrevout(Outpoint),
+}
+
+/// Set of outpoints and scripts to match against compact block filters for detecting blocks
+/// closing the seals.
+///
+/// BIP-158 basic filters commit to the scripts of the outputs spent by the block transactions,
+/// thus a block closing a seal matches the script locked by the sealed outpoint.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct WatchSet {
+    /// Sealed outpoints by the scripts they are locked with.
+    pub scripts: BTreeMap<ScriptPubkey, BTreeSet<Outpoint>>,
+}
+
+impl WatchSet {
+    /// Constructs watch set for the seals, taking sealed output scripts from the transactions
+    /// defining them.
+    ///
+    /// Both primary and fallback outpoints are watched. Seals defined relative to a witness
+    /// transaction which is not yet known are skipped.
+    pub fn with<'tx, D: dbc::Proof>(
+        seals: &[TxoSeal<D>],
+        prev_txs: impl IntoIterator<Item = &'tx Tx>,
+    ) -> Result<Self, WatchError> {
+        let prev_txs = prev_txs.into_iter().map(|tx| (tx.txid(), tx)).collect::<BTreeMap<_, _>>();
+        let mut watch_set = WatchSet::default();
+        let outpoints = seals.iter().flat_map(|seal| match seal.secondary {
+            TxoSealExt::Fallback(fallback) => vec![seal.primary, fallback],
+            TxoSealExt::Noise(_) => vec![seal.primary],
+        });
+        for outpoint in outpoints {
+            if outpoint.txid == Txid::from(WITNESS_TXID_PLACEHOLDER) {
+                continue;
+            }
+            let txout = prev_txs
+                .get(&outpoint.txid)
+                .and_then(|tx| tx.outputs().nth(outpoint.vout.to_usize()))
+                .ok_or(WatchError::UnknownPrevout(outpoint))?;
+            watch_set.insert(txout.script_pubkey.clone(), outpoint);
+        }
+        Ok(watch_set)
+    }
+
+    /// Adds outpoint locked with a given script to the watch set.
+    pub fn insert(&mut self, script_pubkey: ScriptPubkey, outpoint: Outpoint) {
+        self.scripts.entry(script_pubkey).or_default().insert(outpoint);
+    }
+
+    /// Detects whether the watch set is empty.
+    pub fn is_empty(&self) -> bool { self.scripts.is_empty() }
+
+    /// Iterates over all watched outpoints.
+    pub fn outpoints(&self) -> impl Iterator<Item = Outpoint> + '_ {
+        self.scripts.values().flatten().copied()
+    }
+
+    /// Returns elements to be matched against BIP-158 basic filters.
+    pub fn filter_elements(&self) -> impl Iterator<Item = &[u8]> {
+        self.scripts.keys().map(|script| &script[..])
+    }
+
+    /// Returns transactions from a block matching the filter which close some of the watched
+    /// seals, together with the closed outpoints.
+    pub fn closing_txs<'tx>(
+        &self,
+        block_txs: impl IntoIterator<Item = &'tx Tx>,
+    ) -> Vec<(&'tx Tx, BTreeSet<Outpoint>)> {
+        let watched = self.outpoints().collect::<BTreeSet<_>>();
+        block_txs
+            .into_iter()
+            .filter_map(|tx| {
+                let closed = tx
+                    .inputs()
+                    .map(|input| input.prev_output)
+                    .filter(|outpoint| watched.contains(outpoint))
+                    .collect::<BTreeSet<_>>();
+                (!closed.is_empty()).then_some((tx, closed))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::Vout;
+    use commit_verify::Sha256;
+    use dbc::opret::OpretProof;
+
+    use super::*;
+    use crate::test_helpers::{outpoint, p2sh_output, seal, tx};
+    use crate::TxoSealDef;
+
+    #[test]
+    fn watch() {
+        let prev_tx = tx([Outpoint::coinbase()], [p2sh_output(1), p2sh_output(2)]);
+        let txid = prev_tx.txid();
+        let sealed = Outpoint::new(txid, Vout::from_u32(0));
+        let fallback = Outpoint::new(txid, Vout::from_u32(1));
+        let seals = [
+            TxoSeal::<OpretProof>::from_definition(TxoSealDef {
+                primary: sealed,
+                secondary: TxoSealExt::Fallback(fallback),
+            }),
+            TxoSeal::vout_no_fallback(Vout::from_u32(0), Sha256::default(), 0),
+        ];
+
+        let watch_set = WatchSet::with(&seals, [&prev_tx]).unwrap();
+        assert_eq!(watch_set.outpoints().collect::<Vec<_>>(), vec![sealed, fallback]);
+        assert_eq!(watch_set.filter_elements().count(), 2);
+
+        let closing = tx([fallback], [p2sh_output(1)]);
+        let unrelated = tx([outpoint(3)], [p2sh_output(2)]);
+        let closing_txs = watch_set.closing_txs([&unrelated, &closing]);
+        assert_eq!(closing_txs.len(), 1);
+        assert_eq!(closing_txs[0].0, &closing);
+        assert_eq!(closing_txs[0].1, bset! { fallback });
+
+        assert_eq!(
+            WatchSet::with(&[seal(4)], [&prev_tx]),
+            Err(WatchError::UnknownPrevout(outpoint(4)))
+        );
+    }
+}
@@ -0,0 +1,407 @@
+// Bitcoin protocol single-use-seals library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Position of witness transactions relative to the blockchain.
+
+use core::cmp::Ordering;
+use core::fmt::{self, Display, Formatter};
+use core::hash::{Hash, Hasher};
+use std::collections::{BTreeMap, BTreeSet};
+
+use bc::{BlockHeader, Outpoint, Tx, Txid};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{TxoSeal, TxoSealExt};
+
+/// Maximal value of an input sequence number which signals replaceability of a transaction
+/// according to BIP-125.
+pub const BIP125_MAX_SEQ_NO: u32 = 0xFFFF_FFFD;
+
+/// Number of blocks, ending with the given block, which timestamps are used to compute the
+/// block median-time-past.
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Time information of a block, used by protocols enforcing time-based rules.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BlockTime {
+    /// Timestamp of the block header, as claimed by the miner.
+    pub timestamp: u32,
+    /// Median time past of the block, i.e. the median of the timestamps of the block and
+    /// [`MEDIAN_TIME_SPAN`]` - 1` blocks preceding it.
+    pub mtp: u32,
+}
+
+impl BlockTime {
+    /// Constructs time information from the block header timestamp and the block
+    /// median-time-past.
+    pub fn new(timestamp: u32, mtp: u32) -> Self { BlockTime { timestamp, mtp } }
+
+    /// Computes time information for the last of the provided headers.
+    ///
+    /// The headers must go in the blockchain order, ending with the block in question. Only the
+    /// last [`MEDIAN_TIME_SPAN`] headers are used for the median-time-past computation; if fewer
+    /// headers are provided (like for the blocks close to the genesis), the median is computed
+    /// over the available ones. Returns `None` if no headers are provided.
+    pub fn from_headers(headers: &[BlockHeader]) -> Option<Self> {
+        let times = headers.iter().map(|header| header.time).collect::<Vec<_>>();
+        Self::from_timestamps(&times)
+    }
+
+    /// Computes time information for the last block from the timestamps of block headers.
+    ///
+    /// Works the same way as [`BlockTime::from_headers`].
+    pub fn from_timestamps(times: &[u32]) -> Option<Self> {
+        let timestamp = *times.last()?;
+        let mut times = times[times.len().saturating_sub(MEDIAN_TIME_SPAN)..].to_vec();
+        times.sort_unstable();
+        Some(BlockTime {
+            timestamp,
+            mtp: times[times.len() / 2],
+        })
+    }
+
+    /// Returns block header timestamp as a UTC date and time.
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_utc(&self) -> chrono::DateTime<chrono::Utc> { utc(self.timestamp) }
+
+    /// Returns block median-time-past as a UTC date and time.
+    #[cfg(feature = "chrono")]
+    pub fn mtp_utc(&self) -> chrono::DateTime<chrono::Utc> { utc(self.mtp) }
+}
+
+#[cfg(feature = "chrono")]
+fn utc(time: u32) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(time as i64, 0)
+        .expect("32-bit unix timestamp is always within chrono range")
+}
+
+/// Position of a transaction mined into a block.
+///
+/// Positions are ordered first by the block height and then by the index of
+/// the transaction within the block, matching the order of transactions in the
+/// blockchain.
+///
+/// If the witness resolver was able to retrieve it, the position also contains
+/// the time information of the block. The time information doesn't participate
+/// in the comparison, hashing and strict encoding of the positions, such that
+/// the same position reported by resolvers with and without block time support
+/// is the same.
+#[derive(Copy, Clone, Debug, Display)]
+#[display("{height}:{tx_index}")]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WitnessPos {
+    /// Height of the block containing the transaction.
+    pub height: u32,
+    /// Index of the transaction within the block.
+    pub tx_index: u32,
+    /// Time information of the block containing the transaction, if known.
+    #[strict_type(skip)]
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub time: Option<BlockTime>,
+}
+
+impl StrictSerialize for WitnessPos {}
+impl StrictDeserialize for WitnessPos {}
+
+impl PartialEq for WitnessPos {
+    fn eq(&self, other: &Self) -> bool {
+        self.height == other.height && self.tx_index == other.tx_index
+    }
+}
+
+impl Eq for WitnessPos {}
+
+impl PartialOrd for WitnessPos {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for WitnessPos {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.height, self.tx_index).cmp(&(other.height, other.tx_index))
+    }
+}
+
+impl Hash for WitnessPos {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.height.hash(state);
+        self.tx_index.hash(state);
+    }
+}
+
+impl WitnessPos {
+    /// Constructs position of a transaction mined into a block at the given height, without the
+    /// block time information.
+    pub fn new(height: u32, tx_index: u32) -> Self {
+        WitnessPos {
+            height,
+            tx_index,
+            time: None,
+        }
+    }
+
+    /// Constructs position of a transaction mined into a block at the given height, including
+    /// the block time information.
+    pub fn with_time(height: u32, tx_index: u32, time: BlockTime) -> Self {
+        WitnessPos {
+            height,
+            tx_index,
+            time: Some(time),
+        }
+    }
+
+    /// Returns timestamp of the block header containing the transaction, if known.
+    #[inline]
+    pub fn timestamp(&self) -> Option<u32> { self.time.map(|time| time.timestamp) }
+
+    /// Returns median-time-past of the block containing the transaction, if known.
+    #[inline]
+    pub fn mtp(&self) -> Option<u32> { self.time.map(|time| time.mtp) }
+}
+
+/// Ordering of witness transactions for the client-side-validation history.
+///
+/// Mined transactions go first, ordered by their position in the blockchain;
+/// they are followed by the transactions which are present in the mempool, and
+/// the transactions with unknown status go last.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = dbc::LIB_NAME_BPCORE, tags = custom, dumb = Self::Unknown)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum WitnessOrd {
+    /// Transaction is mined at the given position.
+    #[strict_type(tag = 0x00)]
+    Mined(WitnessPos),
+
+    /// Transaction is known to be present in the mempool.
+    #[strict_type(tag = 0x01)]
+    Mempool,
+
+    /// Transaction status is not known.
+    #[default]
+    #[strict_type(tag = 0xFF)]
+    Unknown,
+}
+
+impl Display for WitnessOrd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WitnessOrd::Mined(pos) => Display::fmt(pos, f),
+            WitnessOrd::Mempool => f.write_str("mempool"),
+            WitnessOrd::Unknown => f.write_str("unknown"),
+        }
+    }
+}
+
+impl WitnessOrd {
+    /// Detects whether the transaction is mined.
+    #[inline]
+    pub fn is_mined(&self) -> bool { matches!(self, WitnessOrd::Mined(_)) }
+
+    /// Returns position of the transaction in the blockchain, if the transaction is mined.
+    #[inline]
+    pub fn pos(&self) -> Option<WitnessPos> {
+        match self {
+            WitnessOrd::Mined(pos) => Some(*pos),
+            _ => None,
+        }
+    }
+}
+
+/// Detects whether the transaction explicitly signals its replaceability according to BIP-125.
+pub fn signals_rbf(tx: &Tx) -> bool {
+    tx.inputs().any(|input| input.sequence.to_consensus_u32() <= BIP125_MAX_SEQ_NO)
+}
+
+/// Status of a witness transaction closing a set of seals, taking into account the mempool
+/// replaceability of the transaction and other candidate witnesses spending the same seals.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct WitnessStatus {
+    /// Witness transaction id.
+    pub txid: Txid,
+    /// Position of the witness transaction relative to the blockchain.
+    pub ord: WitnessOrd,
+    /// Whether the witness transaction explicitly signals replaceability according to BIP-125.
+    pub rbf: bool,
+    /// Other candidate witnesses spending some of the same seals.
+    pub conflicts: BTreeSet<Txid>,
+}
+
+impl WitnessStatus {
+    /// Constructs status of a witness transaction without any known conflicts.
+    pub fn with(tx: &Tx, ord: WitnessOrd) -> Self {
+        WitnessStatus {
+            txid: tx.txid(),
+            ord,
+            rbf: signals_rbf(tx),
+            conflicts: none!(),
+        }
+    }
+
+    /// Detects whether the seal closure by the witness is final, i.e. the witness is mined.
+    ///
+    /// NB: the caller is responsible for deciding on the number of confirmations required to
+    /// consider mined witness safe against reorgs.
+    #[inline]
+    pub fn is_safe(&self) -> bool { self.ord.is_mined() }
+
+    /// Detects whether the seal closure may still be replaced by a different witness, i.e. the
+    /// witness is not mined and either signals replaceability or conflicts with other candidate
+    /// witnesses.
+    ///
+    /// NB: nodes running full-RBF policy may replace even non-signalling unmined transactions, so
+    /// only [`WitnessStatus::is_safe`] provides a guarantee against the replacement.
+    #[inline]
+    pub fn is_replaceable(&self) -> bool {
+        !self.ord.is_mined() && (self.rbf || !self.conflicts.is_empty())
+    }
+
+    /// Detects whether there are other candidate witnesses spending the same seals.
+    #[inline]
+    pub fn is_conflicting(&self) -> bool { !self.conflicts.is_empty() }
+}
+
+/// Computes statuses of candidate witnesses closing the seals, detecting candidates which spend
+/// the same sealed outpoints.
+///
+/// Transactions not spending any of the seals are ignored.
+pub fn witness_statuses<'tx, D: dbc::Proof>(
+    seals: &[TxoSeal<D>],
+    candidates: impl IntoIterator<Item = (&'tx Tx, WitnessOrd)>,
+) -> Vec<WitnessStatus> {
+    let sealed = seals
+        .iter()
+        .flat_map(|seal| match seal.secondary {
+            TxoSealExt::Fallback(fallback) => vec![seal.primary, fallback],
+            TxoSealExt::Noise(_) => vec![seal.primary],
+        })
+        .collect::<BTreeSet<_>>();
+    let mut spenders = BTreeMap::<Outpoint, BTreeSet<Txid>>::new();
+    let mut statuses = vec![];
+    for (tx, ord) in candidates {
+        let status = WitnessStatus::with(tx, ord);
+        let spent = tx
+            .inputs()
+            .map(|input| input.prev_output)
+            .filter(|outpoint| sealed.contains(outpoint))
+            .collect::<Vec<_>>();
+        if spent.is_empty() {
+            continue;
+        }
+        for outpoint in spent {
+            spenders.entry(outpoint).or_default().insert(status.txid);
+        }
+        statuses.push((status, tx));
+    }
+    statuses
+        .into_iter()
+        .map(|(mut status, tx)| {
+            for input in tx.inputs() {
+                if let Some(txids) = spenders.get(&input.prev_output) {
+                    status.conflicts.extend(txids.iter().filter(|txid| **txid != status.txid));
+                }
+            }
+            status
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{BlockHash, BlockMerkleRoot, SeqNo};
+
+    use super::*;
+    use crate::test_helpers::{outpoint, seal, tx_with_seq};
+
+    #[test]
+    fn conflicts() {
+        let seal = seal(1);
+        let tx1 = tx_with_seq([(outpoint(1), SeqNo::from_consensus_u32(0xFFFF_FFFF))], []);
+        let tx2 = tx_with_seq([(outpoint(1), SeqNo::ZERO)], []);
+        let tx3 = tx_with_seq([(outpoint(2), SeqNo::ZERO)], []);
+
+        let statuses = witness_statuses(&[seal], [
+            (&tx1, WitnessOrd::Mempool),
+            (&tx2, WitnessOrd::Mempool),
+            (&tx3, WitnessOrd::Mempool),
+        ]);
+        assert_eq!(statuses.len(), 2);
+        assert!(!statuses[0].rbf && statuses[1].rbf);
+        assert_eq!(statuses[0].conflicts, bset![tx2.txid()]);
+        assert!(statuses[0].is_replaceable() && !statuses[0].is_safe());
+
+        let status = WitnessStatus::with(&tx1, WitnessOrd::Mined(WitnessPos::new(1, 1)));
+        assert!(status.is_safe() && !status.is_replaceable());
+    }
+
+    #[test]
+    fn ordering() {
+        let a = WitnessOrd::Mined(WitnessPos::new(100, 5));
+        let b = WitnessOrd::Mined(WitnessPos::new(100, 6));
+        let c = WitnessOrd::Mined(WitnessPos::new(101, 0));
+        assert!(a < b && b < c);
+        assert!(c < WitnessOrd::Mempool);
+        assert!(WitnessOrd::Mempool < WitnessOrd::Unknown);
+
+        let timed = WitnessPos::with_time(100, 5, BlockTime::new(1_700_000_000, 1_699_999_000));
+        assert_eq!(WitnessOrd::Mined(timed), a);
+        assert_eq!(timed.cmp(&WitnessPos::new(100, 5)), Ordering::Equal);
+        assert_eq!(bset![timed, WitnessPos::new(100, 5)].len(), 1);
+    }
+
+    #[test]
+    fn encoding() {
+        let timed = WitnessPos::with_time(100, 5, BlockTime::new(1_700_000_000, 1_699_999_000));
+        let plain = WitnessPos::new(100, 5);
+        assert_eq!(timed, plain);
+        let data = timed.to_strict_serialized::<16>().unwrap();
+        assert_eq!(data, plain.to_strict_serialized::<16>().unwrap());
+        assert_eq!(data.len(), 8);
+        let decoded = WitnessPos::from_strict_serialized::<16>(data).unwrap();
+        assert_eq!(decoded.time, None);
+        assert_eq!(decoded, timed);
+    }
+
+    #[test]
+    fn block_time() {
+        let headers = [5u32, 1, 4, 2, 3, 9, 8, 7, 6, 10, 11, 12]
+            .into_iter()
+            .map(|time| BlockHeader {
+                version: 1,
+                prev_block_hash: BlockHash::from([0u8; 32]),
+                merkle_root: BlockMerkleRoot::from([0u8; 32]),
+                time,
+                bits: 0,
+                nonce: 0,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(BlockTime::from_headers(&[]), None);
+        assert_eq!(BlockTime::from_headers(&headers[..3]), Some(BlockTime::new(4, 4)));
+        assert_eq!(BlockTime::from_headers(&headers), Some(BlockTime::new(12, 7)));
+    }
+}
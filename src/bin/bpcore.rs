@@ -0,0 +1,250 @@
+// Bitcoin protocol core library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Command-line tool for inspecting seals, proofs and anchors.
+
+use std::fmt::{Debug, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::{fs, io};
+
+use amplify::hex::{FromHex, ToHex};
+use amplify::ByteArray;
+use bc::Tx;
+use clap::{Parser, Subcommand};
+use commit_verify::{mpc, CommitId};
+use dbc::armor::{AsciiArmor, StrictArmor};
+use dbc::opret::OpretProof;
+use dbc::tapret::TapretProof;
+use dbc::{decode_canonical, DbcProof, Method};
+use seals::{Anchor, TxoSealDef};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Parse seal definition from a hex or an ASCII-armored input and print
+    /// its details, including the concealed seal.
+    Seal {
+        /// Seal definition as a hex string, ASCII armor, a file name or `-`
+        /// for stdin.
+        input: String,
+    },
+
+    /// Decode DBC proof from a hex or an ASCII-armored input and print it.
+    Proof {
+        /// Proof as a hex string, ASCII armor, a file name or `-` for stdin.
+        input: String,
+    },
+
+    /// Decode seal anchor from a hex or an ASCII-armored input and print it.
+    Anchor {
+        /// Anchor as a hex string, ASCII armor, a file name or `-` for stdin.
+        input: String,
+
+        /// Seal closing method used by the anchor.
+        #[arg(short, long, default_value_t = Method::TapretFirst)]
+        method: Method,
+    },
+
+    /// Verify DBC proof against a raw transaction.
+    Verify {
+        /// Proof as a hex string, ASCII armor, a file name or `-` for stdin.
+        proof: String,
+
+        /// Consensus-encoded witness transaction as a hex string.
+        #[arg(short, long)]
+        tx: String,
+
+        /// Multi-protocol commitment which must be committed by the
+        /// transaction, as a hex string.
+        #[arg(short, long)]
+        commitment: String,
+    },
+}
+
+fn read_input(input: &str) -> Result<String, String> {
+    let data = if input == "-" {
+        io::read_to_string(io::stdin()).map_err(|e| format!("unable to read stdin: {e}"))?
+    } else if Path::new(input).is_file() {
+        fs::read_to_string(input).map_err(|e| format!("unable to read '{input}': {e}"))?
+    } else {
+        input.to_owned()
+    };
+    Ok(data.trim().to_owned())
+}
+
+fn decode_hex<T: StrictSerialize + StrictDeserialize>(data: &str) -> Result<T, String> {
+    let data = Vec::<u8>::from_hex(data).map_err(|e| format!("invalid hex data: {e}"))?;
+    decode_canonical(data).map_err(|e| format!("invalid strict-encoded data: {e}"))
+}
+
+fn decode<T: AsciiArmor + StrictSerialize + StrictDeserialize>(input: &str) -> Result<T, String> {
+    let data = read_input(input)?;
+    if data.starts_with("-----BEGIN") {
+        T::from_ascii_armored_str(&data).map_err(|e| e.to_string())
+    } else {
+        decode_hex(&data)
+    }
+}
+
+fn print_seal(out: &mut String, seal: &TxoSealDef) {
+    writeln!(out, "Seal: {seal}").ok();
+    writeln!(out, "Outpoint: {}", seal.primary).ok();
+    writeln!(out, "Secondary: {}", seal.secondary).ok();
+    writeln!(out, "Concealed seal: {}", seal.commit_id()).ok();
+    writeln!(out).ok();
+    write!(out, "{}", seal.to_ascii_armored_string()).ok();
+}
+
+fn print_proof(out: &mut String, proof: &DbcProof) {
+    writeln!(out, "Method: {}", proof.method()).ok();
+    writeln!(out, "{proof:#?}").ok();
+    writeln!(out).ok();
+    write!(out, "{}", proof.to_ascii_armored_string()).ok();
+}
+
+fn print_anchor<D: dbc::Proof>(out: &mut String, anchor: &Anchor<D>)
+where Anchor<D>: StrictArmor + Debug {
+    writeln!(out, "Anchor id: {}", anchor.armor_id()).ok();
+    writeln!(out, "Method: {}", D::METHOD).ok();
+    writeln!(out, "Protocol id: {}", anchor.mpc_protocol).ok();
+    writeln!(out, "{anchor:#?}").ok();
+    writeln!(out).ok();
+    write!(out, "{}", anchor.to_ascii_armored_string()).ok();
+}
+
+fn run(command: Command) -> Result<String, String> {
+    let mut out = String::new();
+    match command {
+        Command::Seal { input } => print_seal(&mut out, &decode::<TxoSealDef>(&input)?),
+        Command::Proof { input } => print_proof(&mut out, &decode::<DbcProof>(&input)?),
+        Command::Anchor { input, method } => match method {
+            Method::OpretFirst => print_anchor(&mut out, &decode::<Anchor<OpretProof>>(&input)?),
+            Method::TapretFirst => print_anchor(&mut out, &decode::<Anchor<TapretProof>>(&input)?),
+        },
+        Command::Verify {
+            proof,
+            tx,
+            commitment,
+        } => {
+            let proof = decode::<DbcProof>(&proof)?;
+            let tx =
+                Tx::from_str(&read_input(&tx)?).map_err(|e| format!("invalid transaction: {e}"))?;
+            let commitment = Vec::<u8>::from_hex(&commitment)
+                .ok()
+                .and_then(|data| <[u8; 32]>::try_from(data).ok())
+                .map(mpc::Commitment::from_byte_array)
+                .ok_or("commitment must be a 32-byte hex string")?;
+            let report = proof
+                .verify_explain(&commitment, &tx)
+                .map_err(|e| format!("verification failed: {e}"))?;
+            writeln!(out, "Proof is valid").ok();
+            writeln!(out, "Witness txid: {}", report.txid).ok();
+            writeln!(out, "Method: {}", report.method).ok();
+            writeln!(out, "Host output: {} ({})", report.vout, report.script_pubkey.to_hex()).ok();
+            if let Some(tapret) = report.tapret {
+                writeln!(out, "{tapret:#?}").ok();
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse().command) {
+        Ok(out) => {
+            print!("{out}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::{Outpoint, Txid, Vout};
+    use commit_verify::Sha256;
+    use dbc::PROOF_MAX_LEN;
+    use seals::TxoSeal;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    fn hex(data: &impl StrictSerialize) -> String {
+        data.to_strict_serialized::<PROOF_MAX_LEN>().unwrap().release().to_hex()
+    }
+
+    #[test]
+    fn seal() {
+        let seal = TxoSeal::<OpretProof>::no_fallback(
+            Outpoint::new(Txid::from([1u8; 32]), Vout::from_u32(2)),
+            Sha256::default(),
+            0,
+        )
+        .to_definition();
+        let out = run(Command::Seal { input: hex(&seal) }).unwrap();
+        assert!(out.contains(&format!("Concealed seal: {}", seal.commit_id())));
+        let armored = run(Command::Seal {
+            input: seal.to_ascii_armored_string(),
+        })
+        .unwrap();
+        assert_eq!(armored, out);
+        assert!(run(Command::Seal {
+            input: "00".to_owned()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn anchor() {
+        let anchor = Anchor::<OpretProof>::strict_dumb();
+        let out = run(Command::Anchor {
+            input: hex(&anchor),
+            method: Method::OpretFirst,
+        })
+        .unwrap();
+        assert!(out.contains("Method: opret1st"));
+        assert!(out.contains(&anchor.to_ascii_armored_string()));
+        assert!(run(Command::Anchor {
+            input: hex(&anchor),
+            method: Method::TapretFirst,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn proof() {
+        let proof = DbcProof::Opret(OpretProof::default());
+        let out = run(Command::Proof { input: hex(&proof) }).unwrap();
+        assert!(out.starts_with("Method: opret1st"));
+    }
+}
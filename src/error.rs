@@ -0,0 +1,655 @@
+// Bitcoin protocol core library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Top-level error type unifying errors from all BP Core libraries.
+//!
+//! Each error has a stable numeric code, which never changes between library
+//! versions and may be used in FFI and log aggregation. The code is composed
+//! of three parts: ten thousands define the error category (see
+//! [`ErrorCategory`]), hundreds define the error type inside the category, and
+//! the last two digits define the variant of the error type (or `1` for the
+//! error types without variants). For instance, code `30102` is
+//! [`DbcError::Opret`], the second variant of the first verification error
+//! type.
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+
+use bc::{BlockDataParseError, ConsensusDecodeError, OutpointParseError};
+use dbc::armor::StrictArmorError;
+#[cfg(feature = "cbor")]
+use dbc::cbor::CborError;
+use dbc::container::ContainerError;
+#[cfg(feature = "miniscript")]
+use dbc::descriptor::DescriptorError;
+use dbc::fingerprint::FingerprintError;
+use dbc::host::HostError;
+use dbc::opret::{OpretError, OpretPayloadError, OpretVerifyError};
+#[cfg(feature = "ots")]
+use dbc::ots::OtsError;
+#[cfg(feature = "interop")]
+use dbc::psbt::interop::InteropError;
+use dbc::psbt::{PsbtDbcError, PsbtHostError, PsbtKeyError};
+use dbc::tapret::{TapretError, TapretKeyError, TapretPathError};
+use dbc::versioned::VersionError;
+use dbc::{
+    AnchorBundleError, BatchError, CommitError, DbcAnchorError, DbcAnchorMergeError, DbcError,
+    DualProofError, MethodParseError, MultiAnchorError, OwnershipError, PolicyViolation,
+    VerifyError,
+};
+#[cfg(feature = "testkit")]
+use seals::testkit::TestkitError;
+#[cfg(feature = "bitcoind")]
+use seals::BitcoindError;
+#[cfg(feature = "compat")]
+use seals::CompatError;
+#[cfg(feature = "electrum")]
+use seals::ElectrumError;
+#[cfg(feature = "esplora")]
+use seals::EsploraError;
+#[cfg(feature = "headers")]
+use seals::HeaderChainError;
+use seals::{
+    AnchorError, AnchorMergeError, ChainedSealError, FallbackDefError, FallbackSealError,
+    ResolveError, SealVerifyError, TemplateError, TxProofError, WatchError,
+};
+use strict_encoding::DeserializeError;
+
+/// Machine-readable category of an [`Error`].
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+#[repr(u16)]
+pub enum ErrorCategory {
+    /// Errors parsing string representations of data.
+    Parse = 1,
+
+    /// Errors decoding binary data.
+    Encoding = 2,
+
+    /// Failed verification of commitments, proofs and seals.
+    Verification = 3,
+
+    /// Errors retrieving data from blockchain indexers and nodes.
+    Resolver = 4,
+
+    /// Errors constructing commitments, anchors and seal definitions.
+    Construction = 5,
+}
+
+/// Error having generic type parameters, kept in a box together with its
+/// stable code.
+///
+/// The original error can be recovered with [`GenericError::downcast`].
+#[derive(Debug)]
+pub struct GenericError {
+    code: u16,
+    inner: Box<dyn StdError + Send + Sync>,
+}
+
+impl GenericError {
+    fn with(code: u16, err: impl StdError + Send + Sync + 'static) -> Self {
+        GenericError {
+            code,
+            inner: Box::new(err),
+        }
+    }
+
+    /// Returns stable numeric code of the error.
+    pub fn code(&self) -> u16 { self.code }
+
+    /// Returns reference to the original error, if it has the given type.
+    pub fn downcast_ref<T: StdError + 'static>(&self) -> Option<&T> { self.inner.downcast_ref() }
+
+    /// Recovers the original error, if it has the given type.
+    pub fn downcast<T: StdError + 'static>(self) -> Result<Box<T>, Self> {
+        let code = self.code;
+        self.inner.downcast().map_err(|inner| GenericError { code, inner })
+    }
+}
+
+impl Display for GenericError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.inner, f) }
+}
+
+impl StdError for GenericError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> { self.inner.source() }
+}
+
+/// Unified error type for all BP Core libraries.
+#[derive(Debug, Display, Error, From)]
+#[display(inner)]
+pub enum Error {
+    // Parse errors
+    /// Invalid outpoint string.
+    #[from]
+    Outpoint(OutpointParseError),
+
+    /// Invalid hex-encoded block data.
+    #[from]
+    BlockData(BlockDataParseError),
+
+    /// Invalid commitment method name.
+    #[from]
+    Method(MethodParseError),
+
+    /// Invalid OP_RETURN commitment payload.
+    #[from]
+    OpretPayload(OpretPayloadError),
+
+    /// Invalid or mismatching fingerprint.
+    #[from]
+    Fingerprint(FingerprintError),
+
+    // Encoding errors
+    /// Invalid consensus-encoded data.
+    #[from]
+    Consensus(ConsensusDecodeError),
+
+    /// Invalid binary container.
+    #[from]
+    Container(ContainerError),
+
+    /// Invalid ASCII-armored data.
+    #[from]
+    Armor(StrictArmorError),
+
+    /// Invalid versioned proof data.
+    #[from]
+    Version(VersionError),
+
+    /// Invalid strict-encoded data.
+    #[from]
+    Strict(DeserializeError),
+
+    /// Invalid CBOR data.
+    #[cfg(feature = "cbor")]
+    #[from]
+    Cbor(CborError),
+
+    /// Invalid legacy data.
+    #[cfg(feature = "compat")]
+    #[from]
+    Compat(CompatError),
+
+    /// Invalid PSBT proprietary key.
+    #[from]
+    PsbtKey(PsbtKeyError),
+
+    // Verification errors
+    /// Invalid deterministic bitcoin commitment.
+    #[from]
+    Dbc(DbcError),
+
+    /// Invalid dual commitment.
+    #[from]
+    DualProof(DualProofError),
+
+    /// Verification policy is violated.
+    #[from]
+    Policy(PolicyViolation),
+
+    /// Invalid anchor.
+    ///
+    /// Contains [`DbcAnchorError`], parameterized by the DBC proof error type.
+    DbcAnchor(GenericError),
+
+    /// Anchors can't be merged.
+    #[from]
+    DbcAnchorMerge(DbcAnchorMergeError),
+
+    /// Seal anchors can't be merged.
+    #[from]
+    SealAnchorMerge(AnchorMergeError),
+
+    /// Invalid seal anchor.
+    #[from]
+    SealAnchor(AnchorError),
+
+    /// Witness doesn't close the seals.
+    ///
+    /// Contains [`SealVerifyError`], parameterized by the DBC proof error
+    /// type.
+    Seal(GenericError),
+
+    /// Invalid transaction inclusion proof.
+    #[from]
+    TxProof(TxProofError),
+
+    /// Commitment is not owned by the party.
+    #[from]
+    Ownership(OwnershipError),
+
+    /// Invalid multi-chain anchor.
+    #[from]
+    MultiAnchor(MultiAnchorError),
+
+    /// Batch verification has failed.
+    #[from]
+    Batch(BatchError),
+
+    /// Chained seal can't be resolved.
+    #[from]
+    ChainedSeal(ChainedSealError),
+
+    /// Fallback seal can't be resolved.
+    ///
+    /// Contains [`FallbackSealError`], parameterized by the resolver error
+    /// type.
+    FallbackSeal(GenericError),
+
+    /// Invalid block header chain.
+    #[cfg(feature = "headers")]
+    #[from]
+    HeaderChain(HeaderChainError),
+
+    /// Invalid OpenTimestamps attestation.
+    #[cfg(feature = "ots")]
+    #[from]
+    Ots(OtsError),
+
+    /// Commitment is not preserved by a PSBT conversion.
+    #[cfg(feature = "interop")]
+    #[from]
+    Interop(InteropError),
+
+    /// Invalid opret commitment.
+    #[from]
+    OpretVerify(OpretVerifyError),
+
+    // Resolver errors
+    /// Unable to resolve witness data.
+    ///
+    /// Contains [`ResolveError`], parameterized by the error types of the
+    /// witness resolver and the DBC proof.
+    Resolve(GenericError),
+
+    /// Unable to watch the seals.
+    #[from]
+    Watch(WatchError),
+
+    /// Esplora request has failed.
+    #[cfg(feature = "esplora")]
+    #[from]
+    Esplora(EsploraError),
+
+    /// Electrum request has failed.
+    #[cfg(feature = "electrum")]
+    #[from]
+    Electrum(ElectrumError),
+
+    /// Bitcoin Core request has failed.
+    #[cfg(feature = "bitcoind")]
+    #[from]
+    Bitcoind(BitcoindError),
+
+    /// Regtest harness has failed.
+    #[cfg(feature = "testkit")]
+    #[from]
+    Testkit(TestkitError),
+
+    // Construction errors
+    /// Unable to commit to a transaction.
+    #[from]
+    Commit(CommitError),
+
+    /// Invalid PSBT commitment host.
+    #[from]
+    PsbtHost(PsbtHostError),
+
+    /// Unable to commit to a PSBT.
+    #[from]
+    PsbtDbc(PsbtDbcError),
+
+    /// Unable to embed opret commitment.
+    #[from]
+    Opret(OpretError),
+
+    /// Unable to embed tapret commitment.
+    #[from]
+    Tapret(TapretError),
+
+    /// Unable to embed tapret commitment into a key.
+    #[from]
+    TapretKey(TapretKeyError),
+
+    /// Invalid tapret path.
+    #[from]
+    TapretPath(TapretPathError),
+
+    /// Descriptor can't host tapret commitments.
+    #[cfg(feature = "miniscript")]
+    #[from]
+    Descriptor(DescriptorError),
+
+    /// Unable to select commitment host.
+    #[from]
+    Host(HostError),
+
+    /// Invalid anchor bundle.
+    #[from]
+    AnchorBundle(AnchorBundleError),
+
+    /// Invalid fallback seal definition.
+    #[from]
+    FallbackDef(FallbackDefError),
+
+    /// Address template can't be resolved into a seal.
+    #[from]
+    Template(TemplateError),
+}
+
+/// Errors having stable numbers of their variants.
+trait VariantCode {
+    /// Returns number of the variant, starting from 1.
+    fn variant_code(&self) -> u16;
+}
+
+macro_rules! variant_codes {
+    ($($(#[$attr:meta])* $ty:ident $(<$($gen:ident),+>)? { $($variant:ident = $code:literal),+ $(,)? })+) => {$(
+        $(#[$attr])*
+        impl$(<$($gen: StdError),+>)? VariantCode for $ty$(<$($gen),+>)? {
+            fn variant_code(&self) -> u16 {
+                match self {
+                    $($ty::$variant { .. } => $code),+
+                }
+            }
+        }
+    )+};
+}
+
+variant_codes! {
+    OutpointParseError { MalformedSeparator = 1, InvalidVout = 2, InvalidTxid = 3 }
+    BlockDataParseError { Hex = 1, Consensus = 2 }
+    OpretPayloadError { NotOpret = 1, InvalidLength = 2, InvalidData = 3 }
+    FingerprintError { InvalidLen = 1, InvalidChar = 2, Mismatch = 3 }
+
+    ConsensusDecodeError { Io = 1, Data = 2 }
+    ContainerError {
+        Io = 1, Truncated = 2, NoMagic = 3, UnsupportedVersion = 4, TypeMismatch = 5,
+        LengthMismatch = 6, ChecksumMismatch = 7, Decode = 8,
+    }
+    VersionError { Empty = 1, Unknown = 2, Rejected = 3, Decode = 4 }
+    DeserializeError { Decode = 1, DataNotEntirelyConsumed = 2 }
+    #[cfg(feature = "cbor")]
+    CborError { Decode = 1, NonCanonical = 2 }
+    #[cfg(feature = "compat")]
+    CompatError {
+        InvalidLen = 1, UnknownMethod = 2, UnknownTxPtr = 3, InvalidAnchor = 4, MethodMismatch = 5,
+    }
+    PsbtKeyError { InvalidKey = 1, InvalidValue = 2, UnsupportedKey = 3 }
+
+    DbcError { Tapret = 1, Opret = 2 }
+    DualProofError { Opret = 1, Tapret = 2, Both = 3 }
+    PolicyViolation { NonStandardScript = 1, TapretDepth = 2, NetworkMismatch = 3, NoNetwork = 4 }
+    DbcAnchorError<E> { TxidMismatch = 1, Mpc = 2, Dbc = 3 }
+    DbcAnchorMergeError { TxidMismatch = 1, DbcMismatch = 2, MpcMismatch = 3 }
+    AnchorMergeError { AnchorMismatch = 1, TooManyInputs = 2 }
+    AnchorError { Mpc = 1, Mmb = 2 }
+    SealVerifyError<E> {
+        NoSeals = 1, NotSpent = 2, NoFallback = 3, NotIncluded = 4, Anchor = 5, Dbc = 6,
+        Inclusion = 7, Policy = 8,
+    }
+    TxProofError {
+        IndexOutOfRange = 1, MerkleRootMismatch = 2, ForgeableTx = 3, NotCoinbase = 4,
+        BlockMismatch = 5, DepthMismatch = 6,
+    }
+    OwnershipError { Anchor = 1, NotBound = 2, NoInput = 3, NotOwner = 4, NoOwnedInputs = 5 }
+    MultiAnchorError {
+        NoChains = 1, MpcMismatch = 2, Duplicate = 3, TooManyChains = 4, NoTx = 5, Chain = 6,
+    }
+    ChainedSealError { ParentMismatch = 1, NotClosing = 2, NoOutput = 3 }
+    FallbackSealError<E> { AllSpent = 1, NotClosed = 2, DoubleClose = 3, Resolver = 4 }
+    #[cfg(feature = "headers")]
+    HeaderChainError { Empty = 1, InvalidTarget = 2, InsufficientWork = 3, Disconnected = 4 }
+    #[cfg(feature = "ots")]
+    OtsError {
+        Truncated = 1, UnsupportedOp = 2, TooLarge = 3, ExtraData = 4, InvalidFile = 5,
+        DigestMismatch = 6, MerkleRootMismatch = 7, Unverified = 8, Calendar = 9, Empty = 10,
+    }
+    #[cfg(feature = "interop")]
+    InteropError { OutputCountMismatch = 1, Stripped = 2, Modified = 3, Bitcoin = 4 }
+    OpretVerifyError {
+        NoOpretOutput = 1, NotFirstOpret = 2, NonCanonicalScript = 3, PayloadLengthMismatch = 4,
+        PayloadMismatch = 5,
+    }
+
+    ResolveError<E, P> { Resolver = 1, NoSeals = 2, UnknownTx = 3, Unspent = 4, Seal = 5 }
+    WatchError { UnknownPrevout = 1 }
+    #[cfg(feature = "esplora")]
+    EsploraError { Http = 1, Status = 2, InvalidTx = 3 }
+    #[cfg(feature = "electrum")]
+    ElectrumError { Client = 1, InvalidTx = 2, HistoryTooLong = 3 }
+    #[cfg(feature = "bitcoind")]
+    BitcoindError { Http = 1, Rpc = 2, InvalidResponse = 3, InvalidTx = 4 }
+    #[cfg(feature = "testkit")]
+    TestkitError {
+        Bitcoind = 1, InvalidResponse = 2, Unsigned = 3, UnknownTx = 4, TxMismatch = 5,
+        Commit = 6, Mpc = 7, Verify = 8,
+    }
+
+    CommitError { Mpc = 1, Opret = 2, Tapret = 3, TooManyOutputs = 4 }
+    PsbtHostError {
+        NoHost = 1, UnknownOutput = 2, MultipleHosts = 3, ConflictingMarks = 4,
+        NonTaprootHost = 5, NonEmptyOpret = 6, Key = 7,
+    }
+    PsbtDbcError {
+        Host = 1, AlreadyCommitted = 2, NotCommitted = 3, NoInternalKey = 4,
+        TapretUnsuitable = 5, NoTapretProof = 6, CommitmentMismatch = 7, Key = 8, Mpc = 9,
+        Opret = 10, Dbc = 11, OutputsNotModifiable = 12, NotFinalized = 13,
+    }
+    OpretError { NoOpretOutput = 1, InvalidOpretScript = 2 }
+    TapretError { KeyEmbedding = 1, NoTaprootOutput = 2 }
+    TapretKeyError { AlternativeCommitment = 1, IncorrectOrdering = 2 }
+    TapretPathError { MaxDepthExceeded = 1, InvalidNodePartner = 2 }
+    #[cfg(feature = "miniscript")]
+    DescriptorError {
+        NonTaproot = 1, Derivation = 2, InvalidScript = 3, TreeTooDeep = 4, Partner = 5,
+        NoNonce = 6,
+    }
+    HostError { NoHostOutput = 1 }
+    AnchorBundleError { TooManyEntries = 1, InvalidRef = 2, InvalidPartner = 3 }
+    FallbackDefError { Empty = 1, TooMany = 2, Duplicate = 3 }
+    TemplateError { NoMatch = 1, Ambiguous = 2 }
+}
+
+impl Error {
+    /// Returns stable numeric code of the error.
+    pub fn code(&self) -> u16 {
+        match self {
+            Error::Outpoint(err) => 10100 + err.variant_code(),
+            Error::BlockData(err) => 10200 + err.variant_code(),
+            Error::Method(_) => 10301,
+            Error::OpretPayload(err) => 10400 + err.variant_code(),
+            Error::Fingerprint(err) => 10500 + err.variant_code(),
+
+            Error::Consensus(err) => 20100 + err.variant_code(),
+            Error::Container(err) => 20200 + err.variant_code(),
+            Error::Armor(_) => 20301,
+            Error::Version(err) => 20400 + err.variant_code(),
+            Error::Strict(err) => 20500 + err.variant_code(),
+            #[cfg(feature = "cbor")]
+            Error::Cbor(err) => 20600 + err.variant_code(),
+            #[cfg(feature = "compat")]
+            Error::Compat(err) => 20700 + err.variant_code(),
+            Error::PsbtKey(err) => 20800 + err.variant_code(),
+
+            Error::Dbc(err) => 30100 + err.variant_code(),
+            Error::DualProof(err) => 30200 + err.variant_code(),
+            Error::Policy(err) => 30300 + err.variant_code(),
+            Error::DbcAnchor(err) => err.code(),
+            Error::DbcAnchorMerge(err) => 30500 + err.variant_code(),
+            Error::SealAnchorMerge(err) => 30600 + err.variant_code(),
+            Error::SealAnchor(err) => 30700 + err.variant_code(),
+            Error::Seal(err) => err.code(),
+            Error::TxProof(err) => 30900 + err.variant_code(),
+            Error::Ownership(err) => 31000 + err.variant_code(),
+            Error::MultiAnchor(err) => 31100 + err.variant_code(),
+            Error::Batch(_) => 31201,
+            Error::ChainedSeal(err) => 31300 + err.variant_code(),
+            Error::FallbackSeal(err) => err.code(),
+            #[cfg(feature = "headers")]
+            Error::HeaderChain(err) => 31500 + err.variant_code(),
+            #[cfg(feature = "ots")]
+            Error::Ots(err) => 31600 + err.variant_code(),
+            #[cfg(feature = "interop")]
+            Error::Interop(err) => 31700 + err.variant_code(),
+            Error::OpretVerify(err) => 31800 + err.variant_code(),
+
+            Error::Resolve(err) => err.code(),
+            Error::Watch(err) => 40200 + err.variant_code(),
+            #[cfg(feature = "esplora")]
+            Error::Esplora(err) => 40300 + err.variant_code(),
+            #[cfg(feature = "electrum")]
+            Error::Electrum(err) => 40400 + err.variant_code(),
+            #[cfg(feature = "bitcoind")]
+            Error::Bitcoind(err) => 40500 + err.variant_code(),
+            #[cfg(feature = "testkit")]
+            Error::Testkit(err) => 40600 + err.variant_code(),
+
+            Error::Commit(err) => 50100 + err.variant_code(),
+            Error::PsbtHost(err) => 50200 + err.variant_code(),
+            Error::PsbtDbc(err) => 50300 + err.variant_code(),
+            Error::Opret(err) => 50400 + err.variant_code(),
+            Error::Tapret(err) => 50500 + err.variant_code(),
+            Error::TapretKey(err) => 50600 + err.variant_code(),
+            Error::TapretPath(err) => 50700 + err.variant_code(),
+            #[cfg(feature = "miniscript")]
+            Error::Descriptor(err) => 50800 + err.variant_code(),
+            Error::Host(err) => 50900 + err.variant_code(),
+            Error::AnchorBundle(err) => 51000 + err.variant_code(),
+            Error::FallbackDef(err) => 51100 + err.variant_code(),
+            Error::Template(err) => 51200 + err.variant_code(),
+        }
+    }
+
+    /// Returns category of the error.
+    pub fn category(&self) -> ErrorCategory {
+        match self.code() / 10000 {
+            1 => ErrorCategory::Parse,
+            2 => ErrorCategory::Encoding,
+            3 => ErrorCategory::Verification,
+            4 => ErrorCategory::Resolver,
+            5 => ErrorCategory::Construction,
+            _ => unreachable!("error code outside of the known categories"),
+        }
+    }
+}
+
+impl From<VerifyError<DbcError>> for Error {
+    fn from(err: VerifyError<DbcError>) -> Self {
+        match err {
+            VerifyError::Policy(violation) => Error::Policy(violation),
+            VerifyError::Proof(err) => Error::Dbc(err),
+        }
+    }
+}
+
+impl<E: StdError + Send + Sync + 'static> From<DbcAnchorError<E>> for Error {
+    fn from(err: DbcAnchorError<E>) -> Self {
+        Error::DbcAnchor(GenericError::with(30400 + err.variant_code(), err))
+    }
+}
+
+impl<E: StdError + Send + Sync + 'static> From<SealVerifyError<E>> for Error {
+    fn from(err: SealVerifyError<E>) -> Self {
+        Error::Seal(GenericError::with(30800 + err.variant_code(), err))
+    }
+}
+
+impl<E: StdError + Send + Sync + 'static> From<FallbackSealError<E>> for Error {
+    fn from(err: FallbackSealError<E>) -> Self {
+        Error::FallbackSeal(GenericError::with(31400 + err.variant_code(), err))
+    }
+}
+
+impl<E: StdError + Send + Sync + 'static, P: StdError + Send + Sync + 'static>
+    From<ResolveError<E, P>> for Error
+{
+    fn from(err: ResolveError<E, P>) -> Self {
+        match err {
+            ResolveError::Seal(err) => err.into(),
+            err => Error::Resolve(GenericError::with(40100 + err.variant_code(), err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bc::Outpoint;
+
+    use super::*;
+
+    #[test]
+    fn codes() {
+        let err = Error::from(OpretPayloadError::NotOpret);
+        assert_eq!(err.code(), 10401);
+        assert_eq!(err.category(), ErrorCategory::Parse);
+
+        let err = Error::from(VerifyError::Proof(DbcError::Opret(OpretVerifyError::NoOpretOutput)));
+        assert_eq!(err.code(), 30102);
+        assert_eq!(err.category(), ErrorCategory::Verification);
+
+        let err = Error::from(ResolveError::<WatchError, DbcError>::Unspent(Outpoint::coinbase()));
+        assert_eq!(err.code(), 40104);
+        assert_eq!(err.category(), ErrorCategory::Resolver);
+        let Error::Resolve(err) = err else {
+            panic!("resolver error expected")
+        };
+        assert!(matches!(
+            *err.downcast::<ResolveError<WatchError, DbcError>>().unwrap(),
+            ResolveError::Unspent(_)
+        ));
+
+        let err = Error::from(ResolveError::<WatchError, DbcError>::Seal(SealVerifyError::NoSeals));
+        assert_eq!(err.code(), 30801);
+        let Error::Seal(err) = err else {
+            panic!("seal error expected")
+        };
+        assert!(err.downcast_ref::<DbcError>().is_none());
+        assert!(matches!(
+            *err.downcast::<SealVerifyError<DbcError>>().unwrap(),
+            SealVerifyError::NoSeals
+        ));
+
+        let err = Error::from(CommitError::Opret(OpretError::NoOpretOutput));
+        assert_eq!(err.code(), 50102);
+        assert_eq!(err.category(), ErrorCategory::Construction);
+    }
+
+    #[test]
+    fn distinct() {
+        let errors = [
+            Error::from(PsbtDbcError::NotFinalized),
+            Error::from(PsbtDbcError::OutputsNotModifiable),
+            Error::from(PsbtHostError::NoHost),
+            Error::from(TapretPathError::MaxDepthExceeded),
+            Error::from(AnchorBundleError::TooManyEntries),
+            Error::from(FallbackDefError::Empty),
+            Error::from(FallbackSealError::<WatchError>::AllSpent),
+            Error::from(OwnershipError::NotBound),
+            Error::from(OwnershipError::NoOwnedInputs),
+            Error::from(OpretError::NoOpretOutput),
+            Error::from(OpretVerifyError::NoOpretOutput),
+        ];
+        let codes = errors.iter().map(Error::code).collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(codes.len(), errors.len());
+    }
+}
@@ -47,7 +47,6 @@ pub extern crate dbc;
 /// Re-export of `bp-seals` crate.
 pub extern crate seals;
 
-#[cfg(feature = "stl")]
 #[macro_use]
 extern crate amplify;
 #[macro_use]
@@ -59,6 +58,8 @@ extern crate serde;
 #[cfg(feature = "stl")]
 pub mod stl;
 mod bp;
+mod error;
+pub mod prelude;
 
 pub use ::bc::*;
 #[cfg(feature = "stl")]
@@ -67,3 +68,4 @@ pub mod bc {
     pub use bc::stl;
 }
 pub use bp::Bp;
+pub use error::{Error, ErrorCategory};
@@ -0,0 +1,47 @@
+// Bitcoin protocol core library.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2024 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Re-exports of the most commonly used traits and types from all BP Core
+//! libraries, allowing to work with seals and deterministic bitcoin
+//! commitments using a single import:
+//!
+//! ```
+//! use bp::prelude::*;
+//! ```
+
+pub use bc::{ConsensusDecode, ConsensusEncode, Outpoint, Tx, Txid, Vout};
+pub use commit_verify::{
+    mpc, CommitId, CommitVerify, ConvolveCommit, ConvolveCommitProof, EmbedCommitProof,
+    EmbedCommitVerify, TryCommitVerify,
+};
+pub use dbc::armor::{AsciiArmor, StrictArmor};
+pub use dbc::container::Containerized;
+pub use dbc::opret::OpretProof;
+pub use dbc::tapret::TapretProof;
+pub use dbc::{DbcProof, Method, NetworkBound, NetworkMarker, Proof, StrictBytes, VerifyOpts};
+pub use seals::{
+    mmb, Anchor, ResolveWitness, TxoSeal, TxoSealDef, TxoSealExt, TxoWitness, WitnessOrd,
+    WitnessPos,
+};
+pub use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
+pub use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+pub use crate::{Error, ErrorCategory};
@@ -23,7 +23,7 @@
 
 use dbc::opret::OpretProof;
 use dbc::tapret::TapretProof;
-use dbc::LIB_NAME_BPCORE;
+use dbc::{DbcProof, LIB_NAME_BPCORE};
 use strict_types::{CompileError, LibBuilder, TypeLib};
 
 /// Strict types id for the library providing data types from [`dbc`] and
@@ -42,6 +42,20 @@ fn _bp_core_stl() -> Result<TypeLib, CompileError> {
     .transpile::<seals::Anchor<OpretProof>>()
     .transpile::<seals::Anchor<TapretProof>>()
     .transpile::<seals::mpc::Source>()
+    .transpile::<DbcProof>()
+    .transpile::<dbc::DbcAnchor<DbcProof>>()
+    .transpile::<dbc::versioned::VersionedProof>()
+    .transpile::<seals::TxoWitness<OpretProof>>()
+    .transpile::<seals::TxoWitness<TapretProof>>()
+    .transpile::<seals::WitnessPos>()
+    .transpile::<seals::WitnessOrd>()
+    .transpile::<seals::TxProof>()
+    .transpile::<seals::SealTracker>()
+    .transpile::<seals::FallbackSeal>()
+    .transpile::<seals::ChainedSeal>()
+    .transpile::<seals::TemplateSeal>()
+    .transpile::<seals::MinedWitness>()
+    .transpile::<seals::InternedSeals>()
     .compile()
 }
 
@@ -49,6 +63,52 @@ fn _bp_core_stl() -> Result<TypeLib, CompileError> {
 /// [`seals`] crates.
 pub fn bp_core_stl() -> TypeLib { _bp_core_stl().expect("invalid strict type BPCore library") }
 
+/// Error of the strict type library integrity check.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum IntegrityError {
+    /// strict type library {name} has id {actual} instead of {expected}, meaning
+    /// that the strict encoding of its data types has changed.
+    IdMismatch {
+        /// Name of the library.
+        name: &'static str,
+        /// Library id this version of the crate was released with.
+        expected: &'static str,
+        /// Id of the library compiled from the actual data types.
+        actual: String,
+    },
+}
+
+/// Checks that the strict type libraries compiled from the actual data types
+/// match the library ids this version of the crate was released with.
+///
+/// Allows consensus-critical software to detect at startup any change in the
+/// encoding of seals, proofs and anchors, which may be accidentally
+/// introduced by a dependency update.
+///
+/// # Panics
+///
+/// If any of the libraries can't be compiled, which indicates a bug in the
+/// library definition.
+pub fn check_integrity() -> Result<(), IntegrityError> {
+    let libs = [
+        ("Tx", bc::stl::bp_tx_stl(), bc::stl::LIB_ID_BP_TX),
+        ("Bitcoin", bc::stl::bp_consensus_stl(), bc::stl::LIB_ID_BP_CONSENSUS),
+        (LIB_NAME_BPCORE, bp_core_stl(), LIB_ID_BPCORE),
+    ];
+    for (name, lib, expected) in libs {
+        let actual = lib.id().to_string();
+        if actual != expected {
+            return Err(IntegrityError::IdMismatch {
+                name,
+                expected,
+                actual,
+            });
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -58,4 +118,9 @@ mod test {
         let lib = bp_core_stl();
         assert_eq!(lib.id().to_string(), LIB_ID_BPCORE);
     }
+
+    #[test]
+    fn integrity() {
+        assert_eq!(check_integrity(), Ok(()));
+    }
 }